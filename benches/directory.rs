@@ -0,0 +1,143 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use iso9660_simple::helpers::get_directory_entry_by_path;
+use iso9660_simple::ISO9660;
+use std::hint::black_box;
+
+const SECTOR: usize = 2048;
+const ROOT_LBA: usize = 18;
+
+fn both_endian_u32(value: u32) -> [u8; 8] {
+    let mut out = [0; 8];
+    out[..4].copy_from_slice(&value.to_le_bytes());
+    out[4..].copy_from_slice(&value.to_be_bytes());
+    out
+}
+
+fn record(lba: u32, size: u32, flags: u8, identifier: &[u8], system_use: &[u8]) -> Vec<u8> {
+    let mut out = vec![0u8; 33];
+
+    out[2..10].copy_from_slice(&both_endian_u32(lba));
+    out[10..18].copy_from_slice(&both_endian_u32(size));
+    out[25] = flags;
+    out[28] = 1;
+    out[31] = 1;
+    out[32] = identifier.len() as u8;
+    out.extend_from_slice(identifier);
+
+    if identifier.len().is_multiple_of(2) {
+        out.push(0);
+    }
+
+    out.extend_from_slice(system_use);
+
+    if !out.len().is_multiple_of(2) {
+        out.push(0);
+    }
+
+    out[0] = out.len() as u8;
+    out
+}
+
+/// Places records one after another so that none of them crosses a sector boundary.
+fn lay_out(records: &[Vec<u8>], dir_size: u32) -> Vec<u8> {
+    let mut directory = vec![];
+
+    let dot = record(ROOT_LBA as u32, dir_size, 2, &[0], &[]);
+    let dotdot = record(ROOT_LBA as u32, dir_size, 2, &[1], &[]);
+
+    for rec in [&dot, &dotdot].into_iter().chain(records) {
+        let used = directory.len() % SECTOR;
+
+        if used + rec.len() > SECTOR {
+            directory.resize(directory.len() + SECTOR - used, 0);
+        }
+
+        directory.extend_from_slice(rec);
+    }
+
+    directory
+}
+
+/// Builds a plain ISO9660 image whose root directory holds `count` empty files.
+fn build_image(count: usize, rock_ridge: bool) -> Vec<u8> {
+    let mut records = vec![];
+
+    for i in 0..count {
+        let identifier = format!("FILE{i:05}.TXT;1");
+        let mut system_use = vec![];
+
+        if rock_ridge {
+            let name = format!("file_{i}.txt");
+            system_use.extend_from_slice(b"NM");
+            system_use.push(5 + name.len() as u8);
+            system_use.push(1);
+            system_use.push(0);
+            system_use.extend_from_slice(name.as_bytes());
+        }
+
+        records.push(record(0, 0, 0, identifier.as_bytes(), &system_use));
+    }
+
+    // Size of "." must be known before the directory is laid out, so lay it out twice.
+    let size = lay_out(&records, 0).len().div_ceil(SECTOR) * SECTOR;
+    let mut directory = lay_out(&records, size as u32);
+
+    directory.resize(size, 0);
+
+    let mut image = vec![0u8; ROOT_LBA * SECTOR];
+
+    let pvd = &mut image[16 * SECTOR..17 * SECTOR];
+    pvd[0] = 1;
+    pvd[1..6].copy_from_slice(b"CD001");
+    pvd[6] = 1;
+    pvd[156..190].copy_from_slice(&record(ROOT_LBA as u32, size as u32, 2, &[0], &[]));
+
+    let terminator = &mut image[17 * SECTOR..18 * SECTOR];
+    terminator[0] = 0xff;
+    terminator[1..6].copy_from_slice(b"CD001");
+    terminator[6] = 1;
+
+    image.extend_from_slice(&directory);
+    image
+}
+
+fn iterate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("read_root");
+
+    for count in [1_000, 10_000] {
+        for rock_ridge in [false, true] {
+            let label = if rock_ridge { "rock_ridge" } else { "plain" };
+            let iso = ISO9660::from_device(build_image(count, rock_ridge)).unwrap();
+
+            group.bench_with_input(BenchmarkId::new(format!("entries/{label}"), count), &count, |b, _| {
+                b.iter(|| black_box(iso.read_root().count()))
+            });
+
+            group.bench_with_input(BenchmarkId::new(format!("raw/{label}"), count), &count, |b, _| {
+                b.iter(|| {
+                    let mut entries = iso.read_root();
+                    let mut total = 0;
+
+                    while let Some(raw) = entries.next_raw() {
+                        total += raw.identifier.len();
+                    }
+
+                    black_box(total)
+                })
+            });
+        }
+    }
+
+    group.finish();
+}
+
+fn lookup(c: &mut Criterion) {
+    let iso = ISO9660::from_device(build_image(10_000, true)).unwrap();
+
+    c.bench_function("lookup/last_of_10000", |b| {
+        b.iter(|| black_box(get_directory_entry_by_path(&iso, "/file_9999.txt").unwrap()))
+    });
+}
+
+criterion_group!(benches, iterate, lookup);
+criterion_main!(benches);
@@ -1,25 +1,6 @@
 use iso9660_simple::helpers::get_directory_entry_by_path;
-use iso9660_simple::{Read as ISORead, ISO9660};
-use std::{
-    fs::File,
-    io::{Read, Seek, SeekFrom, Write},
-};
-
-struct FileDevice(File);
-
-impl ISORead for FileDevice {
-    fn read(&mut self, position: usize, buffer: &mut [u8]) -> Option<()> {
-        if self.0.seek(SeekFrom::Start(position as u64)).is_err() {
-            return None;
-        }
-
-        if self.0.read_exact(buffer).is_ok() {
-            Some(())
-        } else {
-            None
-        }
-    }
-}
+use iso9660_simple::ISO9660;
+use std::{fs::File, io::Write};
 
 fn main() {
     let mut args = std::env::args();
@@ -37,7 +18,7 @@ fn main() {
         std::process::exit(1);
     });
 
-    let mut iso = match ISO9660::from_device(FileDevice(file)) {
+    let iso = match ISO9660::from_device(file) {
         Some(iso) => iso,
         None => {
             eprintln!("It's not an ISO9660 (*.iso) file!");
@@ -46,7 +27,7 @@ fn main() {
     };
 
     for path_in_iso in args {
-        let entry = match get_directory_entry_by_path(&mut iso, &path_in_iso) {
+        let entry = match get_directory_entry_by_path(&iso, &path_in_iso) {
             Some(e) => e,
             None => {
                 eprintln!("Path '{}' not found in ISO.", path_in_iso);
@@ -59,8 +40,8 @@ fn main() {
             std::process::exit(1);
         }
 
-        let file_size = entry.file_size() as usize;
-        let mut offset: usize = 0;
+        let file_size = entry.file_size();
+        let mut offset: u64 = 0;
         let mut stdout = std::io::stdout();
 
         // Read in chunks and write to stdout
@@ -68,7 +49,7 @@ fn main() {
 
         while offset < file_size {
             let remaining = file_size - offset;
-            let to_read = core::cmp::min(remaining, buffer.len());
+            let to_read = core::cmp::min(remaining, buffer.len() as u64) as usize;
             let buf_slice = &mut buffer[..to_read];
 
             if iso.read_file(&entry, offset, buf_slice).is_none() {
@@ -81,7 +62,7 @@ fn main() {
                 std::process::exit(1);
             }
 
-            offset += to_read;
+            offset += to_read as u64;
         }
     }
 }
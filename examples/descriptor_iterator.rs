@@ -1,24 +1,5 @@
-use iso9660_simple::{Read as ISORead, ISO9660};
-use std::{
-    fs::File,
-    io::{Read, Seek, SeekFrom},
-};
-
-struct FileDevice(File);
-
-impl ISORead for FileDevice {
-    fn read(&mut self, position: usize, buffer: &mut [u8]) -> Option<()> {
-        if self.0.seek(SeekFrom::Start(position as u64)).is_err() {
-            return None;
-        }
-
-        if self.0.read_exact(buffer).is_ok() {
-            Some(())
-        } else {
-            None
-        }
-    }
-}
+use iso9660_simple::ISO9660;
+use std::fs::File;
 
 fn main() {
     // Get last argument in command line
@@ -33,7 +14,7 @@ fn main() {
     let filename = args.nth(args.len() - 1).unwrap();
 
     let file = File::open(filename).unwrap();
-    let mut iso = match ISO9660::from_device(FileDevice(file)) {
+    let iso = match ISO9660::from_device(file) {
         Some(iso) => iso,
         None => {
             eprintln!("It's not an ISO9660 (*.iso) file!");
@@ -0,0 +1,36 @@
+use iso9660_simple::ISO9660;
+use sha2::Sha256;
+use std::fs::File;
+
+fn main() {
+    let mut args = std::env::args();
+
+    if args.len() < 2 {
+        println!("Usage: {} <iso-file>", args.next().unwrap());
+        std::process::exit(1);
+    }
+
+    let iso_filename = args.nth(1).unwrap();
+
+    let file = File::open(&iso_filename).unwrap_or_else(|e| {
+        eprintln!("Failed to open ISO file '{}': {}", iso_filename, e);
+        std::process::exit(1);
+    });
+
+    let iso = ISO9660::from_device(file).unwrap_or_else(|| {
+        eprintln!("It's not an ISO9660 (*.iso) file!");
+        std::process::exit(1);
+    });
+
+    // Same output as `sha256sum`
+    for file in iso.hash_files::<Sha256>() {
+        match file.hash {
+            Some(hash) => {
+                let hex: String = hash.iter().map(|byte| format!("{byte:02x}")).collect();
+
+                println!("{hex}  {}", file.path);
+            }
+            None => eprintln!("Failed to read {}", file.path),
+        }
+    }
+}
@@ -0,0 +1,68 @@
+//! `extract` command: writes files to disk with their Rock Ridge metadata.
+
+use iso9660_simple::extract::{CollisionPolicy, SkipReason, SymlinkPolicy};
+
+use crate::{open, usage_error};
+
+pub(crate) fn run(args: &[String]) -> Result<(), String> {
+    let mut symlinks = SymlinkPolicy::RefuseEscaping;
+    let mut collisions = CollisionPolicy::Skip;
+    let mut positional = Vec::new();
+
+    for arg in args {
+        match arg.as_str() {
+            "--rewrite-links" => symlinks = SymlinkPolicy::Rewrite,
+            "--no-links" => symlinks = SymlinkPolicy::Skip,
+            "--rename" => collisions = CollisionPolicy::Rename,
+            flag if flag.starts_with("--") => return Err(format!("unknown option '{flag}'")),
+            _ => positional.push(arg),
+        }
+    }
+
+    let [image, destination, paths @ ..] = positional.as_slice() else {
+        return Err(usage_error());
+    };
+
+    let iso = open(image)?;
+    let mut extractor = iso.extract(destination).symlinks(symlinks).collisions(collisions);
+
+    for path in paths {
+        extractor = extractor.path(path);
+    }
+
+    #[cfg(feature = "parallel")]
+    let report = extractor.run_parallel();
+    #[cfg(not(feature = "parallel"))]
+    let report = extractor.run();
+
+    let report = report.map_err(|e| format!("{destination}: {e}"))?;
+
+    for (path, output) in &report.renamed {
+        eprintln!("iso9660: {path}: extracted as {}", output.display());
+    }
+
+    for (path, target) in &report.rewritten_links {
+        eprintln!("iso9660: {path}: link target rewritten to {target}");
+    }
+
+    for skipped in &report.skipped {
+        let reason = match &skipped.reason {
+            SkipReason::NotFound => String::from("no such file or directory"),
+            SkipReason::UnsafeName => String::from("unsafe name"),
+            SkipReason::ParentSkipped => String::from("directory was skipped"),
+            SkipReason::Collision => String::from("name is already taken"),
+            SkipReason::EscapingSymlink => String::from("link points outside the destination"),
+            SkipReason::Symlink => String::from("links are not extracted"),
+            SkipReason::Read => String::from("failed to read the image"),
+            SkipReason::Io(error) => error.to_string(),
+        };
+
+        eprintln!("iso9660: {}: skipped, {reason}", skipped.path);
+    }
+
+    if !report.skipped.is_empty() {
+        return Err(format!("{} entries were skipped", report.skipped.len()));
+    }
+
+    Ok(())
+}
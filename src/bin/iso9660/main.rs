@@ -0,0 +1,566 @@
+//! `iso9660` command line tool.
+
+mod extract;
+
+use std::{
+    fs::File,
+    io::{self, Write},
+    process::ExitCode,
+};
+
+use iso9660_simple::{
+    cso::CompressedImage,
+    descriptors::{Descriptor, DescriptorType, PrimarySupplementaryVolumeDescriptor},
+    disc::DiscImage,
+    eltorito::{BootEntry, Emulation},
+    helpers::get_directory_entry_by_path_with,
+    hfs::HFS,
+    normalize::NameOptions,
+    split::SplitImage,
+    udf::UDF,
+    writer::ImageWriter,
+    xar::ExtendedAttributeRecord,
+    ISODirectoryEntry, Read, ISO9660,
+};
+
+const USAGE: &str = "\
+Usage: iso9660 <command> <image> [arguments]
+
+Images can also be CSO/ZSO files, the first part of a split image (image.iso.001),
+Nero (.nrg), Alcohol 120% (.mds/.mdf) or CloneCD (.ccd/.img) images.
+
+Commands:
+    ls [-R] <image> [path]                  List a directory in long format
+    tree <image> [path]                     Print the directory tree
+    cat <image> <path>...                   Write files to the standard output
+    info <image>                            Show the volume descriptors
+    boot <image> [--extract <n> <output>]   Show the El Torito boot catalog or save a boot image
+    tar <image> [path]...                   Write the whole image or the given paths as a tar stream
+    from-tar <archive> <image> [label]      Build an image from a tar archive, '-' reads the standard input
+    extract [options] <image> <destination> [path]...
+                                            Extract the whole image or the given paths
+
+Extract options:
+    --rewrite-links   Make absolute link targets relative to the destination
+    --no-links        Don't create symbolic links
+    --rename          Extract clashing names as name~N instead of skipping them";
+
+/// Size of chunks used to copy file data
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+pub(crate) type Image = ISO9660<Box<dyn Read + Send + Sync>>;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let Some((command, args)) = args.split_first() else {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    };
+
+    let result = match command.as_str() {
+        "ls" => ls(args),
+        "tree" => tree(args),
+        "cat" => cat(args),
+        "info" => info(args),
+        "boot" => boot(args),
+        "extract" => extract::run(args),
+        "tar" => tar(args),
+        "from-tar" => from_tar(args),
+        "-h" | "--help" | "help" => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        other => Err(format!("unknown command '{other}'\n\n{USAGE}")),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("iso9660: {message}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+pub(crate) fn open(path: &str) -> Result<Image, String> {
+    let error = |e: io::Error| format!("{path}: {e}");
+    let file = File::open(path).map_err(error)?;
+
+    let mut magic = [0u8; 4];
+    let is_compressed = file.read(0, &mut magic).is_some() && (&magic == b"CISO" || &magic == b"ZISO");
+    let is_split = path
+        .rsplit_once('.')
+        .is_some_and(|(_, number)| !number.is_empty() && number.bytes().all(|byte| byte.is_ascii_digit()));
+
+    let device: Box<dyn Read + Send + Sync> = if is_disc_image(path) {
+        Box::new(DiscImage::open(path).map_err(error)?)
+    } else if is_compressed {
+        Box::new(CompressedImage::new(file).ok_or_else(|| format!("{path}: damaged CSO/ZSO header"))?)
+    } else if is_split {
+        Box::new(SplitImage::open(path).map_err(error)?)
+    } else {
+        Box::new(file)
+    };
+
+    ISO9660::from_device(device).ok_or_else(|| format!("{path}: not an ISO9660 image"))
+}
+
+/// Whether the image is a track-based container rather than a sector dump
+fn is_disc_image(path: &str) -> bool {
+    let extension = std::path::Path::new(path).extension().and_then(|extension| extension.to_str());
+
+    extension.is_some_and(|extension| {
+        ["nrg", "mds", "mdf", "ccd", "img"].iter().any(|known| extension.eq_ignore_ascii_case(known))
+    })
+}
+
+/// Finds an entry, ignoring version suffixes and case like operating system drivers do.
+fn lookup(iso: &Image, path: &str) -> Result<ISODirectoryEntry, String> {
+    get_directory_entry_by_path_with(iso, path, &NameOptions::LENIENT)
+        .ok_or_else(|| format!("{path}: no such file or directory"))
+}
+
+fn usage_error() -> String {
+    format!("wrong arguments\n\n{USAGE}")
+}
+
+fn ls(args: &[String]) -> Result<(), String> {
+    let recursive = args.first().is_some_and(|arg| arg == "-R");
+    let args = if recursive { &args[1..] } else { args };
+
+    let (image, path) = match args {
+        [image] => (image, "/"),
+        [image, path] => (image, path.as_str()),
+        _ => return Err(usage_error()),
+    };
+
+    let iso = open(image)?;
+    let entry = lookup(&iso, path)?;
+
+    if entry.is_file() {
+        println!("{}", long_format(&entry, path, entry.symlink_target().as_deref()));
+        return Ok(());
+    }
+
+    let mut walk = iso.walk_from(&entry, path);
+
+    if !recursive {
+        walk = walk.max_depth(1);
+    }
+
+    for item in walk {
+        let name = if recursive { item.path.as_str() } else { item.name() };
+
+        println!("{}", long_format(&item.entry, name, item.symlink_target.as_deref()));
+    }
+
+    Ok(())
+}
+
+fn long_format(entry: &ISODirectoryEntry, name: &str, target: Option<&str>) -> String {
+    let attributes = entry.posix_attributes();
+    let protection = protection(entry);
+    let (links, uid, gid) = match (attributes, protection) {
+        (Some(a), _) => (a.links, a.uid, a.gid),
+        (None, Some(xar)) => (1, xar.owner_id() as u32, xar.group_id() as u32),
+        (None, None) => (1, 0, 0),
+    };
+
+    let mut line = format!(
+        "{} {links:>3} {uid:>5} {gid:>5} {:>12} {} {name}",
+        mode_string(file_mode(entry, target.is_some())),
+        entry.file_size(),
+        format_time(entry.modified()),
+    );
+
+    if let Some(target) = target {
+        line.push_str(" -> ");
+        line.push_str(target);
+    }
+
+    line
+}
+
+/// Extended attributes of an entry that gives owner, group and permissions in them
+fn protection(entry: &ISODirectoryEntry) -> Option<&ExtendedAttributeRecord> {
+    entry.extended_attributes.as_ref().filter(|_| entry.has_protection()).map(|xar| &xar.record)
+}
+
+/// Rock Ridge mode, the extended attribute permissions, or a read-only mode for images without either.
+fn file_mode(entry: &ISODirectoryEntry, symlink: bool) -> u32 {
+    match entry.posix_attributes() {
+        Some(attributes) => attributes.mode,
+        None if symlink => 0o120777,
+        None => match protection(entry) {
+            Some(xar) if entry.is_folder() => 0o040000 | xar.mode(),
+            Some(xar) => 0o100000 | xar.mode(),
+            None if entry.is_folder() => 0o040555,
+            None => 0o100444,
+        },
+    }
+}
+
+fn mode_string(mode: u32) -> String {
+    let kind = match mode & 0o170000 {
+        0o040000 => 'd',
+        0o120000 => 'l',
+        0o020000 => 'c',
+        0o060000 => 'b',
+        0o010000 => 'p',
+        0o140000 => 's',
+        _ => '-',
+    };
+
+    let mut result = String::from(kind);
+
+    for shift in [6, 3, 0] {
+        let bits = (mode >> shift) & 0o7;
+
+        result.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+        result.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+        result.push(if bits & 0o1 != 0 { 'x' } else { '-' });
+    }
+
+    result
+}
+
+/// Formats seconds since the Unix epoch as `YYYY-MM-DD HH:MM` (UTC).
+fn format_time(time: i64) -> String {
+    // Civil from days, see http://howardhinnant.github.io/date_algorithms.html
+    let days = time.div_euclid(86400) + 719468;
+    let seconds = time.rem_euclid(86400);
+
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!("{year:04}-{month:02}-{day:02} {:02}:{:02}", seconds / 3600, seconds % 3600 / 60)
+}
+
+fn tree(args: &[String]) -> Result<(), String> {
+    let (image, path) = match args {
+        [image] => (image, "/"),
+        [image, path] => (image, path.as_str()),
+        _ => return Err(usage_error()),
+    };
+
+    let iso = open(image)?;
+    let entry = lookup(&iso, path)?;
+
+    println!("{path}");
+
+    if entry.is_folder() {
+        print_tree(&iso, &entry, "", &mut vec![entry.lsb_position()]);
+    }
+
+    Ok(())
+}
+
+/// Prints the children of `directory`, `ancestors` guards against directory loops.
+fn print_tree(iso: &Image, directory: &ISODirectoryEntry, prefix: &str, ancestors: &mut Vec<u32>) {
+    let mut children = Vec::new();
+    let mut entries = iso.read_directory_record(&directory.record);
+
+    while let Some(raw) = entries.next_raw() {
+        if matches!(raw.identifier, [0] | [1]) {
+            continue;
+        }
+
+        children.push((raw.to_entry(), raw.symlink_target()));
+    }
+
+    let count = children.len();
+
+    for (index, (child, target)) in children.into_iter().enumerate() {
+        let last = index + 1 == count;
+        let branch = if last { "└── " } else { "├── " };
+
+        match &target {
+            Some(target) => println!("{prefix}{branch}{} -> {target}", child.name),
+            None => println!("{prefix}{branch}{}", child.name),
+        }
+
+        if child.is_folder() && !ancestors.contains(&child.lsb_position()) {
+            let prefix = format!("{prefix}{}", if last { "    " } else { "│   " });
+
+            ancestors.push(child.lsb_position());
+            print_tree(iso, &child, &prefix, ancestors);
+            ancestors.pop();
+        }
+    }
+}
+
+fn cat(args: &[String]) -> Result<(), String> {
+    let [image, paths @ ..] = args else {
+        return Err(usage_error());
+    };
+
+    if paths.is_empty() {
+        return Err(usage_error());
+    }
+
+    let iso = open(image)?;
+    let mut stdout = io::stdout().lock();
+
+    for path in paths {
+        let entry = lookup(&iso, path)?;
+
+        if entry.is_folder() {
+            return Err(format!("{path}: is a directory"));
+        }
+
+        copy(&iso, &entry, &mut stdout).map_err(|e| format!("{path}: {e}"))?;
+    }
+
+    Ok(())
+}
+
+/// Copies the data of a file into `output` chunk by chunk.
+fn copy(iso: &Image, entry: &ISODirectoryEntry, output: &mut impl Write) -> io::Result<()> {
+    let size = entry.file_size();
+    let mut buffer = vec![0u8; CHUNK_SIZE.min(size as usize)];
+    let mut offset = 0;
+
+    while offset < size {
+        let length = (size - offset).min(buffer.len() as u64) as usize;
+        let chunk = &mut buffer[..length];
+
+        iso.read_file(entry, offset, chunk)
+            .ok_or_else(|| io::Error::other(format!("failed to read data at offset {offset}")))?;
+        output.write_all(chunk)?;
+
+        offset += length as u64;
+    }
+
+    Ok(())
+}
+
+fn tar(args: &[String]) -> Result<(), String> {
+    let [image, paths @ ..] = args else {
+        return Err(usage_error());
+    };
+
+    let iso = open(image)?;
+    let mut exporter = iso.tar();
+
+    for path in paths {
+        exporter = exporter.path(path);
+    }
+
+    let mut stdout = io::BufWriter::new(io::stdout().lock());
+
+    exporter.write_to(&mut stdout).and_then(|_| stdout.flush()).map_err(|e| e.to_string())
+}
+
+fn from_tar(args: &[String]) -> Result<(), String> {
+    let (archive, image, label) = match args {
+        [archive, image] => (archive, image, "CDROM"),
+        [archive, image, label] => (archive, image, label.as_str()),
+        _ => return Err(usage_error()),
+    };
+
+    let output = File::create(image).map_err(|e| format!("{image}: {e}"))?;
+    let mut writer = ImageWriter::new(io::BufWriter::new(output))
+        .map_err(|e| format!("{image}: {e}"))?
+        .volume_id(label);
+
+    let result = match archive.as_str() {
+        "-" => writer.append_tar(&mut io::stdin().lock()),
+        path => File::open(path).and_then(|file| writer.append_tar(&mut io::BufReader::new(file))),
+    };
+
+    result.map_err(|e| format!("{archive}: {e}"))?;
+    writer.finish().and_then(|output| output.into_inner().map_err(|e| e.into_error())).map_err(|e| format!("{image}: {e}"))?;
+
+    Ok(())
+}
+
+fn info(args: &[String]) -> Result<(), String> {
+    let [image] = args else {
+        return Err(usage_error());
+    };
+
+    let iso = open(image)?;
+
+    for descriptor in iso.descriptors() {
+        print_descriptor(&descriptor);
+        println!();
+    }
+
+    let names = if iso.has_rock_ridge() {
+        "Rock Ridge"
+    } else if iso.has_joliet() {
+        "Joliet"
+    } else {
+        "ISO9660"
+    };
+
+    println!("Names:         {names}");
+
+    if let Some(udf) = UDF::from_device(iso.device()) {
+        println!("UDF:           {}.{:02x} \"{}\"", udf.revision() >> 8, udf.revision() & 0xFF, udf.volume_id());
+    }
+
+    if let Some(hfs) = HFS::from_device(iso.device()) {
+        println!("{:<15}\"{}\"", if hfs.is_plus() { "HFS+:" } else { "HFS:" }, hfs.volume_name());
+    }
+
+    if is_disc_image(image) {
+        let disc = DiscImage::open(image).map_err(|e| format!("{image}: {e}"))?;
+
+        for session in disc.sessions() {
+            println!();
+            println!("Session {} (sectors {}..{})", session.number, session.start, session.end);
+
+            for track in disc.tracks().iter().filter(|track| track.session == session.number) {
+                println!(
+                    "  Track {:2}  {:<10}  sector {:>7}  length {:>7}  {} bytes per sector",
+                    track.number,
+                    format!("{:?}", track.mode),
+                    track.start,
+                    track.length,
+                    track.sector_size
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn print_descriptor(descriptor: &Descriptor) {
+    match descriptor.desc_type {
+        DescriptorType::BootRecord => {
+            let boot = descriptor.try_as_boot_record().unwrap();
+
+            println!("Boot record");
+            println!("  System:      {}", text(&boot.boot_system_id));
+
+            if let Some(lba) = boot.catalog_lba() {
+                println!("  Catalog:     sector {lba}");
+            }
+        }
+        DescriptorType::PrimaryVolume => {
+            println!("Primary volume descriptor");
+            print_volume(descriptor.try_as_pvd().unwrap(), false);
+        }
+        DescriptorType::SupplementaryVolume => {
+            let svd = descriptor.try_as_svd().unwrap();
+
+            println!("Supplementary volume descriptor{}", if svd.is_joliet() { " (Joliet)" } else { "" });
+            print_volume(svd, svd.is_joliet());
+        }
+        DescriptorType::VolumePartition => {
+            let partition = descriptor.try_as_partition().unwrap();
+
+            println!("Volume partition descriptor");
+            println!("  System:      {}", text(&partition.system_id));
+            println!("  Partition:   {}", text(&partition.partition_id));
+            println!("  Blocks:      {} at sector {}", partition.block_count(), partition.location());
+        }
+        DescriptorType::Terminator => println!("Terminator"),
+    }
+}
+
+fn print_volume(volume: &PrimarySupplementaryVolumeDescriptor, joliet: bool) {
+    let field = |data: &[u8]| if joliet { ucs2_text(data) } else { text(data) };
+
+    println!("  System:      {}", field(&volume.system_name));
+    println!("  Volume:      {}", field(&volume.label));
+    println!("  Volume set:  {}", field(&volume.volume_set_id));
+    println!("  Publisher:   {}", field(&volume.publisher_id));
+    println!("  Preparer:    {}", field(&volume.data_preparer_id));
+    println!("  Application: {}", field(&volume.application_id));
+    println!("  Blocks:      {} x {} bytes", volume.block_count(), volume.block_size());
+    println!("  Set:         disc {} of {}", volume.sequence_number(), volume.set_size());
+
+    let dates = [
+        ("Created:     ", volume.creation_date()),
+        ("Modified:    ", volume.modification_date()),
+        ("Expires:     ", volume.expiration_date()),
+        ("Effective:   ", volume.effective_date()),
+    ];
+
+    for (label, date) in dates {
+        if let Some(date) = date {
+            println!("  {label}{}", format_time(date));
+        }
+    }
+}
+
+/// Text field padded with spaces
+fn text(data: &[u8]) -> String {
+    String::from_utf8_lossy(data).trim_end_matches([' ', '\0']).to_string()
+}
+
+/// Joliet text field (UCS-2, big-endian) padded with spaces
+fn ucs2_text(data: &[u8]) -> String {
+    let units = data.chunks_exact(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]]));
+
+    char::decode_utf16(units)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect::<String>()
+        .trim_end_matches([' ', '\0'])
+        .to_string()
+}
+
+fn boot(args: &[String]) -> Result<(), String> {
+    let (image, extract) = match args {
+        [image] => (image, None),
+        [image, flag, index, output] if flag == "--extract" => {
+            let index: usize = index.parse().map_err(|_| format!("{index}: not an entry number"))?;
+
+            (image, Some((index, output)))
+        }
+        _ => return Err(usage_error()),
+    };
+
+    let iso = open(image)?;
+    let catalog = iso.boot_catalog().ok_or("the image is not bootable")?;
+
+    if let Some((index, output)) = extract {
+        let entry = catalog.entries().nth(index).ok_or_else(|| format!("no boot entry {index}"))?;
+
+        return save_boot_image(&iso, &entry, output);
+    }
+
+    println!("Catalog:   sector {}", catalog.lba);
+    println!("Platform:  {:?}", catalog.platform());
+    println!("ID:        {}", text(catalog.id()));
+
+    for (index, entry) in catalog.entries().enumerate() {
+        println!();
+        println!("Entry {index}{}", if index == 0 { " (default)" } else { "" });
+        println!("  Bootable:  {}", if entry.bootable { "yes" } else { "no" });
+        println!("  Platform:  {:?}", entry.platform);
+        println!("  Emulation: {:?}", entry.emulation);
+        println!("  Segment:   {:#06x}", entry.load_segment);
+        println!("  System:    {:#04x}", entry.system_type);
+        println!("  Sectors:   {}", entry.sector_count);
+        println!("  Start:     sector {}", entry.load_rba);
+    }
+
+    Ok(())
+}
+
+fn save_boot_image(iso: &Image, entry: &BootEntry, output: &str) -> Result<(), String> {
+    let mut size = entry.load_size();
+
+    // No emulation images are usually files of the tree too, which know their real size.
+    if entry.emulation == Emulation::NoEmulation {
+        if let Some(file) = iso.walk().find(|item| item.entry.is_file() && item.entry.lsb_position() == entry.load_rba) {
+            size = size.max(file.entry.file_size());
+        }
+    }
+
+    let mut data = vec![0u8; size as usize];
+
+    iso.read_boot_image(entry, 0, &mut data).ok_or("failed to read the boot image")?;
+    std::fs::write(output, data).map_err(|e| format!("{output}: {e}"))
+}
@@ -0,0 +1,278 @@
+//! Sector-granular cache that sits between [`ISO9660`](crate::ISO9660) and a slow device.
+//!
+//! The parser issues a lot of small reads (record headers, names, system use areas).
+//! On optical drives or network-backed storage every one of them is a round trip,
+//! so [`CachedDevice`] keeps recently used sectors in a fixed-capacity buffer and
+//! optionally reads ahead when access looks sequential.
+
+use crate::io::{lock, lock_get_mut, read_from, Lock};
+use crate::{Read, DISK_SECTOR_SIZE};
+
+const SECTOR_SIZE: u64 = DISK_SECTOR_SIZE as u64;
+
+/// Read-ahead used by [`CachedDevice::new`], in sectors.
+pub const DEFAULT_READ_AHEAD: usize = 4;
+
+/// Counters collected by [`CachedDevice`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Sector lookups served from the cache
+    pub hits: u64,
+    /// Sector lookups that had to go to the device
+    pub misses: u64,
+    /// Sectors fetched speculatively by read-ahead
+    pub read_ahead: u64,
+    /// `read` calls issued to the underlying device
+    pub device_reads: u64,
+}
+
+impl CacheStats {
+    /// Share of sector lookups served from the cache, in range `0.0..=1.0`.
+    pub fn hit_ratio(&self) -> f64 {
+        let total = self.hits + self.misses;
+
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+/// LRU sector cache with sequential read-ahead.
+///
+/// `SECTORS` is the capacity of the cache in 2048-byte sectors. The storage lives
+/// inside the structure itself, so no heap is needed.
+/// Reads of a [volume set](crate::volume_set::VolumeSet) are passed on with their volume,
+/// sectors are cached per volume.
+/// The cache is guarded by the same lock as [`Locked`](crate::io::Locked), so with the
+/// `std` feature it can be shared between threads.
+pub struct CachedDevice<D: Read, const SECTORS: usize> {
+    device: D,
+    slots: Lock<Slots<SECTORS>>,
+}
+
+struct Slots<const SECTORS: usize> {
+    data: [[u8; DISK_SECTOR_SIZE]; SECTORS],
+    /// Volume and sector number held by every slot
+    tags: [Option<Sector>; SECTORS],
+    /// Last access time of every slot, used for eviction
+    stamps: [u64; SECTORS],
+    clock: u64,
+    read_ahead: usize,
+    last_sector: Option<Sector>,
+    stats: CacheStats,
+}
+
+/// A sector of the device. Plain reads have no volume, [`Read::read_volume`] ones do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Sector {
+    volume: Option<u16>,
+    number: u64,
+}
+
+impl Sector {
+    fn next(self, count: u64) -> Self {
+        Self {
+            number: self.number + count,
+            ..self
+        }
+    }
+}
+
+impl<D: Read, const SECTORS: usize> CachedDevice<D, SECTORS> {
+    pub fn new(device: D) -> Self {
+        Self {
+            device,
+            slots: Lock::new(Slots {
+                data: [[0; DISK_SECTOR_SIZE]; SECTORS],
+                tags: [None; SECTORS],
+                stamps: [0; SECTORS],
+                clock: 0,
+                read_ahead: DEFAULT_READ_AHEAD,
+                last_sector: None,
+                stats: CacheStats::default(),
+            }),
+        }
+    }
+
+    /// Sets how many sectors are fetched past a sequential miss. `0` disables read-ahead.
+    pub fn with_read_ahead(mut self, sectors: usize) -> Self {
+        self.set_read_ahead(sectors);
+        self
+    }
+
+    pub fn set_read_ahead(&mut self, sectors: usize) {
+        lock_get_mut(&mut self.slots).read_ahead = sectors;
+    }
+
+    pub fn read_ahead(&self) -> usize {
+        lock(&self.slots).read_ahead
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        lock(&self.slots).stats
+    }
+
+    pub fn reset_stats(&self) {
+        lock(&self.slots).stats = CacheStats::default();
+    }
+
+    /// Drops every cached sector (e.g. after the medium was changed).
+    pub fn invalidate(&self) {
+        let mut slots = lock(&self.slots);
+
+        slots.tags = [None; SECTORS];
+        slots.stamps = [0; SECTORS];
+        slots.last_sector = None;
+    }
+
+    pub fn get_ref(&self) -> &D {
+        &self.device
+    }
+
+    /// Gives mutable access to the device. The cache is not invalidated.
+    pub fn get_mut(&mut self) -> &mut D {
+        &mut self.device
+    }
+
+    pub fn into_inner(self) -> D {
+        self.device
+    }
+}
+
+impl<const SECTORS: usize> Slots<SECTORS> {
+    fn find(&self, sector: Sector) -> Option<usize> {
+        self.tags.iter().position(|&tag| tag == Some(sector))
+    }
+
+    /// Loads `sector` (and possibly following ones) into the cache and returns its slot.
+    fn fill(&mut self, device: &impl Read, sector: Sector) -> Option<usize> {
+        let sequential = sector
+            .number
+            .checked_sub(1)
+            .is_some_and(|prev| self.last_sector == Some(Sector { number: prev, ..sector }));
+
+        let mut count = 1;
+
+        if sequential {
+            let limit = (1 + self.read_ahead).min(SECTORS);
+
+            // Stop at the first sector that is already cached so no sector occupies two slots.
+            while count < limit && self.find(sector.next(count as u64)).is_none() {
+                count += 1;
+            }
+        }
+
+        let start = self.pick_window(count);
+
+        if !self.load(device, start, sector, count) {
+            if count == 1 || !self.load(device, start, sector, 1) {
+                return None;
+            }
+
+            count = 1;
+        }
+
+        self.stats.read_ahead += count as u64 - 1;
+
+        Some(start)
+    }
+
+    /// Finds `count` adjacent slots whose most recent use is the oldest.
+    fn pick_window(&self, count: usize) -> usize {
+        (0..=SECTORS - count)
+            .min_by_key(|&start| self.stamps[start..start + count].iter().max().copied())
+            .unwrap_or(0)
+    }
+
+    fn load(&mut self, device: &impl Read, start: usize, sector: Sector, count: usize) -> bool {
+        let buffer = &mut self.data.as_flattened_mut()
+            [start * DISK_SECTOR_SIZE..(start + count) * DISK_SECTOR_SIZE];
+
+        self.stats.device_reads += 1;
+
+        if read_from(device, sector.volume, sector.number * SECTOR_SIZE, buffer).is_none() {
+            for slot in start..start + count {
+                self.tags[slot] = None;
+            }
+
+            return false;
+        }
+
+        for i in 0..count {
+            self.tags[start + i] = Some(sector.next(i as u64));
+            self.stamps[start + i] = self.clock;
+        }
+
+        true
+    }
+}
+
+impl<D: Read, const SECTORS: usize> CachedDevice<D, SECTORS> {
+    fn read_from(&self, volume: Option<u16>, position: u64, buffer: &mut [u8]) -> Option<()> {
+        let mut slots = lock(&self.slots);
+
+        // Large transfers would only evict everything, pass them through.
+        if SECTORS == 0 || buffer.len() >= SECTORS * DISK_SECTOR_SIZE {
+            slots.stats.device_reads += 1;
+            drop(slots);
+
+            return read_from(&self.device, volume, position, buffer);
+        }
+
+        let mut done = 0;
+
+        while done < buffer.len() {
+            let offset = position.checked_add(done as u64)?;
+            let sector = Sector {
+                volume,
+                number: offset / SECTOR_SIZE,
+            };
+            let in_sector = (offset % SECTOR_SIZE) as usize;
+
+            slots.clock += 1;
+
+            let slot = match slots.find(sector) {
+                Some(slot) => {
+                    slots.stats.hits += 1;
+                    slot
+                }
+                None => {
+                    slots.stats.misses += 1;
+
+                    match slots.fill(&self.device, sector) {
+                        Some(slot) => slot,
+                        None => {
+                            // Probably a short last sector, let the device deal with it.
+                            slots.stats.device_reads += 1;
+                            drop(slots);
+
+                            return read_from(&self.device, volume, offset, &mut buffer[done..]);
+                        }
+                    }
+                }
+            };
+
+            slots.stamps[slot] = slots.clock;
+            slots.last_sector = Some(sector);
+
+            let chunk = (DISK_SECTOR_SIZE - in_sector).min(buffer.len() - done);
+            buffer[done..done + chunk].copy_from_slice(&slots.data[slot][in_sector..in_sector + chunk]);
+
+            done += chunk;
+        }
+
+        Some(())
+    }
+}
+
+impl<D: Read, const SECTORS: usize> Read for CachedDevice<D, SECTORS> {
+    fn read(&self, position: u64, buffer: &mut [u8]) -> Option<()> {
+        self.read_from(None, position, buffer)
+    }
+
+    fn read_volume(&self, sequence: u16, position: u64, buffer: &mut [u8]) -> Option<()> {
+        self.read_from(Some(sequence), position, buffer)
+    }
+}
@@ -0,0 +1,222 @@
+//! Block-compressed disc images: CSO (deflate) and ZSO (lz4).
+//!
+//! The file starts with a 24-byte header followed by an index with the position of every block
+//! and the end of the last one. Blocks are compressed independently, so [`CompressedImage`]
+//! reads any range by decompressing only the blocks it touches. The last decompressed block
+//! is kept, as the parser reads a sector at a time.
+//!
+//! In CSO v1 and ZSO the top bit of an index entry marks a stored (uncompressed) block.
+//! CSO v2 stores blocks whose compressed size reaches the block size (the size of the last,
+//! shorter block), and the top bit marks lz4 instead of deflate.
+
+use alloc::{vec, vec::Vec};
+
+use crate::io::{lock, read_from, Lock};
+use crate::Read;
+
+const CSO_MAGIC: &[u8; 4] = b"CISO";
+const ZSO_MAGIC: &[u8; 4] = b"ZISO";
+
+const HEADER_SIZE: usize = 24;
+
+const INDEX_FLAG: u32 = 0x8000_0000;
+
+/// Largest block size accepted, 1 MiB
+const MAX_BLOCK_SIZE: u32 = 1 << 20;
+
+/// Container format of a [`CompressedImage`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// CSO v1, deflate blocks
+    Cso,
+    /// CSO v2, deflate or lz4 blocks
+    Cso2,
+    /// ZSO, lz4 blocks
+    Zso,
+}
+
+/// Volume (for [`Read::read_volume`]) and number of a block.
+type BlockKey = (Option<u16>, u64);
+
+/// Random-access view of the uncompressed image inside a CSO or ZSO file.
+pub struct CompressedImage<D: Read> {
+    device: D,
+    format: Format,
+    size: u64,
+    block_size: u32,
+    index_shift: u8,
+    /// Position of every block, with the flag bit, and the end of the last one
+    index: Vec<u32>,
+    /// Last decompressed block and where it came from
+    cache: Lock<(Option<BlockKey>, Vec<u8>)>,
+}
+
+impl<D: Read> CompressedImage<D> {
+    /// Reads the header and the block index, returns `None` if the device is not a CSO or ZSO file.
+    pub fn new(device: D) -> Option<Self> {
+        let mut header = [0u8; HEADER_SIZE];
+
+        device.read(0, &mut header)?;
+
+        // magic, header size, uncompressed size (64), block size, version, index shift, reserved
+        let size = u64::from_le_bytes(header[8..16].try_into().ok()?);
+        let block_size = u32::from_le_bytes(header[16..20].try_into().ok()?);
+        let (version, index_shift) = (header[20], header[21]);
+
+        let format = match (&header[..4], version) {
+            (magic, 0 | 1) if magic == CSO_MAGIC => Format::Cso,
+            (magic, 2) if magic == CSO_MAGIC => Format::Cso2,
+            (magic, 0 | 1) if magic == ZSO_MAGIC => Format::Zso,
+            _ => return None,
+        };
+
+        if block_size == 0 || block_size > MAX_BLOCK_SIZE || index_shift > 31 {
+            return None;
+        }
+
+        let blocks = usize::try_from(size.div_ceil(block_size as u64)).ok()?;
+        let index_size = blocks.checked_add(1)?.checked_mul(4)?;
+
+        // The sizes in the header are untrusted: the whole index must be on the device
+        // before it's allocated.
+        device.read((HEADER_SIZE as u64).checked_add(index_size as u64 - 1)?, &mut [0u8])?;
+
+        let mut raw = vec![0u8; index_size];
+
+        device.read(HEADER_SIZE as u64, &mut raw)?;
+
+        let index = raw
+            .chunks_exact(4)
+            .map(|entry| u32::from_le_bytes([entry[0], entry[1], entry[2], entry[3]]))
+            .collect();
+
+        Some(Self {
+            device,
+            format,
+            size,
+            block_size,
+            index_shift,
+            index,
+            cache: Lock::new((None, vec![0u8; block_size as usize])),
+        })
+    }
+
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    /// Size of the uncompressed image
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn block_size(&self) -> u32 {
+        self.block_size
+    }
+
+    pub fn into_inner(self) -> D {
+        self.device
+    }
+
+    /// Decompresses block `number` into `output`, which is exactly as long as the block.
+    /// The block is read from `volume` of the device if one is given.
+    fn read_block(&self, volume: Option<u16>, number: u64, output: &mut [u8]) -> Option<()> {
+        let entry = *self.index.get(number as usize)?;
+        let next = *self.index.get(number as usize + 1)?;
+
+        let start = ((entry & !INDEX_FLAG) as u64) << self.index_shift;
+        let end = ((next & !INDEX_FLAG) as u64) << self.index_shift;
+        let stored = end.checked_sub(start)?;
+
+        let flagged = entry & INDEX_FLAG != 0;
+
+        let codec = match self.format {
+            Format::Cso | Format::Zso if flagged => Codec::Stored,
+            Format::Cso => Codec::Deflate,
+            Format::Zso => Codec::Lz4,
+            Format::Cso2 if stored >= output.len() as u64 => Codec::Stored,
+            Format::Cso2 if flagged => Codec::Lz4,
+            Format::Cso2 => Codec::Deflate,
+        };
+
+        if codec == Codec::Stored {
+            return read_from(&self.device, volume, start, output);
+        }
+
+        if stored > 2 * self.block_size as u64 {
+            return None;
+        }
+
+        let mut compressed = vec![0u8; stored as usize];
+
+        read_from(&self.device, volume, start, &mut compressed)?;
+
+        let written = match codec {
+            Codec::Deflate => {
+                miniz_oxide::inflate::decompress_slice_iter_to_slice(output, core::iter::once(&compressed[..]), false, true)
+                    .ok()?
+            }
+            _ => decompress_lz4(&compressed, output, (1usize << self.index_shift) - 1)?,
+        };
+
+        (written == output.len()).then_some(())
+    }
+}
+
+#[derive(PartialEq, Eq)]
+enum Codec {
+    Stored,
+    Deflate,
+    Lz4,
+}
+
+/// Decompresses an lz4 block. Aligned blocks may be followed by up to `padding` zero bytes,
+/// which the block format can't tell apart from data, so shorter inputs are tried as well.
+fn decompress_lz4(input: &[u8], output: &mut [u8], padding: usize) -> Option<usize> {
+    let zeros = input.iter().rev().take_while(|byte| **byte == 0).count().min(padding);
+
+    (0..=zeros).find_map(|cut| lz4_flex::block::decompress_into(&input[..input.len() - cut], output).ok())
+}
+
+impl<D: Read> CompressedImage<D> {
+    fn read_from(&self, volume: Option<u16>, position: u64, buffer: &mut [u8]) -> Option<()> {
+        if position.checked_add(buffer.len() as u64)? > self.size {
+            return None;
+        }
+
+        let block_size = self.block_size as u64;
+        let mut cache = lock(&self.cache);
+        let mut done = 0;
+
+        while done < buffer.len() {
+            let offset = position + done as u64;
+            let number = offset / block_size;
+            let block_start = number * block_size;
+            let length = (self.size - block_start).min(block_size) as usize;
+
+            if cache.0 != Some((volume, number)) {
+                cache.0 = None;
+                self.read_block(volume, number, &mut cache.1[..length])?;
+                cache.0 = Some((volume, number));
+            }
+
+            let from = (offset - block_start) as usize;
+            let count = (length - from).min(buffer.len() - done);
+
+            buffer[done..done + count].copy_from_slice(&cache.1[from..from + count]);
+            done += count;
+        }
+
+        Some(())
+    }
+}
+
+impl<D: Read> Read for CompressedImage<D> {
+    fn read(&self, position: u64, buffer: &mut [u8]) -> Option<()> {
+        self.read_from(None, position, buffer)
+    }
+
+    fn read_volume(&self, sequence: u16, position: u64, buffer: &mut [u8]) -> Option<()> {
+        self.read_from(Some(sequence), position, buffer)
+    }
+}
@@ -1,5 +1,8 @@
 use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout, TryFromBytes};
 
+/// Standard identifier of ISO9660 volume descriptors
+pub const STANDARD_ID: &[u8] = b"CD001";
+
 #[repr(u8)]
 #[derive(Debug, PartialEq, Eq, Immutable, TryFromBytes)]
 pub enum DescriptorType {
@@ -10,7 +13,7 @@ pub enum DescriptorType {
     Terminator = 0xff,
 }
 
-#[repr(packed(1))]
+#[repr(C, packed(1))]
 #[derive(Immutable, TryFromBytes)]
 pub struct Descriptor {
     pub desc_type: DescriptorType,
@@ -28,6 +31,14 @@ impl Descriptor {
         }
     }
 
+    pub fn try_as_boot_record(&self) -> Option<&BootRecordDescriptor> {
+        if self.desc_type == DescriptorType::BootRecord {
+            Some(BootRecordDescriptor::ref_from_bytes(&self.data).unwrap())
+        } else {
+            None
+        }
+    }
+
     pub fn try_as_svd(&self) -> Option<&PrimarySupplementaryVolumeDescriptor> {
         if self.desc_type == DescriptorType::SupplementaryVolume {
             Some(PrimarySupplementaryVolumeDescriptor::ref_from_bytes(&self.data).unwrap())
@@ -35,6 +46,14 @@ impl Descriptor {
             None
         }
     }
+
+    pub fn try_as_partition(&self) -> Option<&VolumePartitionDescriptor> {
+        if self.desc_type == DescriptorType::VolumePartition {
+            Some(VolumePartitionDescriptor::ref_from_bytes(&self.data).unwrap())
+        } else {
+            None
+        }
+    }
 }
 
 #[derive(Debug, FromBytes, Immutable, IntoBytes, KnownLayout)]
@@ -72,7 +91,91 @@ pub struct PrimarySupplementaryVolumeDescriptor {
     pub reserved: [u8; 653],
 }
 
+#[derive(Debug, FromBytes, Immutable, IntoBytes, KnownLayout)]
+#[repr(C, packed(1))]
+pub struct BootRecordDescriptor {
+    pub boot_system_id: [u8; 32],
+    pub boot_id: [u8; 32],
+    pub boot_system_use: [u8; 1977],
+}
+
+impl BootRecordDescriptor {
+    /// Location of the boot catalog if this is an El Torito boot record
+    pub fn catalog_lba(&self) -> Option<u32> {
+        if !self.boot_system_id.starts_with(crate::eltorito::BOOT_SYSTEM_ID) {
+            return None;
+        }
+
+        Some(u32::from_le_bytes(self.boot_system_use[..4].try_into().unwrap()))
+    }
+}
+
+/// A range of blocks set aside from the file structure, for use by the system it names.
+#[derive(Debug, Clone, FromBytes, Immutable, IntoBytes, KnownLayout)]
+#[repr(C, packed(1))]
+pub struct VolumePartitionDescriptor {
+    pub unused00: u8,
+    pub system_id: [u8; 32],
+    pub partition_id: [u8; 32],
+    pub partition_location: [u32; 2],
+    pub partition_size: [u32; 2],
+    pub system_use: [u8; 1960],
+}
+
+impl VolumePartitionDescriptor {
+    /// First logical block of the partition
+    pub fn location(&self) -> u32 {
+        u32::from_le_bytes(self.partition_location[0].to_ne_bytes())
+    }
+
+    /// Number of logical blocks in the partition
+    pub fn block_count(&self) -> u32 {
+        u32::from_le_bytes(self.partition_size[0].to_ne_bytes())
+    }
+}
+
 impl PrimarySupplementaryVolumeDescriptor {
+    /// Whether the escape sequences announce Joliet (UCS-2 level 1, 2 or 3)
+    pub fn is_joliet(&self) -> bool {
+        [b"%/@", b"%/C", b"%/E"].iter().any(|sequence| self.un_used02.starts_with(*sequence))
+    }
+
+    /// Number of logical blocks in the volume
+    pub fn block_count(&self) -> u32 {
+        u32::from_le_bytes(self.volume_space_size[0].to_ne_bytes())
+    }
+
+    pub fn block_size(&self) -> u16 {
+        both_endian_u16(self.logical_block_size)
+    }
+
+    /// Number of discs in the volume set
+    pub fn set_size(&self) -> u16 {
+        both_endian_u16(self.volume_set_size)
+    }
+
+    /// Number of this disc in the volume set
+    pub fn sequence_number(&self) -> u16 {
+        both_endian_u16(self.volume_sequence_number)
+    }
+
+    /// Creation date as seconds since the Unix epoch
+    pub fn creation_date(&self) -> Option<i64> {
+        crate::types::dec_datetime(&self.volume_creation_date)
+    }
+
+    pub fn modification_date(&self) -> Option<i64> {
+        crate::types::dec_datetime(&self.volume_modification_date)
+    }
+
+    pub fn expiration_date(&self) -> Option<i64> {
+        crate::types::dec_datetime(&self.volume_expiration_date)
+    }
+
+    pub fn effective_date(&self) -> Option<i64> {
+        crate::types::dec_datetime(&self.volume_effective_date)
+    }
+
     /// Helper function that exposes ISO header as an array of bytes
     pub fn as_slice(&mut self) -> &[u8] {
         self.as_bytes()
@@ -83,3 +186,10 @@ impl PrimarySupplementaryVolumeDescriptor {
         self.as_mut_bytes()
     }
 }
+
+/// Little endian half of a 16-bit both-endian field that was read as `u32`.
+fn both_endian_u16(value: u32) -> u16 {
+    let bytes = value.to_ne_bytes();
+
+    u16::from_le_bytes([bytes[0], bytes[1]])
+}
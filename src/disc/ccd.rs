@@ -0,0 +1,134 @@
+//! CloneCD images: a `.ccd` text file with the table of contents and a `.img` file with raw
+//! 2352-byte sectors of the whole disc (`.sub` holds the subchannel data, which isn't needed).
+//!
+//! The table of contents is a list of `[Entry N]` sections copied from the disc: points 1 to 99
+//! are tracks, point `0xA2` is the lead-out of a session. `[TRACK N]` sections give track modes.
+
+use alloc::vec::Vec;
+
+use super::{Track, TrackMode};
+
+const SECTOR_SIZE: u32 = 2352;
+
+/// Table of contents point of a session lead-out
+const LEAD_OUT: i64 = 0xA2;
+
+/// Control bit of data tracks
+const CONTROL_DATA: i64 = 0x04;
+
+#[derive(Default)]
+struct Entry {
+    session: i64,
+    point: i64,
+    control: i64,
+    address: i64,
+}
+
+/// Reads the track layout from the contents of a `.ccd` file, `image_size` is the size of the `.img`.
+pub fn parse(text: &str, image_size: u64) -> Option<Vec<Track>> {
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut modes: Vec<(i64, i64)> = Vec::new();
+    let mut section = "";
+
+    for line in text.lines().map(str::trim) {
+        if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            section = name;
+
+            if starts_with_ignore_case(section, "Entry ") {
+                entries.push(Entry::default());
+            }
+
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+
+        let (key, Some(value)) = (key.trim(), number(value.trim())) else {
+            continue;
+        };
+
+        if starts_with_ignore_case(section, "Entry ") {
+            let entry = entries.last_mut()?;
+
+            match key.to_ascii_lowercase().as_str() {
+                "session" => entry.session = value,
+                "point" => entry.point = value,
+                "control" => entry.control = value,
+                "plba" => entry.address = value,
+                _ => {}
+            }
+        } else if starts_with_ignore_case(section, "TRACK ") && key.eq_ignore_ascii_case("MODE") {
+            modes.push((number(section[6..].trim())?, value));
+        }
+    }
+
+    let mut tracks: Vec<Track> = Vec::new();
+
+    for entry in entries.iter().filter(|entry| (1..=99).contains(&entry.point)) {
+        let mode = match modes.iter().find(|(track, _)| *track == entry.point).map(|(_, mode)| *mode) {
+            Some(0) => TrackMode::Audio,
+            Some(1) => TrackMode::Mode1,
+            Some(_) => TrackMode::Mode2,
+            None if entry.control & CONTROL_DATA != 0 => TrackMode::Mode1,
+            None => TrackMode::Audio,
+        };
+
+        let start = u32::try_from(entry.address).ok()?;
+
+        tracks.push(Track {
+            number: entry.point as u8,
+            session: u16::try_from(entry.session).ok()?,
+            mode,
+            start,
+            length: 0,
+            position: start as u64 * SECTOR_SIZE as u64,
+            sector_size: SECTOR_SIZE,
+        });
+    }
+
+    tracks.sort_by_key(|track| track.start);
+
+    // Tracks run until the next one in the session, the lead-out or the end of the image
+    let image_end = u32::try_from(image_size / SECTOR_SIZE as u64).unwrap_or(u32::MAX);
+
+    for index in 0..tracks.len() {
+        let session = tracks[index].session as i64;
+        let lead_out = entries
+            .iter()
+            .find(|entry| entry.point == LEAD_OUT && entry.session == session)
+            .and_then(|entry| u32::try_from(entry.address).ok());
+
+        let end = match tracks.get(index + 1) {
+            Some(next) if next.session == tracks[index].session => next.start,
+            _ => lead_out.unwrap_or(image_end),
+        };
+
+        tracks[index].length = end.min(image_end).saturating_sub(tracks[index].start);
+    }
+
+    Some(tracks)
+}
+
+/// Decimal or `0x`-prefixed hexadecimal number.
+fn number(text: &str) -> Option<i64> {
+    match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => i64::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
+
+fn starts_with_ignore_case(text: &str, prefix: &str) -> bool {
+    text.get(..prefix.len()).is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+}
+
+/// Opens a `.ccd` file and the `.img` next to it.
+#[cfg(feature = "std")]
+pub(super) fn open(path: &std::path::Path) -> std::io::Result<(std::fs::File, Option<Vec<Track>>)> {
+    let text = std::fs::read(path)?;
+    let image = std::fs::File::open(path.with_extension("img"))?;
+    let size = image.metadata()?.len();
+
+    Ok((image, parse(&String::from_utf8_lossy(&text), size)))
+}
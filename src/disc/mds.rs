@@ -0,0 +1,180 @@
+//! Alcohol 120% images: a `.mds` descriptor with the track layout and a `.mdf` file with the data.
+//!
+//! The descriptor starts with a `MEDIA DESCRIPTOR` header pointing at session blocks. Every
+//! session block points at its track blocks, lead-in entries among them (points above 99) are
+//! skipped. The data file name is stored in a track footer, `*.mdf` meaning the name of the
+//! descriptor with another extension.
+
+use alloc::{string::String, vec::Vec};
+
+use super::{Track, TrackMode};
+use crate::Read;
+
+const SIGNATURE: &[u8; 16] = b"MEDIA DESCRIPTOR";
+
+const HEADER_SIZE: usize = 88;
+const SESSION_BLOCK_SIZE: u64 = 24;
+const TRACK_BLOCK_SIZE: u64 = 80;
+
+/// Longest data file name read, in characters
+const MAX_NAME_LENGTH: usize = 1024;
+
+/// Track layout from a descriptor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Descriptor {
+    pub tracks: Vec<Track>,
+    /// Name of the data file, `*.mdf` stands for the name of the descriptor with that extension
+    pub data_file: Option<String>,
+}
+
+pub fn parse<D: Read + ?Sized>(device: &D) -> Option<Descriptor> {
+    let mut header = [0u8; HEADER_SIZE];
+
+    device.read(0, &mut header)?;
+
+    // Version 2 descriptors are encrypted
+    if &header[..16] != SIGNATURE || header[16] != 1 {
+        return None;
+    }
+
+    let sessions = u16::from_le_bytes([header[20], header[21]]) as u64;
+    let sessions_offset = u32::from_le_bytes(header[80..84].try_into().ok()?) as u64;
+
+    let mut tracks = Vec::new();
+    let mut data_file = None;
+
+    for index in 0..sessions {
+        let mut session = [0u8; SESSION_BLOCK_SIZE as usize];
+
+        device.read(sessions_offset + index * SESSION_BLOCK_SIZE, &mut session)?;
+
+        // start, end, number, all blocks, lead-in blocks, first and last track, reserved, track blocks
+        let end = i32::from_le_bytes(session[4..8].try_into().ok()?).max(0) as u32;
+        let number = u16::from_le_bytes([session[8], session[9]]);
+        let blocks = session[10] as u64;
+        let blocks_offset = u32::from_le_bytes(session[20..24].try_into().ok()?) as u64;
+
+        let first = tracks.len();
+
+        for block_index in 0..blocks {
+            let mut block = [0u8; TRACK_BLOCK_SIZE as usize];
+
+            device.read(blocks_offset + block_index * TRACK_BLOCK_SIZE, &mut block)?;
+
+            // mode, subchannel, adr/control, reserved, point, reserved, MSF, extra block,
+            // sector size, reserved, start sector, start offset, file count, footer, reserved
+            let point = block[4];
+
+            if point == 0 || point > 99 {
+                continue;
+            }
+
+            let extra_offset = u32::from_le_bytes(block[12..16].try_into().ok()?) as u64;
+            let footer_offset = u32::from_le_bytes(block[52..56].try_into().ok()?) as u64;
+
+            // pregap and length of the track
+            let mut extra = [0u8; 8];
+            let length = if extra_offset != 0 {
+                device.read(extra_offset, &mut extra)?;
+                u32::from_le_bytes(extra[4..8].try_into().ok()?)
+            } else {
+                0
+            };
+
+            if data_file.is_none() && footer_offset != 0 {
+                data_file = Some(file_name(device, footer_offset)?);
+            }
+
+            tracks.push(Track {
+                number: point,
+                session: number,
+                mode: match block[0] & 0x0F {
+                    0x09 => TrackMode::Audio,
+                    0x0B => TrackMode::Mode2,
+                    0x0C => TrackMode::Mode2Form1,
+                    0x0D => TrackMode::Mode2Form2,
+                    _ => TrackMode::Mode1,
+                },
+                start: u32::from_le_bytes(block[36..40].try_into().ok()?),
+                length,
+                position: u64::from_le_bytes(block[40..48].try_into().ok()?),
+                sector_size: u16::from_le_bytes([block[16], block[17]]) as u32,
+            });
+        }
+
+        // Tracks without an extra block run until the next one or the end of the session
+        tracks[first..].sort_by_key(|track: &Track| track.start);
+
+        for index in first..tracks.len() {
+            if tracks[index].length == 0 {
+                let next = tracks.get(index + 1).map_or(end, |next| next.start);
+
+                tracks[index].length = next.saturating_sub(tracks[index].start);
+            }
+        }
+    }
+
+    Some(Descriptor {
+        tracks,
+        data_file: data_file.flatten(),
+    })
+}
+
+/// Reads the data file name of a footer, in UTF-16 if flagged. `None` inside for an empty name.
+fn file_name<D: Read + ?Sized>(device: &D, footer_offset: u64) -> Option<Option<String>> {
+    let mut footer = [0u8; 8];
+
+    device.read(footer_offset, &mut footer)?;
+
+    let mut position = u32::from_le_bytes(footer[0..4].try_into().ok()?) as u64;
+    let wide = u32::from_le_bytes(footer[4..8].try_into().ok()?) != 0;
+
+    if position == 0 {
+        return Some(None);
+    }
+
+    let mut units = Vec::new();
+
+    while units.len() < MAX_NAME_LENGTH {
+        let mut bytes = [0u8; 2];
+        let length = if wide { 2 } else { 1 };
+
+        device.read(position, &mut bytes[..length])?;
+        position += length as u64;
+
+        match u16::from_le_bytes(bytes) {
+            0 => break,
+            unit => units.push(unit),
+        }
+    }
+
+    let name: String = char::decode_utf16(units).map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER)).collect();
+
+    Some((!name.is_empty()).then_some(name))
+}
+
+/// Opens a descriptor and the data file it names.
+#[cfg(feature = "std")]
+pub(super) fn open(path: &std::path::Path) -> std::io::Result<(std::fs::File, Option<Vec<Track>>)> {
+    let Some(descriptor) = parse(&std::fs::File::open(path)?) else {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "not a media descriptor file"));
+    };
+
+    // The name comes from the descriptor: it may only name a file next to it.
+    let bare = |name: &str| {
+        let mut components = std::path::Path::new(name).components();
+
+        !name.contains(['/', '\\']) && matches!((components.next(), components.next()), (Some(std::path::Component::Normal(_)), None))
+    };
+
+    let data = match descriptor.data_file {
+        Some(name) => match name.strip_prefix("*.") {
+            Some(extension) if bare(extension) => path.with_extension(extension),
+            None if bare(&name) => path.with_file_name(name),
+            _ => return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "data file is not a bare file name")),
+        },
+        None => path.with_extension("mdf"),
+    };
+
+    Ok((std::fs::File::open(data)?, Some(descriptor.tracks)))
+}
@@ -0,0 +1,280 @@
+//! Disc images that store whole tracks rather than plain 2048-byte sectors:
+//! Nero (`.nrg`), Alcohol 120% (`.mds` + `.mdf`) and CloneCD (`.ccd` + `.img`).
+//!
+//! Every parser produces a list of [`Track`]s. [`DiscImage`] puts them back into the disc
+//! address space and reads the 2048-byte user data of data sectors, whatever the sector format
+//! is in the file (cooked, raw with headers, with interleaved subchannel).
+//!
+//! On multisession discs the volume descriptors of the last session are used, like operating
+//! systems do. Its files are addressed from the start of the disc, so [`DiscImage`] only moves
+//! the volume descriptor area.
+
+pub mod ccd;
+pub mod mds;
+pub mod nrg;
+
+use alloc::vec::Vec;
+
+use crate::{descriptors::DescriptorType, io::read_from, Read, DISK_SECTOR_SIZE};
+
+const SECTOR_SIZE: u64 = DISK_SECTOR_SIZE as u64;
+
+/// First sector of the volume descriptor set
+const DESCRIPTORS_START: u32 = 16;
+
+/// Most volume descriptors looked at in a session
+const MAX_DESCRIPTORS: u32 = 32;
+
+/// Content of a track's sectors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrackMode {
+    Audio,
+    Mode1,
+    /// Mode 2 without a known form, read as CD-ROM XA form 1
+    Mode2,
+    Mode2Form1,
+    Mode2Form2,
+}
+
+/// One track of the disc.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Track {
+    pub number: u8,
+    pub session: u16,
+    pub mode: TrackMode,
+    /// Address of the first sector (index 1)
+    pub start: u32,
+    /// Number of sectors
+    pub length: u32,
+    /// Position of the first sector in the image file
+    pub position: u64,
+    /// Bytes per sector in the image file, subchannel data included
+    pub sector_size: u32,
+}
+
+impl Track {
+    pub fn is_data(&self) -> bool {
+        self.user_data_offset().is_some()
+    }
+
+    /// Offset of the 2048 bytes of user data in a stored sector, `None` for audio and form 2 tracks.
+    pub fn user_data_offset(&self) -> Option<u32> {
+        match (self.mode, self.sector_size) {
+            (TrackMode::Audio | TrackMode::Mode2Form2, _) => None,
+            (_, 2048) => Some(0),
+            // Mode 2 sectors without sync and header start with the 8-byte subheader
+            (TrackMode::Mode2 | TrackMode::Mode2Form1, 2336) => Some(8),
+            // Sync (12) and header (4), followed by the subheader (8) in mode 2
+            (TrackMode::Mode1, size) if size >= 2352 => Some(16),
+            (TrackMode::Mode2 | TrackMode::Mode2Form1, size) if size >= 2352 => Some(24),
+            _ => None,
+        }
+    }
+
+    /// Address after the last sector
+    pub fn end(&self) -> u32 {
+        self.start.saturating_add(self.length)
+    }
+}
+
+/// Tracks recorded in one session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    pub number: u16,
+    pub first_track: u8,
+    pub last_track: u8,
+    /// Address of the first sector of the first track
+    pub start: u32,
+    /// Address after the last sector of the last track
+    pub end: u32,
+}
+
+/// Reads the data tracks of a disc image as an ISO9660 device.
+pub struct DiscImage<D: Read> {
+    device: D,
+    tracks: Vec<Track>,
+    /// First sector of the session whose volume descriptors are read
+    volume_start: u32,
+    /// Number of volume descriptors moved from that session
+    descriptors: u32,
+}
+
+impl<D: Read> DiscImage<D> {
+    /// Joins the tracks of an image stored in `device`, using the last session with data.
+    pub fn new(device: D, mut tracks: Vec<Track>) -> Option<Self> {
+        tracks.sort_by_key(|track| track.start);
+
+        let session = tracks.iter().rev().find(|track| track.is_data())?.session;
+
+        Self {
+            device,
+            tracks,
+            volume_start: 0,
+            descriptors: 0,
+        }
+        .with_session(session)
+    }
+
+    /// Reads the volume descriptors of another session.
+    pub fn with_session(mut self, number: u16) -> Option<Self> {
+        let start = self.tracks.iter().find(|track| track.session == number && track.is_data())?.start;
+
+        self.volume_start = 0;
+        self.descriptors = 0;
+
+        if start != 0 {
+            let mut descriptor = [0u8; DISK_SECTOR_SIZE];
+
+            while self.descriptors < MAX_DESCRIPTORS {
+                self.read_sectors(None, start.checked_add(DESCRIPTORS_START + self.descriptors)?, 0, &mut descriptor)?;
+                self.descriptors += 1;
+
+                if descriptor[0] == DescriptorType::Terminator as u8 {
+                    break;
+                }
+            }
+
+            self.volume_start = start;
+        }
+
+        Some(self)
+    }
+
+    pub fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    pub fn sessions(&self) -> Vec<Session> {
+        let mut sessions: Vec<Session> = Vec::new();
+
+        for track in &self.tracks {
+            match sessions.last_mut() {
+                Some(session) if session.number == track.session => {
+                    session.last_track = track.number;
+                    session.end = track.end();
+                }
+                _ => sessions.push(Session {
+                    number: track.session,
+                    first_track: track.number,
+                    last_track: track.number,
+                    start: track.start,
+                    end: track.end(),
+                }),
+            }
+        }
+
+        sessions
+    }
+
+    /// First sector of the session whose volume descriptors are read
+    pub fn volume_start(&self) -> u32 {
+        self.volume_start
+    }
+
+    pub fn into_inner(self) -> D {
+        self.device
+    }
+
+    /// Reads user data starting `offset` bytes into sector `address`, the buffer may span sectors.
+    /// The image is read from `volume` of the device if one is given.
+    fn read_sectors(&self, volume: Option<u16>, mut address: u32, mut offset: usize, buffer: &mut [u8]) -> Option<()> {
+        let mut done = 0;
+
+        while done < buffer.len() {
+            let track = self.tracks.iter().find(|track| (track.start..track.end()).contains(&address))?;
+            let data_offset = track.user_data_offset()?;
+
+            let count = (DISK_SECTOR_SIZE - offset).min(buffer.len() - done);
+            let position = track.position
+                + (address - track.start) as u64 * track.sector_size as u64
+                + data_offset as u64
+                + offset as u64;
+
+            read_from(&self.device, volume, position, &mut buffer[done..done + count])?;
+
+            done += count;
+            address = address.checked_add(1)?;
+            offset = 0;
+        }
+
+        Some(())
+    }
+}
+
+impl<D: Read> DiscImage<D> {
+    fn read_from(&self, volume: Option<u16>, position: u64, buffer: &mut [u8]) -> Option<()> {
+        let mut done = 0;
+
+        // Reads are split where the volume descriptor area starts and ends
+        while done < buffer.len() {
+            let position = position.checked_add(done as u64)?;
+            let address = u32::try_from(position / SECTOR_SIZE).ok()?;
+            let offset = (position % SECTOR_SIZE) as usize;
+
+            let moved = self.descriptors != 0
+                && (DESCRIPTORS_START..DESCRIPTORS_START + self.descriptors).contains(&address);
+
+            let boundary = if moved {
+                Some(DESCRIPTORS_START + self.descriptors)
+            } else if self.descriptors != 0 && address < DESCRIPTORS_START {
+                Some(DESCRIPTORS_START)
+            } else {
+                None
+            };
+
+            let remaining = buffer.len() - done;
+            let count = match boundary {
+                Some(boundary) => remaining.min((boundary - address) as usize * DISK_SECTOR_SIZE - offset),
+                None => remaining,
+            };
+            let address = if moved { address.checked_add(self.volume_start)? } else { address };
+
+            self.read_sectors(volume, address, offset, &mut buffer[done..done + count])?;
+            done += count;
+        }
+
+        Some(())
+    }
+}
+
+impl<D: Read> Read for DiscImage<D> {
+    fn read(&self, position: u64, buffer: &mut [u8]) -> Option<()> {
+        self.read_from(None, position, buffer)
+    }
+
+    fn read_volume(&self, sequence: u16, position: u64, buffer: &mut [u8]) -> Option<()> {
+        self.read_from(Some(sequence), position, buffer)
+    }
+}
+
+#[cfg(feature = "std")]
+impl DiscImage<std::fs::File> {
+    /// Opens an image by its extension: `.nrg`, `.mds` or `.mdf`, `.ccd` or `.img`.
+    pub fn open(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        use std::io::{Error, ErrorKind};
+
+        let path = path.as_ref();
+        let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or_default();
+
+        let (file, tracks) = match extension.to_ascii_lowercase().as_str() {
+            "nrg" => {
+                let file = std::fs::File::open(path)?;
+                let tracks = nrg::parse(&file, file.metadata()?.len());
+
+                (file, tracks)
+            }
+            "mds" | "mdf" => mds::open(&path.with_extension("mds"))?,
+            "ccd" | "img" => ccd::open(&path.with_extension("ccd"))?,
+            _ => return Err(Error::new(ErrorKind::Unsupported, "unknown disc image format")),
+        };
+
+        let invalid = || Error::new(ErrorKind::InvalidData, "no data tracks in the disc image");
+
+        Self::new(file, tracks.ok_or_else(invalid)?).ok_or_else(invalid)
+    }
+}
+
+/// Decodes a binary-coded decimal byte.
+fn from_bcd(value: u8) -> u8 {
+    (value >> 4) * 10 + (value & 0x0F)
+}
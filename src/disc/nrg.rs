@@ -0,0 +1,210 @@
+//! Nero Burning ROM images (`.nrg`).
+//!
+//! Track data comes first, followed by a list of chunks describing it. The file ends with a
+//! footer pointing at the chunk list: `NER5` and a 64-bit offset, or `NERO` and a 32-bit one in
+//! old images. Disc-at-once images describe their tracks in `DAOX`/`DAOI` chunks, with addresses
+//! in a `CUEX` cue sheet, track-at-once ones in `ETN2`/`ETNF` chunks. A `SINF` chunk per session
+//! holds its number of tracks.
+
+use alloc::{vec, vec::Vec};
+
+use super::{from_bcd, Track, TrackMode};
+use crate::Read;
+
+/// Largest chunk accepted, 1 MiB
+const MAX_CHUNK_SIZE: u32 = 1 << 20;
+
+/// Most chunks read before giving up
+const MAX_CHUNKS: usize = 4096;
+
+/// Size of the `DAOX`/`DAOI` header before the track blocks
+const DAO_HEADER_SIZE: usize = 22;
+
+/// Cue sheet entry track number of the lead-out
+const LEAD_OUT: u8 = 0xAA;
+
+/// A track whose address may still be unknown.
+struct Pending {
+    track: Track,
+    /// Address from a `CUEX` or `ETN` chunk
+    start: Option<u32>,
+    /// Sectors of pregap stored before the track
+    pregap: u32,
+}
+
+/// Reads the track layout of an image of `size` bytes.
+pub fn parse<D: Read + ?Sized>(device: &D, size: u64) -> Option<Vec<Track>> {
+    let mut position = chunks_position(device, size)?;
+
+    let mut pending: Vec<Pending> = Vec::new();
+    let mut cue: Vec<(u8, u32)> = Vec::new();
+    let mut session_sizes: Vec<u32> = Vec::new();
+
+    for _ in 0..MAX_CHUNKS {
+        let mut header = [0u8; 8];
+
+        device.read(position, &mut header)?;
+
+        let length = u32::from_be_bytes(header[4..8].try_into().ok()?);
+
+        if &header[..4] == b"END!" {
+            break;
+        }
+
+        if length > MAX_CHUNK_SIZE {
+            return None;
+        }
+
+        let mut data = vec![0u8; length as usize];
+
+        device.read(position + 8, &mut data)?;
+
+        match &header[..4] {
+            b"CUEX" => {
+                // control/address, track and index in BCD, reserved, address
+                for entry in data.chunks_exact(8) {
+                    if entry[1] != LEAD_OUT && from_bcd(entry[2]) == 1 {
+                        cue.push((from_bcd(entry[1]), i32::from_be_bytes(entry[4..8].try_into().ok()?).max(0) as u32));
+                    }
+                }
+            }
+            id @ (b"DAOX" | b"DAOI") => {
+                // ISRC, sector size, mode, reserved, then pregap, start and end offsets
+                let wide = id == b"DAOX";
+                let block_size = if wide { 42 } else { 30 };
+                let first_track = *data.get(20)?;
+
+                for (index, block) in data.get(DAO_HEADER_SIZE..)?.chunks_exact(block_size).enumerate() {
+                    let offset = |number: usize| -> Option<u64> {
+                        Some(if wide {
+                            u64::from_be_bytes(block.get(18 + number * 8..26 + number * 8)?.try_into().ok()?)
+                        } else {
+                            u32::from_be_bytes(block.get(18 + number * 4..22 + number * 4)?.try_into().ok()?) as u64
+                        })
+                    };
+
+                    let sector_size = u16::from_be_bytes([block[12], block[13]]) as u32;
+                    let mode = mode(block[14]).map_or(TrackMode::Audio, |(mode, _)| mode);
+                    let (pregap, start, end) = (offset(0)?, offset(1)?, offset(2)?);
+
+                    if sector_size == 0 || start < pregap || end < start {
+                        return None;
+                    }
+
+                    pending.push(Pending {
+                        track: Track {
+                            number: first_track.wrapping_add(index as u8),
+                            session: 1,
+                            mode,
+                            start: 0,
+                            length: ((end - start) / sector_size as u64) as u32,
+                            position: start,
+                            sector_size,
+                        },
+                        start: None,
+                        pregap: ((start - pregap) / sector_size as u64) as u32,
+                    });
+                }
+            }
+            id @ (b"ETN2" | b"ETNF") => {
+                // offset, size, mode, address, reserved
+                let wide = id == b"ETN2";
+                let block_size = if wide { 32 } else { 20 };
+
+                for block in data.chunks_exact(block_size) {
+                    let (offset, length, rest) = if wide {
+                        (
+                            u64::from_be_bytes(block[0..8].try_into().ok()?),
+                            u64::from_be_bytes(block[8..16].try_into().ok()?),
+                            &block[16..],
+                        )
+                    } else {
+                        (
+                            u32::from_be_bytes(block[0..4].try_into().ok()?) as u64,
+                            u32::from_be_bytes(block[4..8].try_into().ok()?) as u64,
+                            &block[8..],
+                        )
+                    };
+
+                    let (mode, sector_size) = mode(rest[3])?;
+
+                    pending.push(Pending {
+                        track: Track {
+                            number: pending.len() as u8 + 1,
+                            session: 1,
+                            mode,
+                            start: 0,
+                            length: (length / sector_size as u64) as u32,
+                            position: offset,
+                            sector_size,
+                        },
+                        start: Some(u32::from_be_bytes(rest[4..8].try_into().ok()?)),
+                        pregap: 0,
+                    });
+                }
+            }
+            b"SINF" => session_sizes.push(u32::from_be_bytes(data.get(..4)?.try_into().ok()?)),
+            _ => {}
+        }
+
+        position += 8 + length as u64;
+    }
+
+    // Addresses missing from the cue sheet follow the previous track and the stored pregap
+    let mut next = None;
+    let mut sessions = session_sizes.iter().copied().filter(|size| *size != 0);
+    let mut session = (1, sessions.next().unwrap_or(u32::MAX));
+    let mut tracks = Vec::with_capacity(pending.len());
+
+    for Pending { mut track, start, pregap } in pending {
+        let cued = cue.iter().find(|(number, _)| *number == track.number).map(|(_, address)| *address);
+
+        track.start = match start.or(cued) {
+            Some(start) => start,
+            None => next.map_or(Some(0), |next: u32| next.checked_add(pregap))?,
+        };
+        next = Some(track.end());
+
+        if session.1 == 0 {
+            session = (session.0 + 1, sessions.next().unwrap_or(u32::MAX));
+        }
+
+        track.session = session.0;
+        session.1 -= 1;
+
+        tracks.push(track);
+    }
+
+    Some(tracks)
+}
+
+/// Position of the chunk list, from the footer.
+fn chunks_position<D: Read + ?Sized>(device: &D, size: u64) -> Option<u64> {
+    let mut footer = [0u8; 12];
+
+    device.read(size.checked_sub(12)?, &mut footer)?;
+
+    if &footer[..4] == b"NER5" {
+        Some(u64::from_be_bytes(footer[4..12].try_into().ok()?))
+    } else if &footer[4..8] == b"NERO" {
+        Some(u32::from_be_bytes(footer[8..12].try_into().ok()?) as u64)
+    } else {
+        None
+    }
+}
+
+/// Content and stored sector size of a Nero track mode.
+fn mode(code: u8) -> Option<(TrackMode, u32)> {
+    Some(match code {
+        0x00 => (TrackMode::Mode1, 2048),
+        0x02 => (TrackMode::Mode2Form1, 2048),
+        0x03 => (TrackMode::Mode2, 2336),
+        0x05 => (TrackMode::Mode1, 2352),
+        0x06 => (TrackMode::Mode2, 2352),
+        0x07 => (TrackMode::Audio, 2352),
+        0x0F => (TrackMode::Mode1, 2448),
+        0x10 => (TrackMode::Audio, 2448),
+        0x11 => (TrackMode::Mode2, 2448),
+        _ => return None,
+    })
+}
@@ -0,0 +1,202 @@
+//! El Torito boot catalog.
+//!
+//! https://pdos.csail.mit.edu/6.828/2014/readings/boot-cdrom.pdf
+
+use crate::DISK_SECTOR_SIZE;
+
+/// Boot system identifier of an El Torito boot record
+pub const BOOT_SYSTEM_ID: &[u8] = b"EL TORITO SPECIFICATION";
+
+const ENTRY_SIZE: usize = 32;
+
+const HEADER_VALIDATION: u8 = 0x01;
+const HEADER_SECTION: u8 = 0x90;
+const HEADER_FINAL_SECTION: u8 = 0x91;
+const ENTRY_BOOTABLE: u8 = 0x88;
+const ENTRY_EXTENSION: u8 = 0x44;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    X86,
+    PowerPC,
+    Mac,
+    Efi,
+    Other(u8),
+}
+
+impl From<u8> for Platform {
+    fn from(value: u8) -> Self {
+        match value {
+            0x00 => Self::X86,
+            0x01 => Self::PowerPC,
+            0x02 => Self::Mac,
+            0xef => Self::Efi,
+            other => Self::Other(other),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Emulation {
+    NoEmulation,
+    Floppy1200,
+    Floppy1440,
+    Floppy2880,
+    HardDisk,
+    Other(u8),
+}
+
+impl From<u8> for Emulation {
+    fn from(value: u8) -> Self {
+        match value & 0x0f {
+            0 => Self::NoEmulation,
+            1 => Self::Floppy1200,
+            2 => Self::Floppy1440,
+            3 => Self::Floppy2880,
+            4 => Self::HardDisk,
+            other => Self::Other(other),
+        }
+    }
+}
+
+/// Default or section entry of the catalog
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BootEntry {
+    pub platform: Platform,
+    pub bootable: bool,
+    pub emulation: Emulation,
+    /// Real mode segment the image is loaded to, 0 means the default 0x7C0
+    pub load_segment: u16,
+    /// Partition type of an emulated hard disk
+    pub system_type: u8,
+    /// Number of 512-byte sectors loaded by the BIOS
+    pub sector_count: u16,
+    /// First sector (2048 bytes) of the image
+    pub load_rba: u32,
+}
+
+impl BootEntry {
+    fn parse(platform: Platform, data: &[u8]) -> Self {
+        Self {
+            platform,
+            bootable: data[0] == ENTRY_BOOTABLE,
+            emulation: Emulation::from(data[1]),
+            load_segment: u16::from_le_bytes([data[2], data[3]]),
+            system_type: data[4],
+            sector_count: u16::from_le_bytes([data[6], data[7]]),
+            load_rba: u32::from_le_bytes([data[8], data[9], data[10], data[11]]),
+        }
+    }
+
+    /// Size of the image as loaded by the BIOS, in bytes.
+    ///
+    /// For no emulation entries this is just what gets loaded, the real image may be larger.
+    pub fn load_size(&self) -> u64 {
+        match self.emulation {
+            Emulation::Floppy1200 => 1200 * 1024,
+            Emulation::Floppy1440 => 1440 * 1024,
+            Emulation::Floppy2880 => 2880 * 1024,
+            _ => self.sector_count as u64 * 512,
+        }
+    }
+}
+
+/// Boot catalog read from its sector
+pub struct BootCatalog {
+    /// Sector of the catalog
+    pub lba: u32,
+    data: [u8; DISK_SECTOR_SIZE],
+}
+
+impl BootCatalog {
+    /// Checks the validation entry of the catalog.
+    pub(crate) fn new(lba: u32, data: [u8; DISK_SECTOR_SIZE]) -> Option<Self> {
+        let validation = &data[..ENTRY_SIZE];
+
+        if validation[0] != HEADER_VALIDATION || validation[30..32] != [0x55, 0xAA] {
+            return None;
+        }
+
+        // All words of the validation entry must sum up to zero.
+        let checksum = validation
+            .chunks_exact(2)
+            .fold(0u16, |sum, word| sum.wrapping_add(u16::from_le_bytes([word[0], word[1]])));
+
+        if checksum != 0 {
+            return None;
+        }
+
+        Some(Self { lba, data })
+    }
+
+    /// Platform of the default entry
+    pub fn platform(&self) -> Platform {
+        Platform::from(self.data[1])
+    }
+
+    /// Manufacturer of the CD
+    pub fn id(&self) -> &[u8] {
+        &self.data[4..28]
+    }
+
+    /// The default entry followed by all section entries
+    pub fn entries(&self) -> BootEntries<'_> {
+        BootEntries {
+            catalog: self,
+            offset: ENTRY_SIZE,
+            platform: self.platform(),
+            section_left: 0,
+            last_section: false,
+        }
+    }
+}
+
+pub struct BootEntries<'catalog> {
+    catalog: &'catalog BootCatalog,
+    offset: usize,
+    platform: Platform,
+    /// Entries left in the current section
+    section_left: u16,
+    last_section: bool,
+}
+
+impl Iterator for BootEntries<'_> {
+    type Item = BootEntry;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let data = self.catalog.data.get(self.offset..self.offset + ENTRY_SIZE)?;
+            let default = self.offset == ENTRY_SIZE;
+
+            self.offset += ENTRY_SIZE;
+
+            if default {
+                return Some(BootEntry::parse(self.platform, data));
+            }
+
+            // Extension records belong to the previous entry.
+            if data[0] == ENTRY_EXTENSION {
+                continue;
+            }
+
+            if self.section_left > 0 {
+                self.section_left -= 1;
+
+                return Some(BootEntry::parse(self.platform, data));
+            }
+
+            if self.last_section {
+                return None;
+            }
+
+            match data[0] {
+                HEADER_SECTION | HEADER_FINAL_SECTION => {
+                    self.platform = Platform::from(data[1]);
+                    self.section_left = u16::from_le_bytes([data[2], data[3]]);
+                    self.last_section = data[0] == HEADER_FINAL_SECTION;
+                }
+                _ => return None,
+            }
+        }
+    }
+}
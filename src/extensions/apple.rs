@@ -0,0 +1,48 @@
+// Apple extensions to ISO 9660: Finder info in `AA` system use entries (`BA` on older discs),
+// resource forks in associated files recorded right before the file they belong to.
+
+/// System use ID of entries carrying HFS Finder info, ProDOS ones (1) are ignored
+const SYSTEM_USE_HFS: u8 = 2;
+
+/// Type, creator and flags, 4 bytes of header before them
+const ENTRY_LENGTH: usize = 14;
+
+const FINDER_INVISIBLE: u16 = 0x4000;
+
+/// Finder information of a file or folder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FinderInfo {
+    /// Four-character type code such as `TEXT` or `APPL`, zeros for folders
+    pub file_type: [u8; 4],
+    /// Four-character code of the application that created the file
+    pub creator: [u8; 4],
+    /// Finder flags, 0x4000 marks invisible files
+    pub flags: u16,
+}
+
+impl FinderInfo {
+    pub fn is_invisible(&self) -> bool {
+        self.flags & FINDER_INVISIBLE != 0
+    }
+}
+
+/// Finder info of the `AA` or `BA` entry of a system use area.
+pub fn finder_info(data: &[u8]) -> Option<FinderInfo> {
+    let mut rest = super::rock_ridge::entries(data);
+
+    while let [first, second, length, ..] = *rest {
+        let entry = rest.get(..length as usize)?;
+
+        if matches!(&[first, second], b"AA" | b"BA") && entry.len() >= ENTRY_LENGTH && entry[3] == SYSTEM_USE_HFS {
+            return Some(FinderInfo {
+                file_type: entry[4..8].try_into().ok()?,
+                creator: entry[8..12].try_into().ok()?,
+                flags: u16::from_be_bytes([entry[12], entry[13]]),
+            });
+        }
+
+        rest = &rest[length as usize..];
+    }
+
+    None
+}
@@ -1,24 +1,41 @@
-use alloc::borrow::ToOwned;
-use alloc::vec;
+#[cfg(feature = "alloc")]
 use alloc::string::String;
-use zerocopy::FromBytes;
 
-pub(crate) fn parse_name(ucs2_name: &[u8]) -> Option<String> {
+/// Characters of a UCS-2 name, unpaired surrogates replaced by U+FFFD and a trailing odd byte dropped.
+fn chars(ucs2_name: &[u8]) -> impl Iterator<Item = char> + '_ {
+    let units = ucs2_name
+        .chunks_exact(2)
+        .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+        .take_while(|&unit| unit != 0);
+
+    char::decode_utf16(units).map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+}
+
+/// Decodes a Joliet file identifier (UCS-2, big-endian).
+#[cfg(feature = "alloc")]
+pub(crate) fn parse_name(ucs2_name: &[u8]) -> String {
     if ucs2_name.len() == 1 {
         if ucs2_name[0] == 0 {
-            return Some(String::from("."));
+            return String::from(".");
         } else if ucs2_name[0] == 1 {
-            return Some(String::from(".."));
+            return String::from("..");
         }
     }
 
-    debug_assert!(ucs2_name.len() % 2 == 0, "The length of UCS-2 name must be a multiple of two");
+    chars(ucs2_name).collect()
+}
 
-    let mut utf8_str_buf = vec![0u8; ucs2_name.len()];
+/// Decodes a Joliet file identifier into `buffer` as UTF-8.
+/// Returns `None` if the name doesn't fit.
+pub(crate) fn parse_name_into<'a>(ucs2_name: &[u8], buffer: &'a mut [u8]) -> Option<&'a str> {
+    let mut length = 0;
 
-    let utf16_buf: &[u16] = FromBytes::ref_from_bytes(ucs2_name).unwrap();
+    for c in chars(ucs2_name) {
+        let end = length + c.len_utf8();
 
-    ucs2::decode(utf16_buf, &mut utf8_str_buf).ok()?;
+        c.encode_utf8(buffer.get_mut(length..end)?);
+        length = end;
+    }
 
-    str::from_utf8(&utf8_str_buf).map(|x| x.trim_end_matches('\0').to_owned()).ok()
+    str::from_utf8(&buffer[..length]).ok()
 }
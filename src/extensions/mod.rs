@@ -1,2 +1,3 @@
+pub mod apple;
 pub mod rock_ridge;
 pub mod joliet;
\ No newline at end of file
@@ -1,8 +1,22 @@
 // https://people.freebsd.org/~emaste/rrip112.pdf
 
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+use core::fmt::Write;
+use zerocopy::FromBytes;
+
 pub enum Entity<'data> {
     Name {
-        name: &'data str,
+        /// Piece of the name, not necessarily valid UTF-8 on its own
+        name: &'data [u8],
+        /// The name continues in the next `NM` entry
+        continues: bool,
+    },
+    Continuation {
+        /// Block, offset and length of the continuation area
+        block: u32,
+        offset: u32,
+        length: u32,
     },
     PosixAttributes {
         posix_file_mode: u32,
@@ -11,8 +25,84 @@ pub enum Entity<'data> {
         posix_file_group_id: u32,
         posix_file_serial_number: u32,
     },
+    SymbolicLink {
+        /// The link target continues in the next `SL` entry
+        continues: bool,
+        /// Raw component records, see [`symlink_target`]
+        components: &'data [u8],
+    },
+    Timestamps {
+        /// Which timestamps are recorded (`TF_*`) and their format
+        flags: u8,
+        /// Recorded timestamps in the order of their flags
+        stamps: &'data [u8],
+    },
+    Compressed {
+        /// `pz` for zisofs, `PZ` for zisofs2
+        algorithm: [u8; 2],
+        /// Size of the file header in 4-byte units
+        header_size: u8,
+        block_size_log2: u8,
+        /// Size of the uncompressed data, 64 bits for zisofs2
+        real_size: u64,
+    },
+}
+
+/// Decoded `PX` entry.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PosixAttributes {
+    /// `st_mode`, file type bits included
+    pub mode: u32,
+    pub links: u32,
+    pub uid: u32,
+    pub gid: u32,
+    /// `st_ino`, zero on images written before RRIP 1.12
+    pub serial: u32,
 }
 
+/// Decoded `TF` entry, as seconds since the Unix epoch.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Timestamps {
+    pub creation: Option<i64>,
+    pub modify: Option<i64>,
+    pub access: Option<i64>,
+    pub attributes: Option<i64>,
+    pub backup: Option<i64>,
+    pub expiration: Option<i64>,
+    pub effective: Option<i64>,
+}
+
+/// Decoded `ZF` entry: the file data is compressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Compression {
+    pub algorithm: CompressionAlgorithm,
+    /// Size of the header at the start of the data, in bytes
+    pub header_size: u32,
+    pub block_size: u32,
+    /// Size of the uncompressed data
+    pub real_size: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionAlgorithm {
+    /// zisofs, zlib compressed blocks (`pz`)
+    Zisofs,
+    /// zisofs2, the compressor is named in the file header (`PZ`)
+    Zisofs2,
+    Other([u8; 2]),
+}
+
+const NM_CONTINUE: u8 = 1 << 0;
+const NM_CURRENT: u8 = 1 << 1;
+const NM_PARENT: u8 = 1 << 2;
+
+const TF_LONG_FORM: u8 = 1 << 7;
+
+const SL_COMPONENT_CONTINUE: u8 = 1 << 0;
+const SL_COMPONENT_CURRENT: u8 = 1 << 1;
+const SL_COMPONENT_PARENT: u8 = 1 << 2;
+const SL_COMPONENT_ROOT: u8 = 1 << 3;
+
 pub struct RockRidgeParser<'data> {
     data: &'data [u8],
     index: usize
@@ -35,100 +125,134 @@ impl<'data> Iterator for RockRidgeParser<'data> {
             let identifier = &self.data[self.index..=self.index + 1];
             let length = self.data[self.index + 2] as usize;
 
-            // println!("ID: {:x?}; Len: {}", identifier, length);
+            // An entry is at least its 4-byte header and must fit in the area,
+            // anything else is padding or garbage and ends the area.
+            if length < 4 || self.index + length > self.data.len() {
+                return None;
+            }
 
             match identifier {
                 b"SP" => {
                     // IDK what `SP` is and there's no definition in the Rock Ridge spec, so skip this entity.
                     self.index += 7;
 
-                    return self.next();
+                    self.next()
                 }
                 b"PX" => {
-                    // WTF???
-                    // Extract data
-                    // let system_use_entry_version = data[index + 4];
-                    let posix_file_mode = &self.data[self.index + 5..=self.index + 12];
-                    let posix_file_links = &self.data[self.index + 13..=self.index + 20];
-                    let posix_file_user_id = &self.data[self.index + 21..=self.index + 28];
-                    let posix_file_group_id = &self.data[self.index + 29..=self.index + 36];
-                    let posix_file_serial_number = &self.data[self.index + 37..=self.index + 44];
-
-                    // First 4 bytes are needed, because each entry here is a (LSB-MSB) pair.
-                    let posix_file_mode: u32 =
-                        u32::from_le_bytes(posix_file_mode[..4].try_into().unwrap());
-                    let posix_file_links: u32 =
-                        u32::from_le_bytes(posix_file_links[..4].try_into().unwrap());
-                    let posix_file_user_id: u32 =
-                        u32::from_le_bytes(posix_file_user_id[..4].try_into().unwrap());
-                    let posix_file_group_id: u32 =
-                        u32::from_le_bytes(posix_file_group_id[..4].try_into().unwrap());
-                    let posix_file_serial_number: u32 =
-                        u32::from_le_bytes(posix_file_serial_number[..4].try_into().unwrap());
+                    // Each field is a (LSB-MSB) pair, the serial number only exists since RRIP 1.12.
+                    let field = |index: usize| -> Option<u32> {
+                        let start = self.index + 4 + index * 8;
+
+                        if start + 8 > self.index + length {
+                            return None;
+                        }
+
+                        Some(u32::from_le_bytes(self.data.get(start..start + 4)?.try_into().ok()?))
+                    };
+
+                    let posix_file_mode = field(0)?;
+                    let posix_file_links = field(1)?;
+                    let posix_file_user_id = field(2)?;
+                    let posix_file_group_id = field(3)?;
+                    let posix_file_serial_number = field(4).unwrap_or(0);
 
                     self.index += length;
-                        
-                    return Some(Entity::PosixAttributes {
+
+                    Some(Entity::PosixAttributes {
                         posix_file_mode,
                         posix_file_links,
                         posix_file_user_id,
                         posix_file_group_id,
                         posix_file_serial_number,
-                    });
+                    })
                 }
                 b"TF" => {
-                    // let system_use_entry_version = data[index + 4];
-                    // let flags = data[index + 5];
+                    let flags = *self.data.get(self.index + 4)?;
+                    let stamps = self.data.get(self.index + 5..self.index + length)?;
 
                     self.index += length;
-                    return self.next();
+
+                    Some(Entity::Timestamps { flags, stamps })
+                }
+                b"ZF" => {
+                    let data = self.data.get(self.index..self.index + 16)?;
+
+                    self.index += length;
+
+                    // zisofs has a (LSB-MSB) 32-bit size, zisofs2 a little endian 64-bit one.
+                    let real_size = match &data[4..6] {
+                        b"PZ" => u64::from_le_bytes(data[8..16].try_into().ok()?),
+                        _ => u32::from_le_bytes(data[8..12].try_into().ok()?) as u64,
+                    };
+
+                    Some(Entity::Compressed {
+                        algorithm: [data[4], data[5]],
+                        header_size: data[6],
+                        block_size_log2: data[7],
+                        real_size,
+                    })
                 }
                 b"CE" => {
-                    // Just skip it
+                    let field = |index: usize| -> Option<u32> {
+                        let start = self.index + 4 + index * 8;
+
+                        Some(u32::from_le_bytes(self.data.get(start..start + 4)?.try_into().ok()?))
+                    };
+
+                    let (block, offset, area_length) = (field(0)?, field(1)?, field(2)?);
 
                     self.index += length;
-                    return self.next();
+
+                    Some(Entity::Continuation {
+                        block,
+                        offset,
+                        length: area_length,
+                    })
                 }
                 b"AL" => {
                     // Do I know what the hell is this?
 
                     self.index += length;
-                    return self.next();
+                    self.next()
                 }
                 b"NM" => {
-                    // let system_use_entry_version = data[index + 4];
-                    let flags = self.data[self.index + 4];
+                    let flags = *self.data.get(self.index + 4)?;
+                    let name = self.data.get(self.index + 5..self.index + length)?;
 
-                    if (flags & (1 << 1)) != 0 {
-                        self.index += length;
-
-                        return Some(Entity::Name { name: "." });
-                    }
-
-                    if (flags & (1 << 2)) != 0 {
-                        self.index += length;
-                        
-                        return Some(Entity::Name { name: ".." });
-                    }
+                    self.index += length;
 
-                    let start = self.index + 5;
-                    let end = self.index + length;
+                    let name: &[u8] = if (flags & NM_CURRENT) != 0 {
+                        b"."
+                    } else if (flags & NM_PARENT) != 0 {
+                        b".."
+                    } else {
+                        name
+                    };
 
-                    let name = &self.data[start..end];
+                    Some(Entity::Name {
+                        name,
+                        continues: (flags & NM_CONTINUE) != 0,
+                    })
+                }
+                b"SL" => {
+                    let flags = *self.data.get(self.index + 4)?;
+                    let components = self.data.get(self.index + 5..self.index + length)?;
 
                     self.index += length;
 
-                    return Some(Entity::Name {
-                        name: str::from_utf8(name).unwrap(),
-                    });
+                    Some(Entity::SymbolicLink {
+                        continues: (flags & 1) != 0,
+                        components,
+                    })
+                }
+                b"ST" => {
+                    // System use sharing terminator, nothing meaningful follows.
+                    None
                 }
                 &_ => {
-                    todo!(
-                        "Implement entity: {} ({}, {})",
-                        str::from_utf8(identifier).unwrap(),
-                        self.data[self.index],
-                        self.data[self.index + 1]
-                    );
+                    // Entries we don't interpret (RR, CL, PL, RE, ER, ...) are skipped.
+                    self.index += length;
+                    self.next()
                 }
             }
         } else {
@@ -137,6 +261,271 @@ impl<'data> Iterator for RockRidgeParser<'data> {
     }
 }
 
+/// Valid prefix of a system use area: whole entries up to the `ST` terminator or the padding.
+pub fn entries(data: &[u8]) -> &[u8] {
+    let mut index = 0;
+
+    while index + 4 <= data.len() {
+        let length = data[index + 2] as usize;
+
+        if &data[index..index + 2] == b"ST" || length < 4 || index + length > data.len() {
+            break;
+        }
+
+        index += length;
+    }
+
+    &data[..index]
+}
+
+/// Position and length of the continuation area pointed to by the `CE` entry of a system use area.
+pub fn continuation(data: &[u8]) -> Option<(u64, usize)> {
+    parse(data).find_map(|entity| match entity {
+        Entity::Continuation { block, offset, length } => {
+            Some((block as u64 * crate::DISK_SECTOR_SIZE as u64 + offset as u64, length as usize))
+        }
+        _ => None,
+    })
+}
+
+/// Copies the name assembled from all `NM` entries into `buffer`, returns its length.
+/// `None` if there is no name, `Some(None)` if it doesn't fit.
+pub(crate) fn copy_name(data: &[u8], buffer: &mut [u8]) -> Option<Option<usize>> {
+    let mut length = 0;
+    let mut found = false;
+
+    for entity in parse(data) {
+        let Entity::Name { name, continues } = entity else {
+            continue;
+        };
+
+        found = true;
+
+        let Some(target) = buffer.get_mut(length..length + name.len()) else {
+            return Some(None);
+        };
+
+        target.copy_from_slice(name);
+        length += name.len();
+
+        if !continues {
+            break;
+        }
+    }
+
+    found.then_some(Some(length))
+}
+
+/// Name assembled from all `NM` entries of a system use area, written into `buffer`.
+/// Returns `None` if there is no name, it doesn't fit or isn't valid UTF-8.
+pub fn name_into<'a>(data: &[u8], buffer: &'a mut [u8]) -> Option<&'a str> {
+    let length = copy_name(data, buffer)??;
+
+    str::from_utf8(&buffer[..length]).ok()
+}
+
+/// Name assembled from all `NM` entries of a system use area.
+#[cfg(feature = "alloc")]
+pub fn name(data: &[u8]) -> Option<String> {
+    let mut name = alloc::vec::Vec::new();
+    let mut found = false;
+
+    for entity in parse(data) {
+        let Entity::Name { name: piece, continues } = entity else {
+            continue;
+        };
+
+        found = true;
+        name.extend_from_slice(piece);
+
+        if !continues {
+            break;
+        }
+    }
+
+    found.then(|| String::from_utf8_lossy(&name).into_owned())
+}
+
+/// Finds the `PX` entry of a system use area.
+pub fn posix_attributes(data: &[u8]) -> Option<PosixAttributes> {
+    parse(data).find_map(|entity| match entity {
+        Entity::PosixAttributes {
+            posix_file_mode,
+            posix_file_links,
+            posix_file_user_id,
+            posix_file_group_id,
+            posix_file_serial_number,
+        } => Some(PosixAttributes {
+            mode: posix_file_mode,
+            links: posix_file_links,
+            uid: posix_file_user_id,
+            gid: posix_file_group_id,
+            serial: posix_file_serial_number,
+        }),
+        _ => None,
+    })
+}
+
+/// Finds the `ZF` entry of a system use area.
+pub fn compression(data: &[u8]) -> Option<Compression> {
+    parse(data).find_map(|entity| match entity {
+        Entity::Compressed {
+            algorithm,
+            header_size,
+            block_size_log2,
+            real_size,
+        } => Some(Compression {
+            algorithm: match &algorithm {
+                b"pz" => CompressionAlgorithm::Zisofs,
+                b"PZ" => CompressionAlgorithm::Zisofs2,
+                _ => CompressionAlgorithm::Other(algorithm),
+            },
+            header_size: header_size as u32 * 4,
+            block_size: 1u32.checked_shl(block_size_log2 as u32)?,
+            real_size,
+        }),
+        _ => None,
+    })
+}
+
+/// Collects the timestamps of all `TF` entries of a system use area.
+pub fn timestamps(data: &[u8]) -> Timestamps {
+    let mut result = Timestamps::default();
+
+    for entity in parse(data) {
+        let Entity::Timestamps { flags, stamps } = entity else {
+            continue;
+        };
+
+        let size = if (flags & TF_LONG_FORM) != 0 { 17 } else { 7 };
+        let mut chunks = stamps.chunks_exact(size);
+
+        let slots = [
+            &mut result.creation,
+            &mut result.modify,
+            &mut result.access,
+            &mut result.attributes,
+            &mut result.backup,
+            &mut result.expiration,
+            &mut result.effective,
+        ];
+
+        for (bit, slot) in slots.into_iter().enumerate() {
+            if (flags & (1 << bit)) == 0 {
+                continue;
+            }
+
+            let Some(stamp) = chunks.next() else {
+                break;
+            };
+
+            *slot = if size == 17 {
+                crate::types::dec_datetime(stamp)
+            } else {
+                crate::ISODateTime::read_from_bytes(stamp).ok().map(|time| time.unix_time())
+            };
+        }
+    }
+
+    result
+}
+
+/// Writes the target of a symbolic link assembled from all `SL` entries of a system use area.
+/// Returns `Some(false)` if there are no `SL` entries, `None` if they are malformed or `out` fails.
+fn write_symlink_target(data: &[u8], out: &mut impl Write) -> Option<bool> {
+    let mut found = false;
+    let mut empty = true;
+    let mut glue = false;
+
+    for entity in parse(data) {
+        let Entity::SymbolicLink { components, .. } = entity else {
+            continue;
+        };
+
+        found = true;
+
+        let mut index = 0;
+
+        while index + 1 < components.len() {
+            let flags = components[index];
+            let length = components[index + 1] as usize;
+            let content = components.get(index + 2..index + 2 + length)?;
+
+            index += 2 + length;
+
+            if (flags & SL_COMPONENT_ROOT) != 0 {
+                if empty {
+                    out.write_char('/').ok()?;
+                    empty = false;
+                }
+
+                glue = true;
+                continue;
+            }
+
+            if !glue && !empty {
+                out.write_char('/').ok()?;
+            }
+
+            if (flags & SL_COMPONENT_CURRENT) != 0 {
+                out.write_char('.').ok()?;
+            } else if (flags & SL_COMPONENT_PARENT) != 0 {
+                out.write_str("..").ok()?;
+            } else {
+                out.write_str(str::from_utf8(content).ok()?).ok()?;
+            }
+
+            empty = false;
+
+            // A component split over several records must not get a separator in between.
+            glue = (flags & SL_COMPONENT_CONTINUE) != 0;
+        }
+    }
+
+    Some(found)
+}
+
+/// Assembles the target of a symbolic link from all `SL` entries of a system use area.
+#[cfg(feature = "alloc")]
+pub fn symlink_target(data: &[u8]) -> Option<String> {
+    let mut target = String::new();
+
+    write_symlink_target(data, &mut target)?.then_some(target)
+}
+
+/// Same as [`symlink_target`], but the target is written into `buffer`.
+/// Returns `None` if there is no link or it doesn't fit.
+pub fn symlink_target_into<'a>(data: &[u8], buffer: &'a mut [u8]) -> Option<&'a str> {
+    let mut writer = SliceWriter { buffer, length: 0 };
+
+    if !write_symlink_target(data, &mut writer)? {
+        return None;
+    }
+
+    let SliceWriter { buffer, length } = writer;
+
+    str::from_utf8(&buffer[..length]).ok()
+}
+
+struct SliceWriter<'a> {
+    buffer: &'a mut [u8],
+    length: usize,
+}
+
+impl Write for SliceWriter<'_> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let end = self.length + s.len();
+
+        self.buffer
+            .get_mut(self.length..end)
+            .ok_or(core::fmt::Error)?
+            .copy_from_slice(s.as_bytes());
+        self.length = end;
+
+        Ok(())
+    }
+}
+
 #[inline(always)]
 pub fn parse<'data>(data: &'data [u8]) -> RockRidgeParser<'data> {
     RockRidgeParser::new(data)
@@ -0,0 +1,695 @@
+//! Safe extraction of the tree to a host directory.
+//!
+//! Names come from the image as they are (Rock Ridge `NM`, Joliet or plain identifiers), so a
+//! crafted image may contain `..`, separators, control characters or links pointing outside the
+//! destination. Every component is checked before anything is written, and everything that
+//! couldn't be extracted as is ends up in the [`Report`].
+
+use std::{
+    borrow::Cow,
+    collections::HashSet,
+    fs::{self, File, FileTimes, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    helpers::get_directory_entry_by_path_with,
+    normalize::NameOptions,
+    progress::{Cancelled, Monitor, Tracker},
+    ISODirectoryEntry, Read, ISO9660,
+};
+
+/// Size of chunks used to copy file data
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// What to do with symbolic links.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    /// Don't create links at all
+    Skip,
+    /// Create links that stay inside the destination, skip the others
+    #[default]
+    RefuseEscaping,
+    /// Like [`SymlinkPolicy::RefuseEscaping`], but absolute targets are taken relative
+    /// to the destination and rewritten into relative ones
+    Rewrite,
+}
+
+/// What to do with names that clash with an already extracted one,
+/// e.g. `README` and `readme` on a case-insensitive host filesystem.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CollisionPolicy {
+    /// Keep the first entry, skip the rest
+    #[default]
+    Skip,
+    /// Extract the entry under a new name like `readme~1`
+    Rename,
+}
+
+/// Why an entry was not extracted.
+#[derive(Debug)]
+pub enum SkipReason {
+    /// A requested path doesn't exist in the image
+    NotFound,
+    /// The name is empty, `.` or `..`
+    UnsafeName,
+    /// Its directory was skipped
+    ParentSkipped,
+    /// Another entry already took the name
+    Collision,
+    /// The link points outside the destination
+    EscapingSymlink,
+    /// Links are disabled or the host can't create them
+    Symlink,
+    /// Reading the image failed
+    Read,
+    Io(io::Error),
+}
+
+#[derive(Debug)]
+pub struct Skipped {
+    /// Path inside the image
+    pub path: String,
+    pub reason: SkipReason,
+}
+
+/// Result of an extraction.
+#[derive(Debug, Default)]
+pub struct Report {
+    pub files: usize,
+    pub directories: usize,
+    pub symlinks: usize,
+    /// Bytes of file data written
+    pub bytes: u64,
+    pub skipped: Vec<Skipped>,
+    /// Entries written under another name: path inside the image and the path on disk
+    pub renamed: Vec<(String, PathBuf)>,
+    /// Links created with another target: path inside the image and the new target
+    pub rewritten_links: Vec<(String, String)>,
+    /// The run was cancelled. Files that were not complete are removed,
+    /// links and metadata of directories are not restored.
+    pub cancelled: bool,
+}
+
+impl Report {
+    fn skip(&mut self, path: &str, reason: SkipReason) {
+        self.skipped.push(Skipped {
+            path: path.into(),
+            reason,
+        });
+    }
+}
+
+/// Extracts the whole image or some of its paths, created by [`ISO9660::extract`].
+///
+/// Files are read in the order of their extents to keep seeks low. Rock Ridge modes and
+/// timestamps are restored, ownership too when permitted (i.e. when running as root).
+/// Links are created after all files, so nothing is ever written through them.
+pub struct Extractor<'iso, D: Read> {
+    pub(crate) iso: &'iso ISO9660<D>,
+    destination: PathBuf,
+    paths: Vec<String>,
+    names: NameOptions,
+    symlinks: SymlinkPolicy,
+    collisions: CollisionPolicy,
+    case_insensitive: Option<bool>,
+    permissions: bool,
+    owner: bool,
+    pub(crate) monitor: Monitor<'iso>,
+}
+
+/// Why a file was not written.
+pub(crate) enum Failure {
+    Skip(SkipReason),
+    Cancelled,
+}
+
+impl From<SkipReason> for Failure {
+    fn from(reason: SkipReason) -> Self {
+        Self::Skip(reason)
+    }
+}
+
+impl From<Cancelled> for Failure {
+    fn from(_: Cancelled) -> Self {
+        Self::Cancelled
+    }
+}
+
+pub(crate) struct PendingFile {
+    pub(crate) path: String,
+    pub(crate) entry: ISODirectoryEntry,
+    pub(crate) output: PathBuf,
+}
+
+struct PendingLink {
+    path: String,
+    entry: ISODirectoryEntry,
+    output: PathBuf,
+    target: String,
+}
+
+/// An output directory and the number of components below the destination.
+type Parent = Option<(PathBuf, usize)>;
+
+impl<'iso, D: Read> Extractor<'iso, D> {
+    pub(crate) fn new(iso: &'iso ISO9660<D>, destination: PathBuf) -> Self {
+        Self {
+            iso,
+            destination,
+            paths: Vec::new(),
+            names: NameOptions {
+                strip_version: true,
+                strip_trailing_dot: true,
+                ..NameOptions::EXACT
+            },
+            symlinks: SymlinkPolicy::default(),
+            collisions: CollisionPolicy::default(),
+            case_insensitive: None,
+            permissions: true,
+            owner: true,
+            monitor: Monitor::NONE,
+        }
+    }
+
+    /// Extracts only this path (looked up leniently, see [`NameOptions::LENIENT`]).
+    /// Can be called several times, the whole image is extracted if it's never called.
+    pub fn path(mut self, path: &str) -> Self {
+        self.paths.push(path.into());
+        self
+    }
+
+    /// How names are turned into host names. By default version suffixes and trailing dots are dropped.
+    pub fn names(mut self, options: NameOptions) -> Self {
+        self.names = options;
+        self
+    }
+
+    pub fn symlinks(mut self, policy: SymlinkPolicy) -> Self {
+        self.symlinks = policy;
+        self
+    }
+
+    pub fn collisions(mut self, policy: CollisionPolicy) -> Self {
+        self.collisions = policy;
+        self
+    }
+
+    /// Whether names differing only in case collide. Detected on the destination by default.
+    pub fn case_insensitive(mut self, value: bool) -> Self {
+        self.case_insensitive = Some(value);
+        self
+    }
+
+    /// Restore Rock Ridge modes. Set-id bits are only kept when the owner was restored too.
+    pub fn permissions(mut self, value: bool) -> Self {
+        self.permissions = value;
+        self
+    }
+
+    /// Try to restore Rock Ridge owners
+    pub fn owner(mut self, value: bool) -> Self {
+        self.owner = value;
+        self
+    }
+
+    /// Reports progress of file data and stops when cancelled.
+    pub fn monitor(mut self, monitor: Monitor<'iso>) -> Self {
+        self.monitor = monitor;
+        self
+    }
+
+    /// Extracts everything. Fails only if the destination itself can't be used,
+    /// problems with single entries are collected in the report.
+    pub fn run(self) -> io::Result<Report> {
+        let mut state = self.prepare()?;
+        let tracker = self.monitor.start(state.total_bytes());
+
+        for file in core::mem::take(&mut state.files) {
+            let result = self.write_file(&file, &tracker);
+
+            state.file_done(&file, result);
+
+            if state.report.cancelled {
+                return Ok(state.report);
+            }
+        }
+
+        Ok(self.finish(state))
+    }
+
+    /// Creates directories and plans everything else. Files are sorted by their extents.
+    pub(crate) fn prepare(&self) -> io::Result<State> {
+        fs::create_dir_all(&self.destination)?;
+
+        let case_insensitive = self
+            .case_insensitive
+            .unwrap_or_else(|| is_case_insensitive(&self.destination));
+
+        let mut state = State {
+            report: Report::default(),
+            taken: HashSet::new(),
+            case_insensitive,
+            files: Vec::new(),
+            links: Vec::new(),
+            directories: Vec::new(),
+        };
+
+        let paths = if self.paths.is_empty() {
+            vec![String::from("/")]
+        } else {
+            self.paths.clone()
+        };
+
+        for path in &paths {
+            self.collect(path, &mut state);
+        }
+
+        // Reading in the order of the data on the disc keeps seeks low.
+        state.files.sort_by_key(|file| file.entry.lsb_position());
+
+        Ok(state)
+    }
+
+    /// Creates links and restores metadata of directories once all files are written.
+    pub(crate) fn finish(&self, mut state: State) -> Report {
+        for link in core::mem::take(&mut state.links) {
+            match self.create_link(&link) {
+                Ok(()) => state.report.symlinks += 1,
+                Err(reason) => state.report.skip(&link.path, reason),
+            }
+        }
+
+        // Children change the modification time of their directory, so directories come last,
+        // deepest first in case their mode forbids writing.
+        for (path, entry, output) in state.directories.iter().rev() {
+            if let Err(error) = self.apply_metadata(entry, output, false) {
+                state.report.skip(path, SkipReason::Io(error));
+            }
+        }
+
+        state.report
+    }
+
+    /// Plans extraction of one requested path: creates directories, queues files and links.
+    fn collect(&self, path: &str, state: &mut State) {
+        let Some(entry) = get_directory_entry_by_path_with(self.iso, path, &NameOptions::LENIENT) else {
+            state.report.skip(path, SkipReason::NotFound);
+            return;
+        };
+
+        let mut components: Vec<&str> = path.split('/').filter(|v| !v.is_empty()).collect();
+
+        // A file or a link is added to its directory like any entry of a walk.
+        let last = if entry.is_folder() { None } else { components.pop() };
+
+        // Directories leading to the requested path are only sanitized, they are not part of the report.
+        let mut output = self.destination.clone();
+
+        for component in &components {
+            let Some(name) = self.host_name(component) else {
+                state.report.skip(path, SkipReason::UnsafeName);
+                return;
+            };
+
+            output.push(name.as_ref());
+
+            if let Err(error) = create_directory(&output) {
+                state.report.skip(path, SkipReason::Io(error));
+                return;
+            }
+        }
+
+        if let Some(name) = last {
+            let target = entry.symlink_target();
+
+            self.add(path, entry, target, name, &Some((output, components.len())), state);
+            return;
+        }
+
+        // The destination itself keeps its metadata when the whole image is extracted.
+        if !components.is_empty() {
+            state.directories.push((path.into(), entry.clone(), output.clone()));
+        }
+
+        let mut parents: Vec<Parent> = vec![Some((output, components.len()))];
+
+        for item in self.iso.walk_from(&entry, path) {
+            parents.truncate(item.depth);
+
+            let parent = parents.last().cloned().flatten();
+            let is_folder = item.entry.is_folder() && item.symlink_target.is_none();
+
+            let created = if parent.is_some() {
+                let name = item.entry.name.clone();
+
+                self.add(&item.path, item.entry, item.symlink_target, &name, &parent, state)
+            } else {
+                state.report.skip(&item.path, SkipReason::ParentSkipped);
+                None
+            };
+
+            if is_folder {
+                parents.push(created);
+            }
+        }
+    }
+
+    /// Checks the name of one entry and creates or queues it.
+    /// Returns the output path of a created directory.
+    fn add(
+        &self,
+        path: &str,
+        entry: ISODirectoryEntry,
+        target: Option<String>,
+        name: &str,
+        parent: &Parent,
+        state: &mut State,
+    ) -> Parent {
+        let (parent, depth) = parent.clone()?;
+
+        let Some(host_name) = self.host_name(name) else {
+            state.report.skip(path, SkipReason::UnsafeName);
+            return None;
+        };
+
+        let Some(output) = state.claim(&parent, &host_name, self.collisions) else {
+            state.report.skip(path, SkipReason::Collision);
+            return None;
+        };
+
+        if output.file_name().is_some_and(|file_name| file_name != host_name.as_ref())
+            || host_name != self.names.normalize(name)
+        {
+            state.report.renamed.push((path.into(), output.clone()));
+        }
+
+        if let Some(target) = target {
+            match self.link_target(&target, depth) {
+                Some(new_target) => {
+                    if new_target != target {
+                        state.report.rewritten_links.push((path.into(), new_target.clone()));
+                    }
+
+                    state.links.push(PendingLink {
+                        path: path.into(),
+                        entry,
+                        output,
+                        target: new_target,
+                    });
+                }
+                None if self.symlinks == SymlinkPolicy::Skip => state.report.skip(path, SkipReason::Symlink),
+                None => state.report.skip(path, SkipReason::EscapingSymlink),
+            }
+
+            return None;
+        }
+
+        if entry.is_folder() {
+            if let Err(error) = create_directory(&output) {
+                state.report.skip(path, SkipReason::Io(error));
+                return None;
+            }
+
+            state.report.directories += 1;
+            state.directories.push((path.into(), entry, output.clone()));
+
+            return Some((output, depth + 1));
+        }
+
+        state.files.push(PendingFile {
+            path: path.into(),
+            entry,
+            output,
+        });
+
+        None
+    }
+
+    /// Turns a name from the image into a safe host name, `None` if it can't be made safe.
+    fn host_name<'a>(&self, name: &'a str) -> Option<Cow<'a, str>> {
+        let name = self.names.normalize(name);
+
+        if matches!(name.as_ref(), "" | "." | "..") {
+            return None;
+        }
+
+        let unsafe_char = |c: char| {
+            c == '/' || c == '\\' || c.is_control() || (cfg!(windows) && matches!(c, ':' | '*' | '?' | '"' | '<' | '>' | '|'))
+        };
+
+        if !name.contains(unsafe_char) {
+            return Some(name);
+        }
+
+        Some(Cow::Owned(name.replace(unsafe_char, "_")))
+    }
+
+    /// Checks a link target, `depth` is the number of components between the destination
+    /// and the directory containing the link. `None` if the link must not be created.
+    fn link_target(&self, target: &str, depth: usize) -> Option<String> {
+        if self.symlinks == SymlinkPolicy::Skip || target.is_empty() {
+            return None;
+        }
+
+        let absolute = target.starts_with('/');
+
+        if absolute && self.symlinks != SymlinkPolicy::Rewrite {
+            return None;
+        }
+
+        // `..` after a regular component depends on what that component is (it may be a link
+        // itself), so such targets are never considered safe.
+        let mut level = if absolute { 0 } else { depth };
+        let mut regular = false;
+        let mut components = Vec::new();
+
+        for component in target.split('/') {
+            match component {
+                "" | "." => {}
+                ".." if regular => return None,
+                ".." => level = level.checked_sub(1)?,
+                other => {
+                    regular = true;
+                    components.push(other);
+                }
+            }
+        }
+
+        if !absolute {
+            return Some(target.into());
+        }
+
+        let mut rewritten = "../".repeat(depth);
+        rewritten.push_str(&components.join("/"));
+
+        if rewritten.is_empty() {
+            rewritten.push('.');
+        }
+
+        Some(rewritten.trim_end_matches('/').into())
+    }
+
+    fn write_file(&self, file: &PendingFile, tracker: &Tracker) -> Result<(), Failure> {
+        tracker.check()?;
+
+        let mut output = create_file(&file.output).map_err(SkipReason::Io)?;
+
+        let size = file.entry.file_size();
+        let mut buffer = vec![0u8; CHUNK_SIZE.min(usize::try_from(size).unwrap_or(usize::MAX))];
+        let mut offset = 0;
+
+        while offset < size {
+            tracker.check()?;
+
+            let length = (size - offset).min(buffer.len() as u64) as usize;
+            let chunk = &mut buffer[..length];
+
+            self.iso.read_file(&file.entry, offset, chunk).ok_or(SkipReason::Read)?;
+            output.write_all(chunk).map_err(SkipReason::Io)?;
+
+            offset += length as u64;
+            tracker.advance(length as u64, &file.path);
+        }
+
+        drop(output);
+
+        self.apply_metadata(&file.entry, &file.output, false).map_err(SkipReason::Io)?;
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    fn create_link(&self, link: &PendingLink) -> Result<(), SkipReason> {
+        replace_link(&link.output).map_err(SkipReason::Io)?;
+        std::os::unix::fs::symlink(&link.target, &link.output).map_err(SkipReason::Io)?;
+
+        self.apply_metadata(&link.entry, &link.output, true).map_err(SkipReason::Io)
+    }
+
+    #[cfg(not(unix))]
+    fn create_link(&self, _link: &PendingLink) -> Result<(), SkipReason> {
+        Err(SkipReason::Symlink)
+    }
+
+    /// Restores timestamps, ownership and mode.
+    ///
+    /// Times are set first, as the restored mode may forbid opening the output again.
+    /// Directories are only passed here once their children are written.
+    pub(crate) fn apply_metadata(&self, entry: &ISODirectoryEntry, output: &Path, symlink: bool) -> io::Result<()> {
+        // The standard library can't change times of a link itself.
+        if !symlink {
+            let modified = system_time(entry.modified());
+            let accessed = entry.timestamps().access.map_or(modified, system_time);
+
+            File::open(output)?.set_times(FileTimes::new().set_modified(modified).set_accessed(accessed))?;
+        }
+
+        #[cfg(unix)]
+        if let Some(attributes) = entry.posix_attributes() {
+            use std::os::unix::fs::PermissionsExt;
+
+            let mut owned = false;
+
+            if self.owner {
+                match std::os::unix::fs::lchown(output, Some(attributes.uid), Some(attributes.gid)) {
+                    Ok(()) => owned = true,
+                    Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {}
+                    Err(e) => return Err(e),
+                }
+            }
+
+            if self.permissions && !symlink {
+                let mask = if owned { 0o7777 } else { 0o777 };
+
+                fs::set_permissions(output, fs::Permissions::from_mode(attributes.mode & mask))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub(crate) struct State {
+    pub(crate) report: Report,
+    /// Output paths already used, lowercased on case-insensitive hosts
+    taken: HashSet<PathBuf>,
+    case_insensitive: bool,
+    pub(crate) files: Vec<PendingFile>,
+    links: Vec<PendingLink>,
+    directories: Vec<(String, ISODirectoryEntry, PathBuf)>,
+}
+
+impl State {
+    /// Counts a written file, or removes what was left of it.
+    pub(crate) fn file_done(&mut self, file: &PendingFile, result: Result<(), Failure>) {
+        if result.is_err() {
+            // No partial outputs are left behind.
+            let _ = fs::remove_file(&file.output);
+        }
+
+        match result {
+            Ok(()) => {
+                self.report.files += 1;
+                self.report.bytes += file.entry.file_size();
+            }
+            Err(Failure::Skip(reason)) => self.report.skip(&file.path, reason),
+            Err(Failure::Cancelled) => self.report.cancelled = true,
+        }
+    }
+
+    /// Size of all queued files
+    pub(crate) fn total_bytes(&self) -> u64 {
+        self.files.iter().map(|file| file.entry.file_size()).sum()
+    }
+
+    /// Reserves a name inside `parent`, renaming it if asked to. `None` if it's taken.
+    fn claim(&mut self, parent: &Path, name: &str, policy: CollisionPolicy) -> Option<PathBuf> {
+        for attempt in 0.. {
+            let candidate = match attempt {
+                0 => Cow::Borrowed(name),
+                n if policy == CollisionPolicy::Rename => Cow::Owned(numbered(name, n)),
+                _ => return None,
+            };
+
+            let output = parent.join(candidate.as_ref());
+            let key = if self.case_insensitive {
+                PathBuf::from(output.to_string_lossy().to_lowercase())
+            } else {
+                output.clone()
+            };
+
+            if self.taken.insert(key) {
+                return Some(output);
+            }
+        }
+
+        None
+    }
+}
+
+/// `name.ext` → `name~N.ext`
+fn numbered(name: &str, n: usize) -> String {
+    match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => format!("{stem}~{n}.{extension}"),
+        _ => format!("{name}~{n}"),
+    }
+}
+
+/// Creates a directory, an existing one is fine unless it's a link.
+fn create_directory(path: &Path) -> io::Result<()> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => Ok(()),
+        Ok(_) => {
+            fs::remove_file(path)?;
+            fs::create_dir(path)
+        }
+        Err(_) => fs::create_dir(path),
+    }
+}
+
+/// Creates an empty file, a link left at its place is removed first.
+pub(crate) fn create_file(path: &Path) -> io::Result<File> {
+    replace_link(path)?;
+
+    OpenOptions::new().write(true).create(true).truncate(true).open(path)
+}
+
+/// Removes a link left at `path`, so it is never followed when writing.
+fn replace_link(path: &Path) -> io::Result<()> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_symlink() => fs::remove_file(path),
+        Ok(metadata) if metadata.is_dir() => Err(io::Error::new(io::ErrorKind::AlreadyExists, "a directory is in the way")),
+        _ => Ok(()),
+    }
+}
+
+/// Checks whether names differing only in case refer to the same file in `directory`.
+///
+/// The probe gets a name nobody else uses and is never opened over an existing file.
+fn is_case_insensitive(directory: &Path) -> bool {
+    let nanos = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map_or(0, |time| time.as_nanos());
+    let name = format!(".iso9660-case-probe-{}-{nanos}", std::process::id());
+    let probe = directory.join(&name);
+
+    if OpenOptions::new().write(true).create_new(true).open(&probe).is_err() {
+        return cfg!(any(windows, target_os = "macos"));
+    }
+
+    let result = fs::symlink_metadata(directory.join(name.to_uppercase())).is_ok();
+    let _ = fs::remove_file(&probe);
+
+    result
+}
+
+fn system_time(seconds: i64) -> SystemTime {
+    let offset = Duration::from_secs(seconds.unsigned_abs());
+
+    if seconds >= 0 {
+        SystemTime::UNIX_EPOCH + offset
+    } else {
+        SystemTime::UNIX_EPOCH - offset
+    }
+}
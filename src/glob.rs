@@ -0,0 +1,278 @@
+//! Shell-style pattern search over the directory tree.
+//!
+//! Supported syntax: `*` (any run of characters inside one component), `?` (one character),
+//! `[abc]`, `[a-z]`, `[!a-z]` (character classes), `**` (any number of whole components)
+//! and `\` to escape a special character.
+//! Patterns are always matched against absolute paths, the leading `/` is optional.
+
+use alloc::{string::String, vec::Vec};
+
+use crate::{helpers::get_directory_entry_by_path, walk::{Walk, WalkEntry}, Read, ISO9660};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Literal(char),
+    AnyChar,
+    AnyRun,
+    Class { negated: bool, ranges: Vec<(char, char)> },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Component {
+    /// `**`
+    Recursive,
+    Segment(Vec<Token>),
+}
+
+/// A compiled glob pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    components: Vec<Component>,
+}
+
+impl Pattern {
+    /// Compiles a pattern, returns `None` if it is malformed (e.g. an unclosed `[`).
+    pub fn new(pattern: &str) -> Option<Self> {
+        let components = pattern
+            .split('/')
+            .filter(|v| !v.is_empty())
+            .map(|component| {
+                if component == "**" {
+                    Some(Component::Recursive)
+                } else {
+                    parse_segment(component).map(Component::Segment)
+                }
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(Self { components })
+    }
+
+    /// Checks whether the whole path matches the pattern.
+    pub fn matches_path(&self, path: &str) -> bool {
+        let states = self.advance(path);
+
+        states.contains(&self.components.len())
+    }
+
+    /// Checks whether something below directory `path` may still match,
+    /// used to avoid descending into subtrees that can't contain results.
+    pub fn could_match_below(&self, path: &str) -> bool {
+        let states = self.advance(path);
+
+        states.iter().any(|&state| state < self.components.len())
+    }
+
+    /// Leading components without any wildcards, joined into an absolute path.
+    fn literal_prefix(&self) -> String {
+        let mut prefix = String::from("/");
+
+        for component in &self.components[..self.components.len().saturating_sub(1)] {
+            let Component::Segment(tokens) = component else {
+                break;
+            };
+
+            if !tokens.iter().all(|token| matches!(token, Token::Literal(_))) {
+                break;
+            }
+
+            if !prefix.ends_with('/') {
+                prefix.push('/');
+            }
+
+            prefix.extend(tokens.iter().filter_map(|token| match token {
+                Token::Literal(c) => Some(*c),
+                _ => None,
+            }));
+        }
+
+        prefix
+    }
+
+    /// Runs the path through the pattern and returns the reachable positions in it.
+    fn advance(&self, path: &str) -> Vec<usize> {
+        let mut states = self.closure(alloc::vec![0]);
+
+        for name in path.split('/').filter(|v| !v.is_empty()) {
+            let mut next = Vec::new();
+
+            for &state in &states {
+                match self.components.get(state) {
+                    Some(Component::Recursive) => next.push(state),
+                    Some(Component::Segment(tokens)) if match_segment(tokens, name) => next.push(state + 1),
+                    _ => {}
+                }
+            }
+
+            states = self.closure(next);
+
+            if states.is_empty() {
+                break;
+            }
+        }
+
+        states
+    }
+
+    /// `**` may match zero components, so a position before it also means the position after it.
+    fn closure(&self, mut states: Vec<usize>) -> Vec<usize> {
+        let mut index = 0;
+
+        while index < states.len() {
+            let state = states[index];
+
+            if matches!(self.components.get(state), Some(Component::Recursive)) && !states.contains(&(state + 1)) {
+                states.push(state + 1);
+            }
+
+            index += 1;
+        }
+
+        states.sort_unstable();
+        states.dedup();
+        states
+    }
+}
+
+fn parse_segment(segment: &str) -> Option<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = segment.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let token = match c {
+            '*' => {
+                // Several stars in a row mean the same as one.
+                if tokens.last() == Some(&Token::AnyRun) {
+                    continue;
+                }
+
+                Token::AnyRun
+            }
+            '?' => Token::AnyChar,
+            '\\' => Token::Literal(chars.next()?),
+            '[' => {
+                let negated = chars.next_if(|&c| c == '!' || c == '^').is_some();
+                let mut ranges = Vec::new();
+                let mut first = true;
+
+                loop {
+                    let mut start = chars.next()?;
+
+                    // `]` right after the opening bracket is a regular character.
+                    if start == ']' && !first {
+                        break;
+                    }
+
+                    if start == '\\' {
+                        start = chars.next()?;
+                    }
+
+                    let end = if chars.peek() == Some(&'-') {
+                        chars.next();
+
+                        match chars.next()? {
+                            ']' => {
+                                ranges.push((start, start));
+                                ranges.push(('-', '-'));
+                                break;
+                            }
+                            '\\' => chars.next()?,
+                            end => end,
+                        }
+                    } else {
+                        start
+                    };
+
+                    ranges.push((start, end));
+                    first = false;
+                }
+
+                Token::Class { negated, ranges }
+            }
+            c => Token::Literal(c),
+        };
+
+        tokens.push(token);
+    }
+
+    Some(tokens)
+}
+
+fn match_token(token: &Token, c: char) -> bool {
+    match token {
+        Token::Literal(l) => *l == c,
+        Token::AnyChar => true,
+        Token::AnyRun => false,
+        Token::Class { negated, ranges } => ranges.iter().any(|&(start, end)| (start..=end).contains(&c)) != *negated,
+    }
+}
+
+fn match_segment(tokens: &[Token], name: &str) -> bool {
+    let name: Vec<char> = name.chars().collect();
+
+    let (mut t, mut n) = (0, 0);
+    // Position of the last `*` and of the character it started matching at.
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match tokens.get(t) {
+            Some(Token::AnyRun) => {
+                backtrack = Some((t, n));
+                t += 1;
+            }
+            Some(token) if match_token(token, name[n]) => {
+                t += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, start)) => {
+                    t = star + 1;
+                    n = start + 1;
+                    backtrack = Some((star, start + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    tokens[t..].iter().all(|token| *token == Token::AnyRun)
+}
+
+/// Iterator over the entries matching a [`Pattern`], created by [`ISO9660::glob`].
+pub struct Glob<'iso, D: Read> {
+    walk: Option<Walk<'iso, D>>,
+    pattern: Pattern,
+}
+
+impl<'iso, D: Read> Glob<'iso, D> {
+    pub(crate) fn new(iso: &'iso ISO9660<D>, pattern: Pattern) -> Self {
+        // Wildcard-free leading directories are looked up directly instead of being walked.
+        let prefix = pattern.literal_prefix();
+
+        let start = get_directory_entry_by_path(iso, &prefix).filter(|entry| entry.is_folder());
+
+        let walk = start.map(|entry| {
+            let filter = pattern.clone();
+
+            Walk::new(iso, entry.record.clone(), prefix).filter_entry(move |item| {
+                if item.entry.is_folder() && filter.could_match_below(&item.path) {
+                    return true;
+                }
+
+                filter.matches_path(&item.path)
+            })
+        });
+
+        Self { walk, pattern }
+    }
+}
+
+impl<D: Read> Iterator for Glob<'_, D> {
+    type Item = WalkEntry;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let walk = self.walk.as_mut()?;
+
+        walk.find(|item| self.pattern.matches_path(&item.path))
+    }
+}
@@ -1,47 +1,96 @@
-use alloc::{vec::Vec};
-use alloc::string::ToString;
-
-use crate::iter::DirectoryIter;
-use crate::{ISODirectoryEntry, ISO9660};
+use crate::iter::{RawDirectoryEntry, MAX_NAME_LENGTH};
+use crate::normalize::{split_version, NameOptions};
+#[cfg(feature = "alloc")]
+use crate::ISODirectoryEntry;
+use crate::{ISODirectoryRecord, Read, ISO9660};
 
 /// This helper function searches for an entry by path.
-pub fn get_directory_entry_by_path(iso: &mut ISO9660, path: &str) -> Option<ISODirectoryEntry> {
-    let mut stems: Vec<&str> = path.split("/").filter(|v| !v.is_empty()).collect();
-    if stems.is_empty() {
-        return Some(ISODirectoryEntry {
-            record: iso.root_directory.clone(),
-            name: "/".to_string()
-        });
+#[cfg(feature = "alloc")]
+pub fn get_directory_entry_by_path<D: Read>(iso: &ISO9660<D>, path: &str) -> Option<ISODirectoryEntry> {
+    get_directory_entry_by_path_with(iso, path, &NameOptions::EXACT)
+}
+
+/// Same as [`get_directory_entry_by_path`], but names are compared after normalization.
+///
+/// When version suffixes are stripped and several versions of a file exist, the highest one wins,
+/// unless the path asks for a specific version (`/README.TXT;2`).
+#[cfg(feature = "alloc")]
+pub fn get_directory_entry_by_path_with<D: Read>(iso: &ISO9660<D>, path: &str, options: &NameOptions) -> Option<ISODirectoryEntry> {
+    if path.split("/").all(|v| v.is_empty()) {
+        return Some(iso.root_entry());
     }
 
-    let mut entry: DirectoryIter<'_> = iso.read_root();
+    lookup(iso, path, options, |raw| raw.to_entry())
+}
+
+/// Searches for the record of an entry by path without allocating anything.
+pub fn get_directory_record_by_path<D: Read>(iso: &ISO9660<D>, path: &str, options: &NameOptions) -> Option<ISODirectoryRecord> {
+    if path.split("/").all(|v| v.is_empty()) {
+        return Some(iso.root_directory.clone());
+    }
+
+    lookup(iso, path, options, |raw| raw.record.clone())
+}
+
+/// Resolves a non-empty path, `convert` turns the matching raw entry into the result.
+fn lookup<D: Read, T>(
+    iso: &ISO9660<D>,
+    path: &str,
+    options: &NameOptions,
+    convert: impl Fn(&RawDirectoryEntry<'_>) -> T,
+) -> Option<T> {
+    let mut stems = path.split("/").filter(|v| !v.is_empty()).peekable();
+    let mut directory = iso.root_directory.clone();
+    let mut buffer = [0u8; MAX_NAME_LENGTH];
 
-    loop {
-        let mut found = false;
+    while let Some(stem) = stems.next() {
+        let mut entries = iso.read_directory_record(&directory);
 
-        for i in &entry {
-            if i.name == stems[0] {
-                if stems.len() == 1 {
-                    return Some(i.clone());
-                }
+        let (query, query_version) = if options.strip_version {
+            split_version(stem)
+        } else {
+            (stem, None)
+        };
 
-                if i.is_file() {
-                    return None;
-                }
+        // Converted entry, its version and its record.
+        let mut best: Option<(T, Option<u16>, ISODirectoryRecord)> = None;
 
-                entry = iso.read_directory(i.record.lba.get() as usize);
+        while let Some(raw) = entries.next_raw() {
+            let Some(name) = raw.name_into(&mut buffer) else {
+                continue;
+            };
 
-                found = true;
+            if !options.matches(name, query) {
+                continue;
+            }
+
+            let version = raw.version();
 
-                stems.remove(0);
+            if query_version.is_some() && version != query_version {
+                continue;
+            }
+
+            if best.as_ref().is_none_or(|(_, best_version, _)| version > *best_version) {
+                best = Some((convert(&raw), version, raw.record.clone()));
+            }
 
+            // Without stripping there can't be another version of the same name.
+            if !options.strip_version {
                 break;
             }
         }
 
-        if !found {
-            break;
+        let (entry, _, record) = best?;
+
+        if stems.peek().is_none() {
+            return Some(entry);
+        }
+
+        if !record.is_folder() {
+            return None;
         }
+
+        directory = record;
     }
 
     None
@@ -0,0 +1,119 @@
+//! B-trees of the catalog and extents overflow files.
+//!
+//! Every node starts with a 14-byte descriptor and ends with the offsets of its records, stored
+//! backwards. Node 0 is the header node, with the root and the node size. Records of index nodes
+//! are a key and the number of the child node whose keys are not smaller.
+
+use alloc::vec::Vec;
+
+const NODE_DESCRIPTOR_SIZE: usize = 14;
+
+pub(super) const KIND_INDEX: i8 = 0;
+pub(super) const KIND_LEAF: i8 = -1;
+const KIND_HEADER: i8 = 1;
+
+/// Smallest and largest node sizes allowed
+const MIN_NODE_SIZE: u32 = 512;
+const MAX_NODE_SIZE: u32 = 32768;
+
+/// Values of the header record needed to search the tree.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Header {
+    /// 0 for an empty tree
+    pub root: u32,
+    pub node_size: u32,
+}
+
+/// Reads the header record out of the header node. Node sizes are at least 512 bytes, so
+/// `node` can be the first 512 bytes of the file.
+pub(super) fn header(node: &[u8]) -> Option<Header> {
+    if *node.get(8)? as i8 != KIND_HEADER {
+        return None;
+    }
+
+    // Depth, root, leaf records, first and last leaf, node size
+    let record = node.get(NODE_DESCRIPTOR_SIZE..)?;
+    let node_size = u16::from_be_bytes(record.get(18..20)?.try_into().ok()?) as u32;
+
+    if !(MIN_NODE_SIZE..=MAX_NODE_SIZE).contains(&node_size) || !node_size.is_power_of_two() {
+        return None;
+    }
+
+    Some(Header {
+        root: u32::from_be_bytes(record.get(2..6)?.try_into().ok()?),
+        node_size,
+    })
+}
+
+/// A node with its records split into keys and data.
+pub(super) struct Node {
+    pub kind: i8,
+    /// Next node of the same level, 0 for the last one
+    pub next: u32,
+    data: Vec<u8>,
+    records: Vec<(usize, usize, usize)>,
+}
+
+impl Node {
+    /// Splits a node. Keys start with their length, a byte on HFS and 16 bits on HFS+.
+    pub fn parse(data: Vec<u8>, wide_keys: bool) -> Option<Node> {
+        let count = u16::from_be_bytes(data.get(10..12)?.try_into().ok()?) as usize;
+        let offset = |index: usize| -> Option<usize> {
+            let position = data.len().checked_sub(2 * (index + 1))?;
+
+            Some(u16::from_be_bytes(data.get(position..position + 2)?.try_into().ok()?) as usize)
+        };
+
+        let mut records = Vec::with_capacity(count);
+
+        for index in 0..count {
+            let (start, end) = (offset(index)?, offset(index + 1)?);
+
+            if start < NODE_DESCRIPTOR_SIZE || end < start || end > data.len() {
+                return None;
+            }
+
+            let record = &data[start..end];
+            let (key_start, key_length) = if wide_keys {
+                (start + 2, u16::from_be_bytes(record.get(..2)?.try_into().ok()?) as usize)
+            } else {
+                (start + 1, *record.first()? as usize)
+            };
+
+            // Data is aligned to 16 bits
+            let data_start = (key_start + key_length).next_multiple_of(2);
+
+            if data_start > end {
+                return None;
+            }
+
+            records.push((key_start, data_start, end));
+        }
+
+        Some(Node {
+            kind: *data.get(8)? as i8,
+            next: u32::from_be_bytes(data.get(..4)?.try_into().ok()?),
+            data,
+            records,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Key without its length, which may include padding, and data of a record.
+    pub fn record(&self, index: usize) -> Option<(&[u8], &[u8])> {
+        let (key, data, end) = *self.records.get(index)?;
+
+        Some((&self.data[key..data], &self.data[data..end]))
+    }
+
+    /// Child of the last index record whose key is `before` the one looked for, or of the first one.
+    pub fn child(&self, before: impl Fn(&[u8]) -> bool) -> Option<u32> {
+        let index = (0..self.len()).rev().find(|index| self.record(*index).is_some_and(|(key, _)| before(key))).unwrap_or(0);
+        let (_, data) = self.record(index)?;
+
+        Some(u32::from_be_bytes(data.get(..4)?.try_into().ok()?))
+    }
+}
@@ -0,0 +1,919 @@
+//! Read-only HFS and HFS+ reader for the Mac side of hybrid discs.
+//!
+//! Classic Mac hybrid CDs put an Apple Partition Map in the ISO9660 system area, pointing at an
+//! `Apple_HFS` partition. The HFS volume usually shares file data with the ISO9660 tree, but
+//! Mac-only files, resource forks and Finder type and creator codes only exist on the HFS side.
+//! [`HFS`] reads it with the same kind of API as [`ISO9660`](crate::ISO9660) and [`UDF`](crate::udf::UDF).
+//!
+//! Files are found through the catalog B-tree, fragmented files through the extents overflow
+//! B-tree. HFS wrappers around an embedded HFS+ volume are opened as HFS+. The journal of HFS+
+//! volumes is not replayed, which doesn't matter for discs.
+
+mod btree;
+
+use alloc::{string::String, vec, vec::Vec};
+
+use btree::{Header, Node, KIND_INDEX, KIND_LEAF};
+
+use crate::{normalize::NameOptions, Read};
+
+pub use crate::extensions::apple::FinderInfo;
+
+const SIGNATURE_HFS: u16 = 0x4244;
+const SIGNATURE_HFS_PLUS: u16 = 0x482B;
+const SIGNATURE_HFSX: u16 = 0x4858;
+
+/// Position of the master directory block or volume header inside the volume
+const VOLUME_HEADER_OFFSET: u64 = 1024;
+
+/// Most partition map entries read
+const MAX_PARTITIONS: u32 = 64;
+
+/// Catalog node IDs of the parent of the root folder and of the B-tree files
+const ROOT_PARENT_ID: u32 = 1;
+const EXTENTS_FILE_ID: u32 = 3;
+const CATALOG_FILE_ID: u32 = 4;
+
+const RECORD_FOLDER: u8 = 1;
+const RECORD_FILE: u8 = 2;
+
+/// Fork types of extents overflow keys
+const FORK_DATA: u8 = 0x00;
+const FORK_RESOURCE: u8 = 0xFF;
+
+/// Seconds from 1904-01-01, the Mac epoch, to 1970-01-01
+const MAC_EPOCH_OFFSET: i64 = 2_082_844_800;
+
+/// Most levels of a B-tree and leaf nodes visited while looking for a key
+const MAX_DEPTH: usize = 16;
+const MAX_LEAF_HOPS: usize = 16;
+
+/// Most leaf nodes visited by one listing
+const MAX_LEAF_NODES: usize = 1 << 20;
+
+/// Most extents overflow records read for one fork
+const MAX_OVERFLOW_RECORDS: usize = 4096;
+
+const MAX_SYMLINK_SIZE: u64 = 64 << 10;
+
+/// Name of the HFS+ folder holding the targets of hard links
+const PRIVATE_FOLDER: &str = "\0\0\0\0HFS+ Private Data";
+
+/// Characters 0x80 to 0xFF of Mac OS Roman, used for HFS names
+const MAC_ROMAN: &str = "ÄÅÇÉÑÖÜáàâäãåçéèêëíìîïñóòôöõúùûü†°¢£§•¶ß®©™´¨≠ÆØ∞±≤≥¥µ∂∑∏π∫ªºΩæø\
+    ¿¡¬√ƒ≈∆«»…\u{A0}ÀÃÕŒœ–—“”‘’÷◊ÿŸ⁄€‹›ﬁﬂ‡·‚„‰ÂÊÁËÈÍÎÏÌÓÔ\u{F8FF}ÒÚÛÙıˆ˜¯˘˙˚¸˝˛ˇ";
+
+/// A partition of an Apple Partition Map.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApplePartition {
+    pub name: String,
+    /// Type such as `Apple_HFS` or `Apple_partition_map`
+    pub partition_type: String,
+    /// Position in bytes
+    pub start: u64,
+    /// Length in bytes
+    pub length: u64,
+}
+
+/// Reads the Apple Partition Map at the start of the device, empty if there is none.
+pub fn apple_partitions<D: Read + ?Sized>(device: &D) -> Vec<ApplePartition> {
+    // The driver descriptor map gives the block size, which partition map entries are counted in
+    let mut driver = [0u8; 4];
+    let driver_block_size = match device.read(0, &mut driver) {
+        Some(()) if &driver[..2] == b"ER" => u16::from_be_bytes([driver[2], driver[3]]) as u64,
+        _ => 0,
+    };
+
+    for block_size in [driver_block_size, 512, 2048] {
+        if !(512..=4096).contains(&block_size) {
+            continue;
+        }
+
+        let mut entry = [0u8; 80];
+        let mut partitions = Vec::new();
+        let mut count = 1;
+        let mut index = 1;
+
+        while index <= count {
+            if device.read(index as u64 * block_size, &mut entry).is_none() || &entry[..2] != b"PM" {
+                break;
+            }
+
+            // Signature, reserved, number of entries, start, length, name, type
+            count = u32::from_be_bytes(entry[4..8].try_into().unwrap()).min(MAX_PARTITIONS);
+
+            partitions.push(ApplePartition {
+                name: c_string(&entry[16..48]),
+                partition_type: c_string(&entry[48..80]),
+                start: u32::from_be_bytes(entry[8..12].try_into().unwrap()) as u64 * block_size,
+                length: u32::from_be_bytes(entry[12..16].try_into().unwrap()) as u64 * block_size,
+            });
+
+            index += 1;
+        }
+
+        if !partitions.is_empty() {
+            return partitions;
+        }
+    }
+
+    Vec::new()
+}
+
+/// Position of the HFS or HFS+ volume of the device: the first `Apple_HFS` partition holding
+/// one, or the start of the device for volumes without a partition map.
+pub fn volume_offset<D: Read + ?Sized>(device: &D) -> Option<u64> {
+    apple_partitions(device)
+        .iter()
+        .filter(|partition| partition.partition_type.eq_ignore_ascii_case("Apple_HFS"))
+        .map(|partition| partition.start)
+        .chain([0])
+        .find(|offset| volume_signature(device, *offset).is_some())
+}
+
+/// Whether the device is a hybrid disc, with both an ISO9660 and an HFS or HFS+ volume.
+pub fn is_hybrid<D: Read + ?Sized>(device: &D) -> bool {
+    let mut identifier = [0u8; 5];
+
+    device.read(0x8001, &mut identifier).is_some()
+        && identifier == crate::descriptors::STANDARD_ID
+        && volume_offset(device).is_some()
+}
+
+fn volume_signature<D: Read + ?Sized>(device: &D, offset: u64) -> Option<u16> {
+    let mut signature = [0u8; 2];
+
+    device.read(offset + VOLUME_HEADER_OFFSET, &mut signature)?;
+
+    let signature = u16::from_be_bytes(signature);
+
+    [SIGNATURE_HFS, SIGNATURE_HFS_PLUS, SIGNATURE_HFSX].contains(&signature).then_some(signature)
+}
+
+/// Length and allocation block runs (start, count) of a fork.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Fork {
+    size: u64,
+    extents: Vec<(u32, u32)>,
+}
+
+/// A file or folder of an HFS or HFS+ volume.
+#[derive(Debug, Clone)]
+pub struct HFSEntry {
+    pub name: String,
+    id: u32,
+    parent: u32,
+    folder: bool,
+    data: Fork,
+    resource: Fork,
+    finder_info: FinderInfo,
+    created: u32,
+    modified: u32,
+    accessed: u32,
+    /// Owner, group and mode, set on HFS+ volumes written by Mac OS X
+    permissions: Option<(u32, u32, u16)>,
+    /// Inode number of HFS+ hard links
+    special: u32,
+}
+
+impl HFSEntry {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Catalog node ID, unique for every file and folder
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn parent_id(&self) -> u32 {
+        self.parent
+    }
+
+    pub fn is_folder(&self) -> bool {
+        self.folder
+    }
+
+    pub fn is_file(&self) -> bool {
+        !self.folder && !self.is_symlink()
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        !self.folder
+            && (self.mode().is_some_and(|mode| mode & 0o170000 == 0o120000)
+                || (&self.finder_info.file_type, &self.finder_info.creator) == (b"slnk", b"rhap"))
+    }
+
+    /// Whether the Finder hides the entry
+    pub fn is_hidden(&self) -> bool {
+        self.finder_info.is_invisible()
+    }
+
+    /// Size of the data fork
+    pub fn file_size(&self) -> u64 {
+        self.data.size
+    }
+
+    /// Size of the resource fork, 0 if there is none
+    pub fn resource_size(&self) -> u64 {
+        self.resource.size
+    }
+
+    pub fn finder_info(&self) -> FinderInfo {
+        self.finder_info
+    }
+
+    /// POSIX mode with the file type bits, HFS+ only
+    pub fn mode(&self) -> Option<u32> {
+        self.permissions.map(|(_, _, mode)| mode as u32)
+    }
+
+    pub fn uid(&self) -> Option<u32> {
+        self.permissions.map(|(uid, _, _)| uid)
+    }
+
+    pub fn gid(&self) -> Option<u32> {
+        self.permissions.map(|(_, gid, _)| gid)
+    }
+
+    /// Modification time as seconds since the Unix epoch, 0 if not recorded. HFS stores local
+    /// time, HFS+ stores UTC.
+    pub fn modified(&self) -> i64 {
+        mac_time(self.modified).unwrap_or(0)
+    }
+
+    pub fn created(&self) -> Option<i64> {
+        mac_time(self.created)
+    }
+
+    /// Access time, recorded by HFS+ only
+    pub fn accessed(&self) -> Option<i64> {
+        mac_time(self.accessed)
+    }
+}
+
+fn mac_time(time: u32) -> Option<i64> {
+    (time != 0).then(|| time as i64 - MAC_EPOCH_OFFSET)
+}
+
+/// A B-tree file.
+struct Tree {
+    fork: Fork,
+    header: Header,
+}
+
+/// Main structure of the HFS and HFS+ reader.
+pub struct HFS<D: Read> {
+    device: D,
+    /// Position of allocation block 0
+    blocks_start: u64,
+    block_size: u32,
+    plus: bool,
+    extents: Tree,
+    catalog: Tree,
+    volume_name: String,
+    root: HFSEntry,
+    /// Folder holding the targets of HFS+ hard links
+    private_folder: Option<u32>,
+}
+
+impl<D: Read> HFS<D> {
+    /// Opens the HFS or HFS+ volume of the device, found with [`volume_offset`].
+    pub fn from_device(device: D) -> Option<Self> {
+        let offset = volume_offset(&device)?;
+
+        Self::from_device_at(device, offset)
+    }
+
+    /// Opens the volume starting `offset` bytes into the device.
+    pub fn from_device_at(device: D, offset: u64) -> Option<Self> {
+        let mut header = [0u8; 512];
+
+        device.read(offset + VOLUME_HEADER_OFFSET, &mut header)?;
+
+        match u16_at(&header, 0)? {
+            SIGNATURE_HFS => {
+                // Master directory block: allocation block size, first allocation block in
+                // 512-byte sectors, volume name, embedded volume, extents and catalog files
+                let block_size = u32_at(&header, 20)?;
+                let blocks_start = offset + u16_at(&header, 28)? as u64 * 512;
+
+                if u16_at(&header, 124)? == SIGNATURE_HFS_PLUS {
+                    let start = u16_at(&header, 126)? as u64;
+
+                    return Self::from_device_at(device, blocks_start + start * block_size as u64);
+                }
+
+                let name = header.get(37..37 + (header[36] as usize).min(27))?;
+
+                Self::open(
+                    device,
+                    false,
+                    blocks_start,
+                    block_size,
+                    hfs_fork(u32_at(&header, 130)?, header.get(134..146)?),
+                    hfs_fork(u32_at(&header, 146)?, header.get(150..162)?),
+                    mac_roman(name),
+                )
+            }
+            SIGNATURE_HFS_PLUS | SIGNATURE_HFSX => {
+                // Volume header: allocation block size, then the fork data of the special files
+                Self::open(
+                    device,
+                    true,
+                    offset,
+                    u32_at(&header, 40)?,
+                    plus_fork(header.get(192..272)?)?,
+                    plus_fork(header.get(272..352)?)?,
+                    String::new(),
+                )
+            }
+            _ => None,
+        }
+    }
+
+    fn open(
+        device: D,
+        plus: bool,
+        blocks_start: u64,
+        block_size: u32,
+        extents: Fork,
+        catalog: Fork,
+        volume_name: String,
+    ) -> Option<Self> {
+        if block_size == 0 || !block_size.is_multiple_of(512) {
+            return None;
+        }
+
+        let placeholder = Header { root: 0, node_size: 512 };
+
+        let mut hfs = HFS {
+            device,
+            blocks_start,
+            block_size,
+            plus,
+            extents: Tree { fork: extents, header: placeholder },
+            catalog: Tree { fork: catalog, header: placeholder },
+            volume_name,
+            root: HFSEntry {
+                name: String::new(),
+                id: ROOT_PARENT_ID,
+                parent: 0,
+                folder: true,
+                data: Fork::default(),
+                resource: Fork::default(),
+                finder_info: FinderInfo::default(),
+                created: 0,
+                modified: 0,
+                accessed: 0,
+                permissions: None,
+                special: 0,
+            },
+            private_folder: None,
+        };
+
+        let mut node = [0u8; 512];
+
+        hfs.read_fork(&hfs.extents.fork, 0, &mut node)?;
+        hfs.extents.header = btree::header(&node)?;
+
+        // The catalog file is the only special file whose extents may overflow
+        let catalog = hfs.fork_extents(CATALOG_FILE_ID, FORK_DATA, &hfs.catalog.fork)?;
+
+        hfs.catalog.fork.extents = catalog;
+        hfs.read_fork(&hfs.catalog.fork, 0, &mut node)?;
+        hfs.catalog.header = btree::header(&node)?;
+
+        let root = hfs.read_directory(&hfs.root).find(HFSEntry::is_folder)?;
+
+        if hfs.plus {
+            hfs.volume_name = root.name.clone();
+        }
+
+        hfs.root = root;
+        hfs.private_folder = hfs.children(hfs.root.id, true).find(|entry| entry.name == PRIVATE_FOLDER).map(|entry| entry.id);
+
+        Some(hfs)
+    }
+
+    pub fn volume_name(&self) -> &str {
+        &self.volume_name
+    }
+
+    /// Whether the volume is HFS+ (or HFSX), rather than HFS
+    pub fn is_plus(&self) -> bool {
+        self.plus
+    }
+
+    /// Allocation block size
+    pub fn block_size(&self) -> u32 {
+        self.block_size
+    }
+
+    pub fn device(&self) -> &D {
+        &self.device
+    }
+
+    /// Destroys the reader and gives the device back.
+    pub fn into_inner(self) -> D {
+        self.device
+    }
+
+    pub fn root_entry(&self) -> HFSEntry {
+        self.root.clone()
+    }
+
+    pub fn read_root(&self) -> HFSDirectoryIter<'_, D> {
+        self.read_directory(&self.root)
+    }
+
+    /// Lists a folder. The iterator is empty if `directory` is not one or can't be read.
+    pub fn read_directory(&self, directory: &HFSEntry) -> HFSDirectoryIter<'_, D> {
+        let id = if directory.folder { directory.id } else { 0 };
+
+        self.children(id, false)
+    }
+
+    /// Reads data fork bytes at `offset`.
+    pub fn read_file(&self, entry: &HFSEntry, offset: u64, data: &mut [u8]) -> Option<()> {
+        if entry.folder {
+            return None;
+        }
+
+        let extents = self.fork_extents(entry.id, FORK_DATA, &entry.data)?;
+
+        self.read_fork(&Fork { size: entry.data.size, extents }, offset, data)
+    }
+
+    /// Reads resource fork bytes at `offset`.
+    pub fn read_resource_fork(&self, entry: &HFSEntry, offset: u64, data: &mut [u8]) -> Option<()> {
+        if entry.folder {
+            return None;
+        }
+
+        let extents = self.fork_extents(entry.id, FORK_RESOURCE, &entry.resource)?;
+
+        self.read_fork(&Fork { size: entry.resource.size, extents }, offset, data)
+    }
+
+    /// Target of a symbolic link, `None` if the entry is not a link.
+    pub fn symlink_target(&self, entry: &HFSEntry) -> Option<String> {
+        if !entry.is_symlink() || entry.file_size() > MAX_SYMLINK_SIZE {
+            return None;
+        }
+
+        let mut data = vec![0u8; entry.file_size() as usize];
+
+        self.read_file(entry, 0, &mut data)?;
+
+        Some(String::from_utf8_lossy(&data).into_owned())
+    }
+
+    /// Recursively walks the whole tree starting at the root folder.
+    pub fn walk(&self) -> HFSWalk<'_, D> {
+        HFSWalk {
+            hfs: self,
+            stack: vec![(self.read_root(), String::new(), self.root.id)],
+        }
+    }
+
+    /// Finds an entry by its path, names are compared as is.
+    pub fn entry_by_path(&self, path: &str) -> Option<HFSEntry> {
+        self.entry_by_path_with(path, &NameOptions::EXACT)
+    }
+
+    /// Same as [`entry_by_path`](Self::entry_by_path), but names are compared after normalization.
+    pub fn entry_by_path_with(&self, path: &str, options: &NameOptions) -> Option<HFSEntry> {
+        let mut entry = self.root_entry();
+
+        for stem in path.split('/').filter(|stem| !stem.is_empty()) {
+            entry = self.read_directory(&entry).find(|child| options.matches(&child.name, stem))?;
+        }
+
+        Some(entry)
+    }
+
+    /// Children of a folder. `private` keeps entries hidden from listings, like the folder of
+    /// hard link targets, and doesn't resolve hard links.
+    fn children(&self, parent: u32, private: bool) -> HFSDirectoryIter<'_, D> {
+        let plus = self.plus;
+        let start = (parent != 0)
+            .then(|| self.first_record(&self.catalog, |key| catalog_parent(key, plus).is_some_and(|found| found < parent)))
+            .flatten();
+        let (node, index) = start.map_or((None, 0), |(node, index)| (Some(node), index));
+
+        HFSDirectoryIter {
+            hfs: self,
+            cursor: Cursor { node, index, hops: 0 },
+            parent,
+            private,
+        }
+    }
+
+    /// Leaf node and index of the first record whose key is not `before` the one looked for.
+    fn first_record(&self, tree: &Tree, before: impl Fn(&[u8]) -> bool) -> Option<(Node, usize)> {
+        let mut number = tree.header.root;
+
+        for _ in 0..MAX_DEPTH {
+            if number == 0 {
+                return None;
+            }
+
+            let mut node = self.read_node(tree, number)?;
+
+            match node.kind {
+                KIND_INDEX => number = node.child(&before)?,
+                KIND_LEAF => {
+                    // The record may be the first one of the next leaf
+                    for _ in 0..MAX_LEAF_HOPS {
+                        if let Some(index) = (0..node.len()).find(|index| node.record(*index).is_some_and(|(key, _)| !before(key))) {
+                            return Some((node, index));
+                        }
+
+                        node = self.read_node(tree, node.next).filter(|node| node.kind == KIND_LEAF)?;
+                    }
+
+                    return None;
+                }
+                _ => return None,
+            }
+        }
+
+        None
+    }
+
+    fn read_node(&self, tree: &Tree, number: u32) -> Option<Node> {
+        let size = tree.header.node_size;
+        let mut data = vec![0u8; size as usize];
+
+        self.read_fork(&tree.fork, number as u64 * size as u64, &mut data)?;
+
+        Node::parse(data, self.plus)
+    }
+
+    /// All extents of a fork, completed from the extents overflow file if the first record
+    /// doesn't cover it.
+    fn fork_extents(&self, id: u32, fork_type: u8, fork: &Fork) -> Option<Vec<(u32, u32)>> {
+        let mut extents = fork.extents.clone();
+        let mut blocks: u64 = extents.iter().map(|(_, count)| *count as u64).sum();
+        let needed = fork.size.div_ceil(self.block_size as u64);
+
+        if blocks >= needed || id == EXTENTS_FILE_ID {
+            return Some(extents);
+        }
+
+        let plus = self.plus;
+        let before = |key: &[u8]| extents_key(key, plus).is_some_and(|(found_type, found_id, _)| (found_id, found_type) < (id, fork_type));
+        let (node, index) = self.first_record(&self.extents, before)?;
+
+        let mut cursor = Cursor {
+            node: Some(node),
+            index,
+            hops: 0,
+        };
+
+        for _ in 0..MAX_OVERFLOW_RECORDS {
+            if blocks >= needed {
+                return Some(extents);
+            }
+
+            let (key, data) = cursor.next(self, &self.extents)?;
+            let (found_type, found_id, start) = extents_key(key, plus)?;
+
+            if (found_id, found_type) != (id, fork_type) || start as u64 != blocks {
+                return None;
+            }
+
+            let record = if plus { plus_extents(data)? } else { hfs_extents(data)? };
+
+            blocks += record.iter().map(|(_, count)| *count as u64).sum::<u64>();
+            extents.extend(record);
+        }
+
+        None
+    }
+
+    /// Reads fork bytes at `offset`, `fork.extents` having to cover them.
+    fn read_fork(&self, fork: &Fork, offset: u64, data: &mut [u8]) -> Option<()> {
+        let end = offset.checked_add(data.len() as u64)?;
+
+        if end > fork.size {
+            return None;
+        }
+
+        let block_size = self.block_size as u64;
+        let mut start = 0u64;
+
+        for (block, count) in &fork.extents {
+            let extent_end = start + *count as u64 * block_size;
+
+            if extent_end > offset && start < end {
+                let from = offset.max(start);
+                let to = end.min(extent_end);
+                let position = self.blocks_start + *block as u64 * block_size + (from - start);
+
+                self.device.read(position, &mut data[(from - offset) as usize..(to - offset) as usize])?;
+            }
+
+            start = extent_end;
+
+            if start >= end {
+                return Some(());
+            }
+        }
+
+        (start >= end).then_some(())
+    }
+
+    /// Builds an entry out of a catalog leaf record, `None` for thread records.
+    fn entry(&self, name: String, parent: u32, record: &[u8]) -> Option<HFSEntry> {
+        if self.plus {
+            // Type, flags, valence or reserved, ID, creation, content, attribute, access and
+            // backup dates, BSD info, Finder info, extended Finder info, text encoding, reserved
+            let record_type = u16_at(record, 0)?;
+
+            if record_type != RECORD_FOLDER as u16 && record_type != RECORD_FILE as u16 {
+                return None;
+            }
+
+            let folder = record_type == RECORD_FOLDER as u16;
+            let mode = u16_at(record, 42)?;
+
+            Some(HFSEntry {
+                name,
+                id: u32_at(record, 8)?,
+                parent,
+                folder,
+                data: if folder { Fork::default() } else { plus_fork(record.get(88..168)?)? },
+                resource: if folder { Fork::default() } else { plus_fork(record.get(168..248)?)? },
+                finder_info: finder_info(record.get(48..64)?, folder)?,
+                created: u32_at(record, 12)?,
+                modified: u32_at(record, 16)?,
+                accessed: u32_at(record, 24)?,
+                permissions: (mode != 0).then_some((u32_at(record, 32)?, u32_at(record, 36)?, mode)),
+                special: u32_at(record, 44)?,
+            })
+        } else {
+            match *record.first()? {
+                // Flags, valence, ID, creation, modification and backup dates, Finder info
+                RECORD_FOLDER => Some(HFSEntry {
+                    name,
+                    id: u32_at(record, 6)?,
+                    parent,
+                    folder: true,
+                    data: Fork::default(),
+                    resource: Fork::default(),
+                    finder_info: finder_info(record.get(22..38)?, true)?,
+                    created: u32_at(record, 10)?,
+                    modified: u32_at(record, 14)?,
+                    accessed: 0,
+                    permissions: None,
+                    special: 0,
+                }),
+                // Flags, type, Finder info, ID, data fork start, length and allocated length,
+                // the same for the resource fork, dates, Finder info, clump size, extent records
+                RECORD_FILE => Some(HFSEntry {
+                    name,
+                    id: u32_at(record, 20)?,
+                    parent,
+                    folder: false,
+                    data: hfs_fork(u32_at(record, 26)?, record.get(74..86)?),
+                    resource: hfs_fork(u32_at(record, 36)?, record.get(86..98)?),
+                    finder_info: finder_info(record.get(4..20)?, false)?,
+                    created: u32_at(record, 44)?,
+                    modified: u32_at(record, 48)?,
+                    accessed: 0,
+                    permissions: None,
+                    special: 0,
+                }),
+                _ => None,
+            }
+        }
+    }
+
+    /// Replaces the content of an HFS+ hard link by the one of its target, an `iNode<number>`
+    /// file in the private folder.
+    fn resolve_hard_link(&self, entry: HFSEntry) -> HFSEntry {
+        let is_link = (&entry.finder_info.file_type, &entry.finder_info.creator) == (b"hlnk", b"hfs+");
+
+        let Some(folder) = self.private_folder.filter(|_| is_link && self.plus) else {
+            return entry;
+        };
+
+        let name = alloc::format!("iNode{}", entry.special);
+
+        match self.children(folder, true).find(|target| target.name == name) {
+            Some(target) => HFSEntry {
+                name: entry.name,
+                parent: entry.parent,
+                ..target
+            },
+            None => entry,
+        }
+    }
+}
+
+/// Position in the leaf records of a B-tree.
+struct Cursor {
+    node: Option<Node>,
+    index: usize,
+    hops: usize,
+}
+
+impl Cursor {
+    /// Key and data of the next leaf record.
+    fn next<'cursor, D: Read>(&'cursor mut self, hfs: &HFS<D>, tree: &Tree) -> Option<(&'cursor [u8], &'cursor [u8])> {
+        loop {
+            let node = self.node.as_ref()?;
+
+            if self.index < node.len() {
+                break;
+            }
+
+            let next = node.next;
+
+            self.index = 0;
+            self.hops += 1;
+            self.node = (next != 0 && self.hops < MAX_LEAF_NODES)
+                .then(|| hfs.read_node(tree, next))
+                .flatten()
+                .filter(|node| node.kind == KIND_LEAF);
+        }
+
+        self.index += 1;
+        self.node.as_ref()?.record(self.index - 1)
+    }
+}
+
+/// Iterator over a folder, created by [`HFS::read_directory`]. Thread records are skipped, and
+/// so is the folder of hard link targets, hard links themselves read as their targets.
+pub struct HFSDirectoryIter<'hfs, D: Read> {
+    hfs: &'hfs HFS<D>,
+    cursor: Cursor,
+    parent: u32,
+    private: bool,
+}
+
+impl<D: Read> Iterator for HFSDirectoryIter<'_, D> {
+    type Item = HFSEntry;
+
+    fn next(&mut self) -> Option<HFSEntry> {
+        loop {
+            let (key, record) = self.cursor.next(self.hfs, &self.hfs.catalog)?;
+            let (parent, name) = catalog_key(key, self.hfs.plus)?;
+
+            if parent != self.parent {
+                self.cursor.node = None;
+                return None;
+            }
+
+            let Some(entry) = self.hfs.entry(name, parent, record) else {
+                continue;
+            };
+
+            if self.private {
+                return Some(entry);
+            }
+
+            if entry.name.starts_with('\0') {
+                continue;
+            }
+
+            return Some(self.hfs.resolve_hard_link(entry));
+        }
+    }
+}
+
+/// An entry yielded by [`HFSWalk`].
+#[derive(Debug, Clone)]
+pub struct HFSWalkEntry {
+    pub entry: HFSEntry,
+    /// Absolute path inside the volume
+    pub path: String,
+    /// 1 for entries of the root folder, 2 for their children and so on
+    pub depth: usize,
+}
+
+/// Depth-first iterator over the tree, created by [`HFS::walk`]. Folders come before their
+/// children, a folder already on the current path (a loop in a crafted image) is not entered.
+pub struct HFSWalk<'hfs, D: Read> {
+    hfs: &'hfs HFS<D>,
+    stack: Vec<(HFSDirectoryIter<'hfs, D>, String, u32)>,
+}
+
+impl<D: Read> Iterator for HFSWalk<'_, D> {
+    type Item = HFSWalkEntry;
+
+    fn next(&mut self) -> Option<HFSWalkEntry> {
+        loop {
+            let depth = self.stack.len();
+            let (entries, path, _) = self.stack.last_mut()?;
+
+            let Some(entry) = entries.next() else {
+                self.stack.pop();
+                continue;
+            };
+
+            let path = alloc::format!("{path}/{}", entry.name);
+
+            if entry.is_folder() && !self.stack.iter().any(|(_, _, id)| *id == entry.id) {
+                self.stack.push((self.hfs.read_directory(&entry), path.clone(), entry.id));
+            }
+
+            return Some(HFSWalkEntry { entry, path, depth });
+        }
+    }
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(data.get(offset..offset + 2)?.try_into().ok()?))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+/// Parent ID of a catalog key.
+fn catalog_parent(key: &[u8], plus: bool) -> Option<u32> {
+    u32_at(key, if plus { 0 } else { 1 })
+}
+
+/// Parent ID and name of a catalog key. `/` is shown as `:`, like Mac OS X does, since it
+/// separates path components here.
+fn catalog_key(key: &[u8], plus: bool) -> Option<(u32, String)> {
+    let parent = catalog_parent(key, plus)?;
+
+    let name: String = if plus {
+        let length = u16_at(key, 4)? as usize;
+        let units = key.get(6..6 + length * 2)?.chunks_exact(2).map(|unit| u16::from_be_bytes([unit[0], unit[1]]));
+
+        char::decode_utf16(units).map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER)).collect()
+    } else {
+        mac_roman(key.get(6..6 + *key.get(5)? as usize)?)
+    };
+
+    Some((parent, name.replace('/', ":")))
+}
+
+/// Fork type, file ID and first allocation block of an extents overflow key.
+fn extents_key(key: &[u8], plus: bool) -> Option<(u8, u32, u32)> {
+    if plus {
+        Some((*key.first()?, u32_at(key, 2)?, u32_at(key, 6)?))
+    } else {
+        Some((*key.first()?, u32_at(key, 1)?, u16_at(key, 5)? as u32))
+    }
+}
+
+/// HFS+ fork data: length, clump size, allocated blocks and eight extents.
+fn plus_fork(data: &[u8]) -> Option<Fork> {
+    Some(Fork {
+        size: u64::from_be_bytes(data.get(..8)?.try_into().ok()?),
+        extents: plus_extents(data.get(16..80)?)?,
+    })
+}
+
+fn plus_extents(data: &[u8]) -> Option<Vec<(u32, u32)>> {
+    let extents = data.get(..64)?.chunks_exact(8).map(|extent| (u32_at(extent, 0).unwrap_or(0), u32_at(extent, 4).unwrap_or(0)));
+
+    Some(extents.filter(|(_, count)| *count != 0).collect())
+}
+
+/// HFS fork: a length and a record of three extents.
+fn hfs_fork(size: u32, extents: &[u8]) -> Fork {
+    Fork {
+        size: size as u64,
+        extents: hfs_extents(extents).unwrap_or_default(),
+    }
+}
+
+fn hfs_extents(data: &[u8]) -> Option<Vec<(u32, u32)>> {
+    let extents = data.get(..12)?.chunks_exact(4).map(|extent| (u16_at(extent, 0).unwrap_or(0) as u32, u16_at(extent, 2).unwrap_or(0) as u32));
+
+    Some(extents.filter(|(_, count)| *count != 0).collect())
+}
+
+/// File or folder Finder info. Files start with the type and creator, folders with their
+/// window rectangle; both have the Finder flags after them.
+fn finder_info(data: &[u8], folder: bool) -> Option<FinderInfo> {
+    Some(FinderInfo {
+        file_type: if folder { [0; 4] } else { data.get(..4)?.try_into().ok()? },
+        creator: if folder { [0; 4] } else { data.get(4..8)?.try_into().ok()? },
+        flags: u16_at(data, 8)?,
+    })
+}
+
+fn mac_roman(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| match byte {
+            0..=0x7F => *byte as char,
+            _ => MAC_ROMAN.chars().nth(*byte as usize - 0x80).unwrap_or(char::REPLACEMENT_CHARACTER),
+        })
+        .collect()
+}
+
+fn c_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|byte| *byte == 0).unwrap_or(bytes.len());
+
+    bytes[..end].iter().map(|byte| *byte as char).collect()
+}
@@ -1,4 +1,298 @@
+#[cfg(feature = "alloc")]
+use alloc::{boxed::Box, rc::Rc, sync::Arc, vec::Vec};
+use core::ops::DerefMut;
+
+use crate::{block_position, ISODirectoryRecord};
+
 /// Implement reading functionality from ANYTHING by implementing this trait.
+///
+/// Reads are positional (like `pread`) and take `&self`, so one device can serve
+/// many readers at once. Devices that have to seek first implement [`ReadMut`] and
+/// are wrapped into [`Locked`].
 pub trait Read {
-    fn read(&mut self, position: usize, buffer: &mut [u8]) -> Option<()>;
+    fn read(&self, position: u64, buffer: &mut [u8]) -> Option<()>;
+
+    /// Reads from volume `sequence` of a volume set, see [`crate::volume_set::VolumeSet`].
+    /// Devices holding a single volume ignore the number.
+    fn read_volume(&self, sequence: u16, position: u64, buffer: &mut [u8]) -> Option<()> {
+        let _ = sequence;
+
+        self.read(position, buffer)
+    }
+}
+
+/// Plain read when `volume` is `None`, [`Read::read_volume`] otherwise.
+///
+/// Devices that wrap another one use it to pass the volume of a read on.
+pub(crate) fn read_from<D: Read + ?Sized>(device: &D, volume: Option<u16>, position: u64, buffer: &mut [u8]) -> Option<()> {
+    match volume {
+        Some(sequence) => device.read_volume(sequence, position, buffer),
+        None => device.read(position, buffer),
+    }
+}
+
+/// Reading functionality for stateful devices (`seek` + `read`).
+pub trait ReadMut {
+    fn read(&mut self, position: u64, buffer: &mut [u8]) -> Option<()>;
+
+    /// Reads from volume `sequence` of a volume set, see [`Read::read_volume`].
+    fn read_volume(&mut self, sequence: u16, position: u64, buffer: &mut [u8]) -> Option<()> {
+        let _ = sequence;
+
+        self.read(position, buffer)
+    }
+}
+
+impl<T: ReadMut + ?Sized> ReadMut for &mut T {
+    fn read(&mut self, position: u64, buffer: &mut [u8]) -> Option<()> {
+        (**self).read(position, buffer)
+    }
+    fn read_volume(&mut self, sequence: u16, position: u64, buffer: &mut [u8]) -> Option<()> {
+        (**self).read_volume(sequence, position, buffer)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: ReadMut + ?Sized> ReadMut for Box<T> {
+    fn read(&mut self, position: u64, buffer: &mut [u8]) -> Option<()> {
+        (**self).read(position, buffer)
+    }
+    fn read_volume(&mut self, sequence: u16, position: u64, buffer: &mut [u8]) -> Option<()> {
+        (**self).read_volume(sequence, position, buffer)
+    }
+}
+
+#[cfg(feature = "std")]
+pub(crate) type Lock<T> = std::sync::Mutex<T>;
+
+#[cfg(not(feature = "std"))]
+pub(crate) type Lock<T> = core::cell::RefCell<T>;
+
+#[cfg(feature = "std")]
+pub(crate) fn lock<T>(lock: &Lock<T>) -> impl DerefMut<Target = T> + '_ {
+    lock.lock().unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) fn lock<T>(lock: &Lock<T>) -> impl DerefMut<Target = T> + '_ {
+    lock.borrow_mut()
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn lock_get_mut<T>(lock: &mut Lock<T>) -> &mut T {
+    lock.get_mut().unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) fn lock_get_mut<T>(lock: &mut Lock<T>) -> &mut T {
+    lock.get_mut()
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn lock_into_inner<T>(lock: Lock<T>) -> T {
+    lock.into_inner().unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) fn lock_into_inner<T>(lock: Lock<T>) -> T {
+    lock.into_inner()
+}
+
+/// Turns a [`ReadMut`] device into a [`Read`] one by locking it for every read.
+///
+/// With the `std` feature the lock is a `Mutex`, so the device can be shared between threads.
+/// Without it a `RefCell` is used.
+pub struct Locked<D>(Lock<D>);
+
+impl<D: ReadMut> Locked<D> {
+    pub fn new(device: D) -> Self {
+        Self(Lock::new(device))
+    }
+
+    pub fn get_mut(&mut self) -> &mut D {
+        lock_get_mut(&mut self.0)
+    }
+
+    pub fn into_inner(self) -> D {
+        lock_into_inner(self.0)
+    }
+}
+
+impl<D: ReadMut> Read for Locked<D> {
+    fn read(&self, position: u64, buffer: &mut [u8]) -> Option<()> {
+        lock(&self.0).read(position, buffer)
+    }
+    fn read_volume(&self, sequence: u16, position: u64, buffer: &mut [u8]) -> Option<()> {
+        lock(&self.0).read_volume(sequence, position, buffer)
+    }
+}
+
+impl<T: Read + ?Sized> Read for &T {
+    fn read(&self, position: u64, buffer: &mut [u8]) -> Option<()> {
+        (**self).read(position, buffer)
+    }
+    fn read_volume(&self, sequence: u16, position: u64, buffer: &mut [u8]) -> Option<()> {
+        (**self).read_volume(sequence, position, buffer)
+    }
+}
+
+impl<T: Read + ?Sized> Read for &mut T {
+    fn read(&self, position: u64, buffer: &mut [u8]) -> Option<()> {
+        (**self).read(position, buffer)
+    }
+    fn read_volume(&self, sequence: u16, position: u64, buffer: &mut [u8]) -> Option<()> {
+        (**self).read_volume(sequence, position, buffer)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Read + ?Sized> Read for Box<T> {
+    fn read(&self, position: u64, buffer: &mut [u8]) -> Option<()> {
+        (**self).read(position, buffer)
+    }
+    fn read_volume(&self, sequence: u16, position: u64, buffer: &mut [u8]) -> Option<()> {
+        (**self).read_volume(sequence, position, buffer)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Read + ?Sized> Read for Rc<T> {
+    fn read(&self, position: u64, buffer: &mut [u8]) -> Option<()> {
+        (**self).read(position, buffer)
+    }
+    fn read_volume(&self, sequence: u16, position: u64, buffer: &mut [u8]) -> Option<()> {
+        (**self).read_volume(sequence, position, buffer)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Read + ?Sized> Read for Arc<T> {
+    fn read(&self, position: u64, buffer: &mut [u8]) -> Option<()> {
+        (**self).read(position, buffer)
+    }
+    fn read_volume(&self, sequence: u16, position: u64, buffer: &mut [u8]) -> Option<()> {
+        (**self).read_volume(sequence, position, buffer)
+    }
+}
+
+/// Data of a record read as a device of its own. Position 0 is the first byte after the
+/// extended attribute record, the units of interleaved files follow each other without gaps.
+pub(crate) struct Extent<'a, D: Read + ?Sized> {
+    pub device: &'a D,
+    pub record: &'a ISODirectoryRecord,
+}
+
+impl<D: Read + ?Sized> Read for Extent<'_, D> {
+    fn read(&self, position: u64, buffer: &mut [u8]) -> Option<()> {
+        let start = block_position(self.record.data_lba() as u64)?;
+        let volume = self.record.volume_sequence_number();
+
+        if !self.record.is_interleaved() {
+            return self.device.read_volume(volume, start.checked_add(position)?, buffer);
+        }
+
+        let unit = block_position(self.record.unit_size() as u64)?;
+        let stride = block_position(self.record.unit_size() as u64 + self.record.interleave_gap_size() as u64)?;
+        let mut done = 0;
+
+        while done < buffer.len() {
+            let offset = position.checked_add(done as u64)?;
+            let within = offset % unit;
+            let count = ((unit - within) as usize).min(buffer.len() - done);
+            let physical = start.checked_add((offset / unit).checked_mul(stride)?)? + within;
+
+            self.device.read_volume(volume, physical, &mut buffer[done..done + count])?;
+            done += count;
+        }
+
+        Some(())
+    }
+}
+
+/// A part of a device, e.g. a volume partition, read as a device of its own.
+pub struct Region<D: Read> {
+    device: D,
+    start: u64,
+    size: u64,
+}
+
+impl<D: Read> Region<D> {
+    pub fn new(device: D, start: u64, size: u64) -> Self {
+        Self { device, start, size }
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn into_inner(self) -> D {
+        self.device
+    }
+}
+
+impl<D: Read> Region<D> {
+    fn read_from(&self, volume: Option<u16>, position: u64, buffer: &mut [u8]) -> Option<()> {
+        if position.checked_add(buffer.len() as u64)? > self.size {
+            return None;
+        }
+
+        read_from(&self.device, volume, self.start.checked_add(position)?, buffer)
+    }
+}
+
+impl<D: Read> Read for Region<D> {
+    fn read(&self, position: u64, buffer: &mut [u8]) -> Option<()> {
+        self.read_from(None, position, buffer)
+    }
+    fn read_volume(&self, sequence: u16, position: u64, buffer: &mut [u8]) -> Option<()> {
+        self.read_from(Some(sequence), position, buffer)
+    }
+}
+
+/// Images that are already in memory.
+impl Read for [u8] {
+    fn read(&self, position: u64, buffer: &mut [u8]) -> Option<()> {
+        let start = usize::try_from(position).ok()?;
+        let end = start.checked_add(buffer.len())?;
+
+        buffer.copy_from_slice(self.get(start..end)?);
+
+        Some(())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Read for Vec<u8> {
+    fn read(&self, position: u64, buffer: &mut [u8]) -> Option<()> {
+        self.as_slice().read(position, buffer)
+    }
+}
+
+#[cfg(all(feature = "std", unix))]
+impl Read for std::fs::File {
+    fn read(&self, position: u64, buffer: &mut [u8]) -> Option<()> {
+        use std::os::unix::fs::FileExt;
+
+        self.read_exact_at(buffer, position).ok()
+    }
+}
+
+#[cfg(all(feature = "std", windows))]
+impl Read for std::fs::File {
+    fn read(&self, position: u64, buffer: &mut [u8]) -> Option<()> {
+        use std::os::windows::fs::FileExt;
+
+        let mut done = 0;
+
+        while done < buffer.len() {
+            match self.seek_read(&mut buffer[done..], position.checked_add(done as u64)?) {
+                Ok(0) => return None,
+                Ok(n) => done += n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(_) => return None,
+            }
+        }
+
+        Some(())
+    }
 }
@@ -1,101 +1,461 @@
-use alloc::borrow::ToOwned;
-use alloc::string::String;
-use alloc::vec;
-use core::cell::RefCell;
-use zerocopy::IntoBytes;
+#[cfg(feature = "alloc")]
+use alloc::{borrow::Cow, string::String, vec::Vec};
+use zerocopy::FromBytes;
 
+#[cfg(feature = "alloc")]
+use crate::ISODirectoryEntry;
 use crate::{
-    ISO9660, ISODirectoryEntry, ISODirectoryRecord, ISOInternalFlags, PRIMARY_VOLUME_DESCRIPTOR_POSITION, Read, descriptors::{Descriptor, DescriptorType}
+    extensions, normalize, ISO9660, ISODirectoryRecord, ISOInternalFlags, DISK_SECTOR_SIZE, block_position, FileFlags, PRIMARY_VOLUME_DESCRIPTOR_POSITION, Read, descriptors::{Descriptor, DescriptorType, STANDARD_ID}
 };
 
-pub struct DirectoryIter<'iso> {
-    iso: RefCell<&'iso mut ISO9660>,
-    byte_offset: RefCell<usize>,
+/// Enough to hold any decoded name: Rock Ridge names are at most 255 bytes,
+/// Joliet identifiers are at most 128 bytes of UCS-2 (192 bytes of UTF-8).
+pub const MAX_NAME_LENGTH: usize = 256;
+
+/// Size of the stack buffer the system use area and its continuations are gathered into
+/// by the non-allocating accessors. Longer chains are cut.
+const SYSTEM_USE_BUFFER_SIZE: usize = 4096;
+
+/// Most `CE` continuation areas followed for one record, guards against loops.
+const MAX_CONTINUATIONS: usize = 16;
+
+/// Which records [`DirectoryIter`] leaves out.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ListOptions {
+    /// Skip records with the existence bit set
+    pub hide_hidden: bool,
+    /// Skip associated files that could not be paired with the file they belong to
+    pub hide_associated: bool,
 }
 
-impl<'iso> DirectoryIter<'iso> {
-    pub fn new(iso: &'iso mut ISO9660, byte_offset: usize) -> Self {
-        Self {
-            iso: iso.into(),
-            byte_offset: byte_offset.into(),
+impl ListOptions {
+    /// Every record is listed.
+    pub const ALL: Self = Self {
+        hide_hidden: false,
+        hide_associated: false,
+    };
+
+    /// Like the Linux driver: hidden files are shown, associated ones only with `showassoc`.
+    pub const LINUX: Self = Self {
+        hide_hidden: false,
+        hide_associated: true,
+    };
+
+    /// Like Windows with default Explorer settings: neither hidden nor associated files are shown.
+    pub const WINDOWS: Self = Self {
+        hide_hidden: true,
+        hide_associated: true,
+    };
+}
+
+/// A directory record borrowed straight from the sector buffer of [`DirectoryIter`].
+///
+/// Nothing is allocated: names are borrowed from the sector or decoded into a caller buffer,
+/// unless the allocating helpers (`name`, `to_entry`) are used.
+pub struct RawDirectoryEntry<'buf> {
+    pub record: &'buf ISODirectoryRecord,
+    /// File identifier exactly as stored on disk
+    pub identifier: &'buf [u8],
+    /// System use area (Rock Ridge and friends live here)
+    pub system_use: &'buf [u8],
+    /// Associated file recorded right before this one, holding its resource fork
+    pub resource_fork: Option<ISODirectoryRecord>,
+    joliet: bool,
+    /// Continuation areas (`CE`) are read from here
+    device: &'buf dyn Read,
+    /// Volume of the directory, continuation areas are recorded on it too
+    volume: u16,
+}
+
+impl<'buf> RawDirectoryEntry<'buf> {
+    pub const fn is_folder(&self) -> bool {
+        self.record.is_folder()
+    }
+
+    pub const fn file_flags(&self) -> FileFlags {
+        self.record.file_flags()
+    }
+
+    /// Fixed part of the extended attribute record, read from the device.
+    pub fn extended_attribute_record(&self) -> Option<crate::xar::ExtendedAttributeRecord> {
+        crate::xar::ExtendedAttributeRecord::read(self.device, self.record)
+    }
+
+    pub const fn is_file(&self) -> bool {
+        !self.is_folder()
+    }
+
+    /// Calls `sink` with the system use area and every continuation area chained to it,
+    /// until `sink` returns `None`.
+    fn for_each_system_use_area(&self, mut sink: impl FnMut(&[u8]) -> Option<()>) -> Option<()> {
+        let mut sector = [0u8; DISK_SECTOR_SIZE];
+
+        sink(extensions::rock_ridge::entries(self.system_use))?;
+
+        let mut next = extensions::rock_ridge::continuation(self.system_use);
+
+        for _ in 0..MAX_CONTINUATIONS {
+            let Some((position, length)) = next else {
+                break;
+            };
+
+            let area = &mut sector[..length.min(DISK_SECTOR_SIZE)];
+
+            self.device.read_volume(self.volume, position, area)?;
+            sink(extensions::rock_ridge::entries(area))?;
+
+            next = extensions::rock_ridge::continuation(area);
+        }
+
+        Some(())
+    }
+
+    /// Runs `f` on the whole system use area, continuations included, without allocating.
+    fn with_system_use<R>(&self, f: impl FnOnce(&[u8]) -> R) -> R {
+        if extensions::rock_ridge::continuation(self.system_use).is_none() {
+            return f(self.system_use);
+        }
+
+        let mut buffer = [0u8; SYSTEM_USE_BUFFER_SIZE];
+        let mut length = 0;
+
+        self.for_each_system_use_area(|area| {
+            buffer.get_mut(length..length + area.len())?.copy_from_slice(area);
+            length += area.len();
+
+            Some(())
+        });
+
+        f(&buffer[..length])
+    }
+
+    /// System use area with all its continuation areas appended.
+    #[cfg(feature = "alloc")]
+    pub fn full_system_use(&self) -> Cow<'buf, [u8]> {
+        if extensions::rock_ridge::continuation(self.system_use).is_none() {
+            return Cow::Borrowed(self.system_use);
+        }
+
+        let mut data = Vec::new();
+
+        self.for_each_system_use_area(|area| {
+            data.extend_from_slice(area);
+
+            Some(())
+        });
+
+        Cow::Owned(data)
+    }
+
+    /// Name of the entry (Rock Ridge, Joliet or plain), without allocating.
+    ///
+    /// The name is decoded into `buffer` ([`MAX_NAME_LENGTH`] bytes are enough unless
+    /// a Rock Ridge name is split over several `NM` entries). A Rock Ridge name that
+    /// doesn't fit gives `None` rather than the plain identifier.
+    pub fn name_into<'a>(&'a self, buffer: &'a mut [u8]) -> Option<&'a str> {
+        let rock_ridge = match self.joliet {
+            true => None,
+            false => self.with_system_use(|data| extensions::rock_ridge::copy_name(data, buffer)),
+        };
+
+        match rock_ridge {
+            Some(length) => str::from_utf8(&buffer[..length?]).ok(),
+            None => self.identifier_into(buffer),
+        }
+    }
+
+    /// File identifier itself, ignoring Rock Ridge names, without allocating.
+    pub fn identifier_into<'a>(&'a self, buffer: &'a mut [u8]) -> Option<&'a str> {
+        match self.identifier {
+            [] | [0] => Some("."),
+            [1] => Some(".."),
+            identifier if self.joliet => extensions::joliet::parse_name_into(identifier, buffer),
+            identifier => str::from_utf8(identifier).ok(),
+        }
+    }
+
+    /// Version number from the `;N` suffix of the identifier
+    pub fn version(&self) -> Option<u16> {
+        let mut buffer = [0; MAX_NAME_LENGTH];
+
+        normalize::split_version(self.identifier_into(&mut buffer)?).1
+    }
+
+    /// Target of a Rock Ridge symbolic link written into `buffer`, `None` if the entry is not a link.
+    pub fn symlink_target_into<'a>(&self, buffer: &'a mut [u8]) -> Option<&'a str> {
+        self.with_system_use(|data| extensions::rock_ridge::symlink_target_into(data, buffer))
+    }
+
+    /// Rock Ridge `PX` attributes: mode, links, owner and serial number
+    pub fn posix_attributes(&self) -> Option<extensions::rock_ridge::PosixAttributes> {
+        self.with_system_use(extensions::rock_ridge::posix_attributes)
+    }
+
+    /// Rock Ridge `TF` timestamps
+    pub fn timestamps(&self) -> extensions::rock_ridge::Timestamps {
+        self.with_system_use(extensions::rock_ridge::timestamps)
+    }
+
+    /// Rock Ridge `ZF` entry of compressed files
+    pub fn compression(&self) -> Option<extensions::rock_ridge::Compression> {
+        self.with_system_use(extensions::rock_ridge::compression)
+    }
+
+    /// Finder type, creator and flags from an Apple `AA` entry
+    pub fn finder_info(&self) -> Option<extensions::apple::FinderInfo> {
+        self.with_system_use(extensions::apple::finder_info)
+    }
+
+    /// Decodes the name of the entry.
+    ///
+    /// Plain names are borrowed, Rock Ridge names are assembled from their `NM` entries
+    /// and Joliet names have to be converted from UCS-2.
+    #[cfg(feature = "alloc")]
+    pub fn name(&self) -> Cow<'buf, str> {
+        match self.rock_ridge_name(&self.full_system_use()) {
+            Some(name) => Cow::Owned(name),
+            None => self.identifier_name(),
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    fn rock_ridge_name(&self, system_use: &[u8]) -> Option<String> {
+        match self.joliet {
+            true => None,
+            false => extensions::rock_ridge::name(system_use),
+        }
+    }
+
+    /// Decodes the file identifier itself, ignoring Rock Ridge names.
+    #[cfg(feature = "alloc")]
+    pub fn identifier_name(&self) -> Cow<'buf, str> {
+        match self.identifier {
+            [] | [0] => Cow::Borrowed("."),
+            [1] => Cow::Borrowed(".."),
+            identifier if self.joliet => Cow::Owned(extensions::joliet::parse_name(identifier)),
+            identifier => String::from_utf8_lossy(identifier),
+        }
+    }
+
+    /// Target of a Rock Ridge symbolic link, `None` if the entry is not a link.
+    #[cfg(feature = "alloc")]
+    pub fn symlink_target(&self) -> Option<String> {
+        extensions::rock_ridge::symlink_target(&self.full_system_use())
+    }
+
+    /// Copies the record and decodes its name into an owned entry.
+    #[cfg(feature = "alloc")]
+    pub fn to_entry(&self) -> ISODirectoryEntry {
+        let identifier = self.identifier_name().into_owned();
+        let system_use = self.full_system_use().into_owned();
+
+        ISODirectoryEntry {
+            record: self.record.clone(),
+            name: self.rock_ridge_name(&system_use).unwrap_or_else(|| identifier.clone()),
+            identifier,
+            system_use,
+            resource_fork: self.resource_fork.clone(),
+            extended_attributes: crate::xar::ExtendedAttributes::read(self.device, self.record),
         }
     }
 }
 
-impl Iterator for &DirectoryIter<'_> {
-    type Item = ISODirectoryEntry;
+/// Iterates over the records of a directory extent.
+///
+/// The directory is read one whole sector at a time, records are parsed in place.
+pub struct DirectoryIter<'iso, D: Read> {
+    iso: &'iso ISO9660<D>,
+    sector: [u8; DISK_SECTOR_SIZE],
+    /// LBA of the next sector to be loaded
+    next_lba: u64,
+    /// Bytes of the extent that were not loaded yet, unknown until the first sector is read
+    remaining: Option<u64>,
+    /// Position of the next record inside `sector`, `None` if the sector must be (re)loaded
+    offset: Option<usize>,
+    /// Copy of an associated record while the one after it is looked at
+    held: [u8; 256],
+    options: ListOptions,
+    /// Volume of the set the directory is recorded on
+    volume: u16,
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let mut record = ISODirectoryRecord::default();
-        let ptr = record.as_mut_bytes();
+impl<'iso, D: Read> DirectoryIter<'iso, D> {
+    pub(crate) fn new(iso: &'iso ISO9660<D>, lba: u32, volume: u16) -> Self {
+        Self {
+            iso,
+            sector: [0; DISK_SECTOR_SIZE],
+            next_lba: lba as u64,
+            remaining: None,
+            offset: None,
+            held: [0; 256],
+            options: ListOptions::ALL,
+            volume,
+        }
+    }
+
+    /// Leaves out hidden or associated records, everything is listed by default.
+    pub fn options(mut self, options: ListOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    fn load_sector(&mut self) -> Option<()> {
+        if self.remaining == Some(0) {
+            return None;
+        }
 
         self.iso
-            .borrow_mut()
             .device
-            .read(*self.byte_offset.borrow() as _, ptr);
+            .read_volume(self.volume, block_position(self.next_lba)?, &mut self.sector)?;
 
-        if record.length == 0 {
-            return None;
+        // The first record of every directory is "." which knows the size of the whole extent.
+        let size = match self.remaining {
+            Some(size) => size,
+            None => ISODirectoryRecord::ref_from_prefix(&self.sector).ok()?.0.data_length.get() as u64,
+        };
+
+        self.remaining = Some(size.saturating_sub(DISK_SECTOR_SIZE as u64));
+        self.next_lba += 1;
+        self.offset = Some(0);
+
+        Some(())
+    }
+
+    /// Position and length of the next record inside `sector`, loading sectors as needed.
+    fn next_record(&mut self) -> Option<(usize, usize)> {
+        let header_size = size_of::<ISODirectoryRecord>();
+
+        loop {
+            let Some(offset) = self.offset else {
+                self.load_sector()?;
+                continue;
+            };
+
+            let length = self.sector.get(offset).copied().unwrap_or(0) as usize;
+
+            // Records never cross sector boundaries, zero length means padding up to the next sector.
+            if length < header_size || offset + length > DISK_SECTOR_SIZE {
+                self.offset = None;
+                continue;
+            }
+
+            self.offset = Some(offset + length);
+
+            return Some((offset, length));
         }
+    }
+
+    /// Returns the next record without allocating anything.
+    ///
+    /// An associated file is returned as the `resource_fork` of the file recorded right after
+    /// it with the same identifier, and on its own only if there is no such file.
+    pub fn next_raw(&mut self) -> Option<RawDirectoryEntry<'_>> {
+        let joliet = self.iso.flags.contains(ISOInternalFlags::HasJoliet);
+        let hidden = |flags: FileFlags| flags.contains(FileFlags::Hidden);
+
+        // Whether the record is in `held` rather than `sector`, where it is and its resource fork
+        let (from_held, start, length, fork) = loop {
+            let (start, length) = self.next_record()?;
+            let flags = split_record(&self.sector[start..start + length])?.0.file_flags();
+
+            if !flags.contains(FileFlags::Associated) {
+                if self.options.hide_hidden && hidden(flags) {
+                    continue;
+                }
+
+                break (false, start, length, None);
+            }
+
+            // The next record may be in another sector, so the associated one is kept aside
+            self.held[..length].copy_from_slice(&self.sector[start..start + length]);
+
+            if let Some((next, next_length)) = self.next_record() {
+                let (associated, identifier, _) = split_record(&self.held[..length])?;
+                let (record, next_identifier, _) = split_record(&self.sector[next..next + next_length])?;
+                let next_flags = record.file_flags();
+
+                if !next_flags.contains(FileFlags::Associated) && next_identifier == identifier {
+                    // A hidden file takes its resource fork with it
+                    if self.options.hide_hidden && hidden(next_flags) {
+                        continue;
+                    }
 
-        let main_part_size = size_of::<ISODirectoryRecord>();
+                    break (false, next, next_length, Some(associated.clone()));
+                }
 
-        let name: String;
+                self.offset = Some(next);
+            }
 
-        if self.iso.borrow().flags.contains(ISOInternalFlags::HasJoliet) {
-            name = self.iso.borrow_mut().read_joliet_name(
-                *self.byte_offset.borrow() + main_part_size,
-                record.file_identifier_length as _
-            ).expect("Expected a valid UCS-2 name");
+            if self.options.hide_associated || (self.options.hide_hidden && hidden(flags)) {
+                continue;
+            }
+
+            break (true, 0, length, None);
+        };
+
+        let data = if from_held {
+            &self.held[start..start + length]
         } else {
-            let main_part_size = main_part_size + record.file_identifier_length as usize;
-            let extension_size = record.length as usize - main_part_size;
+            &self.sector[start..start + length]
+        };
 
-            // println!("Ext size: {}", extension_size);
+        raw_entry(data, fork, joliet, &self.iso.device, self.volume)
+    }
+}
 
-            let rr_name = self.iso.borrow_mut().read_rock_ridge_name(
-                *self.byte_offset.borrow(),
-                main_part_size,
-                extension_size,
-            );
+/// Splits a record into its fixed part, file identifier and system use area.
+fn split_record(data: &[u8]) -> Option<(&ISODirectoryRecord, &[u8], &[u8])> {
+    let (record, rest) = ISODirectoryRecord::ref_from_prefix(data).ok()?;
 
-            name = if let Some(n) = rr_name {
-                n
-            } else {
-                let size = record.file_identifier_length as usize;
+    let identifier_length = (record.file_identifier_length as usize).min(rest.len());
+    let (identifier, rest) = rest.split_at(identifier_length);
 
-                let mut result = vec![0; size];
+    // Padding byte keeps the system use area at an even offset.
+    let system_use = if identifier_length.is_multiple_of(2) && !rest.is_empty() {
+        &rest[1..]
+    } else {
+        rest
+    };
 
-                self.iso.borrow_mut().device.read(
-                    *self.byte_offset.borrow() + size_of::<ISODirectoryRecord>(),
-                    &mut result,
-                );
+    Some((record, identifier, system_use))
+}
 
-                let final_name = if result[0] == 0 {
-                    "."
-                } else if result[0] == 1 {
-                    ".."
-                } else {
-                    str::from_utf8(&result).unwrap()
-                };
+fn raw_entry<'buf>(
+    data: &'buf [u8],
+    resource_fork: Option<ISODirectoryRecord>,
+    joliet: bool,
+    device: &'buf dyn Read,
+    volume: u16,
+) -> Option<RawDirectoryEntry<'buf>> {
+    let (record, identifier, system_use) = split_record(data)?;
 
-                final_name.to_owned()
-            };
-        }
+    Some(RawDirectoryEntry {
+        record,
+        identifier,
+        system_use,
+        resource_fork,
+        joliet,
+        device,
+        volume,
+    })
+}
 
-        *self.byte_offset.borrow_mut() += record.length as usize;
+#[cfg(feature = "alloc")]
+impl<D: Read> Iterator for DirectoryIter<'_, D> {
+    type Item = ISODirectoryEntry;
 
-        Some(ISODirectoryEntry { record, name })
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_raw().map(|raw| raw.to_entry())
     }
 }
 
-pub struct DescriptorIterator<'dev> {
-    device: &'dev mut dyn Read,
-    position: usize,
+pub struct DescriptorIterator<'dev, D: Read + ?Sized = dyn Read + 'dev> {
+    device: &'dev D,
+    position: u64,
 }
 
-impl<'a> DescriptorIterator<'a> {
-    pub fn new(dev: &'a mut dyn Read) -> Self {
+impl<'a, D: Read + ?Sized> DescriptorIterator<'a, D> {
+    pub fn new(dev: &'a D) -> Self {
         Self {
             device: dev,
             position: PRIMARY_VOLUME_DESCRIPTOR_POSITION,
@@ -103,25 +463,32 @@ impl<'a> DescriptorIterator<'a> {
     }
 }
 
-impl<'a> Iterator for DescriptorIterator<'a> {
+impl<D: Read + ?Sized> Iterator for DescriptorIterator<'_, D> {
     type Item = Descriptor;
 
     fn next(&mut self) -> Option<Self::Item> {
         let mut buffer = [0u8; 2048];
 
-        self.device.read(self.position, &mut buffer)?;
-
         loop {
+            self.device.read(self.position, &mut buffer)?;
+            self.position += core::mem::size_of::<Descriptor>() as u64;
+
+            // UDF volume recognition descriptors may follow the ISO9660 set or replace it
+            match &buffer[1..6] {
+                STANDARD_ID => {}
+                b"BEA01" | b"NSR02" | b"NSR03" | b"TEA01" | b"BOOT2" | b"CDW02" => continue,
+                _ => return None,
+            }
+
             let Ok(descriptor): Result<Self::Item, _> = zerocopy::try_transmute!(buffer) else {
-                // invalid type, skip to next descriptor immediately
-                self.position += core::mem::size_of::<Descriptor>();
+                // invalid type, skip to the next descriptor
                 continue;
             };
 
-            break if descriptor.desc_type == DescriptorType::Terminator {
+            return if descriptor.desc_type == DescriptorType::Terminator {
+                self.position -= core::mem::size_of::<Descriptor>() as u64;
                 None
             } else {
-                self.position += core::mem::size_of::<Descriptor>();
                 Some(descriptor)
             };
         }
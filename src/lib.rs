@@ -1,29 +1,65 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 #![deny(unsafe_code)]
 
+pub mod cache;
+#[cfg(feature = "cso")]
+pub mod cso;
 pub mod descriptors;
+#[cfg(feature = "alloc")]
+pub mod disc;
+pub mod eltorito;
+#[cfg(feature = "std")]
+pub mod extract;
+#[cfg(feature = "alloc")]
+pub mod glob;
 pub mod helpers;
+#[cfg(feature = "alloc")]
+pub mod hfs;
 pub mod extensions;
 pub mod types;
 pub mod iter;
-
-use alloc::vec;
+pub mod normalize;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+#[cfg(feature = "std")]
+pub mod progress;
+#[cfg(feature = "alloc")]
+pub mod split;
+#[cfg(feature = "std")]
+pub mod stream;
+#[cfg(feature = "std")]
+pub mod tar;
+#[cfg(feature = "std")]
+pub mod verify;
+#[cfg(feature = "alloc")]
+pub mod udf;
+#[cfg(feature = "alloc")]
+pub mod volume_set;
+#[cfg(feature = "alloc")]
+pub mod walk;
+#[cfg(feature = "zisofs")]
+mod zisofs;
+#[cfg(feature = "std")]
+pub mod writer;
+pub mod xar;
 
 /// Each sector in ISO is 2048 bytes (imho)
 const DISK_SECTOR_SIZE: usize = 2048;
 
-const PRIMARY_VOLUME_DESCRIPTOR_POSITION: usize = 0x8000;
+const PRIMARY_VOLUME_DESCRIPTOR_POSITION: u64 = 0x8000;
+
+/// Byte position of a logical block, `None` on overflow.
+fn block_position(lba: u64) -> Option<u64> {
+    lba.checked_mul(DISK_SECTOR_SIZE as u64)
+}
 
-//const FLAG_HIDDEN: u8 = 1 << 0;
-const FLAG_DIRECTORY: u8 = 1 << 1;
-//const FLAG_ASSOCIATED: u8 = 1 << 2;
-//const FLAG_EXTENDED_ATTR: u8 = 1 << 3;
 
+#[cfg(feature = "alloc")]
 extern crate alloc;
 
+#[cfg(feature = "alloc")]
 use alloc::{
-    borrow::ToOwned,
-    boxed::Box,
+    borrow::Cow,
     string::String,
     vec::Vec,
 };
@@ -33,12 +69,31 @@ use bitflags::bitflags;
 bitflags! {
     pub struct ISOInternalFlags: u32 {
         const HasJoliet = (1 << 0);
+        const HasRockRidge = (1 << 1);
+    }
+}
+
+bitflags! {
+    /// File flags of a directory record.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct FileFlags: u8 {
+        /// The existence bit: the user doesn't need to know about the file
+        const Hidden = (1 << 0);
+        const Directory = (1 << 1);
+        /// Associated file, e.g. the resource fork of a Mac file
+        const Associated = (1 << 2);
+        /// The record format is given in the extended attribute record
+        const Record = (1 << 3);
+        /// Owner, group and permissions are given in the extended attribute record
+        const Protection = (1 << 4);
+        /// The file continues in the next record
+        const MultiExtent = (1 << 7);
     }
 }
 
 /// Represents date and time packed into every DirectoryEntry
 #[repr(C, packed(1))]
-#[derive(Clone, Copy, Debug, Default, FromBytes, Immutable, IntoBytes)]
+#[derive(Clone, Copy, Debug, Default, FromBytes, Immutable, IntoBytes, KnownLayout)]
 pub struct ISODateTime {
     pub year: u8,
     pub month: u8,
@@ -49,9 +104,42 @@ pub struct ISODateTime {
     pub gmt_offset: u8,
 }
 
+impl ISODateTime {
+    /// Seconds since the Unix epoch
+    pub fn unix_time(&self) -> i64 {
+        let time = types::unix_time(
+            1900 + self.year as i64,
+            self.month,
+            self.day,
+            self.hour,
+            self.minute,
+            self.second,
+        );
+
+        // The offset from GMT is stored in 15 minute intervals.
+        time - (self.gmt_offset as i8) as i64 * 15 * 60
+    }
+
+    /// UTC date and time of a Unix timestamp, clamped to the years 1900 to 2155 the format can hold.
+    pub fn from_unix_time(time: i64) -> Self {
+        let time = time.clamp(types::unix_time(1900, 1, 1, 0, 0, 0), types::unix_time(2155, 12, 31, 23, 59, 59));
+        let (year, month, day, hour, minute, second) = types::civil_time(time);
+
+        Self {
+            year: (year - 1900) as u8,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+            gmt_offset: 0,
+        }
+    }
+}
+
 /// Represents a raw directory record (name is not counted in)
 #[repr(C, packed(1))]
-#[derive(Debug, Default, Clone, FromBytes, Immutable, IntoBytes)]
+#[derive(Debug, Default, Clone, FromBytes, Immutable, IntoBytes, KnownLayout)]
 pub struct ISODirectoryRecord {
     pub(crate) length: u8,
     pub(crate) xar_length: u8,
@@ -65,147 +153,455 @@ pub struct ISODirectoryRecord {
     pub(crate) file_identifier_length: u8, // Here comes the name which size is dynamic
 }
 
+impl ISODirectoryRecord {
+    pub const fn is_folder(&self) -> bool {
+        (self.flags & FileFlags::Directory.bits()) != 0
+    }
+
+    pub const fn file_flags(&self) -> FileFlags {
+        FileFlags::from_bits_retain(self.flags)
+    }
+
+    pub fn file_size(&self) -> u64 {
+        self.data_length.get() as u64
+    }
+
+    /// Blocks taken by the extended attribute record at the start of the extent
+    pub const fn xar_length(&self) -> u8 {
+        self.xar_length
+    }
+
+    /// First block of the data, after the extended attribute record
+    pub fn data_lba(&self) -> u32 {
+        self.lba.get().saturating_add(self.xar_length as u32)
+    }
+
+    /// Volume of the set the extent is recorded on
+    pub fn volume_sequence_number(&self) -> u16 {
+        self.volume_seq_number.get()
+    }
+
+    /// Blocks in every file unit of an interleaved file, 0 if the data is contiguous
+    pub const fn unit_size(&self) -> u8 {
+        self.unit_size
+    }
+
+    /// Blocks skipped after every file unit of an interleaved file
+    pub const fn interleave_gap_size(&self) -> u8 {
+        self.interleave_gap_size
+    }
+
+    pub const fn is_interleaved(&self) -> bool {
+        self.unit_size != 0 && self.interleave_gap_size != 0
+    }
+}
+
 /// Represents a human-readable directory record.
+#[cfg(feature = "alloc")]
 #[derive(Debug, Default, Clone)]
 pub struct ISODirectoryEntry {
     pub record: ISODirectoryRecord,
+    /// Best available name: Rock Ridge, Joliet or the plain identifier
     pub name: String,
+    /// File identifier as recorded in the directory, e.g. `README.TXT;1`
+    pub identifier: String,
+    /// System use area (Rock Ridge and friends live here)
+    pub system_use: Vec<u8>,
+    /// Associated file holding the resource fork of Mac files
+    pub resource_fork: Option<ISODirectoryRecord>,
+    /// Extended attribute record, if the extent starts with one
+    pub extended_attributes: Option<xar::ExtendedAttributes>,
 }
 
+#[cfg(feature = "alloc")]
 impl ISODirectoryEntry {
     /// Simple function that checks is this entry a folder
     pub const fn is_folder(&self) -> bool {
-        (self.record.flags & FLAG_DIRECTORY) != 0
+        self.record.is_folder()
     }
 
     pub const fn is_file(&self) -> bool {
         !self.is_folder()
     }
 
+    pub const fn file_flags(&self) -> FileFlags {
+        self.record.file_flags()
+    }
+
+    /// Whether the existence bit asks to hide the entry from the user
+    pub const fn is_hidden(&self) -> bool {
+        self.file_flags().contains(FileFlags::Hidden)
+    }
+
+    /// Whether this is an associated file that wasn't paired with the entry it belongs to
+    pub const fn is_associated(&self) -> bool {
+        self.file_flags().contains(FileFlags::Associated)
+    }
+
+    /// Whether the extended attribute record gives the record format
+    pub const fn has_record_format(&self) -> bool {
+        self.file_flags().contains(FileFlags::Record)
+    }
+
+    /// Whether the extended attribute record gives owner, group and permissions
+    pub const fn has_protection(&self) -> bool {
+        self.file_flags().contains(FileFlags::Protection)
+    }
+
+    /// Whether the file continues in the next record of the directory
+    pub const fn is_multi_extent(&self) -> bool {
+        self.file_flags().contains(FileFlags::MultiExtent)
+    }
+
     pub fn lsb_position(&self) -> u32 {
         self.record.lba.get()
     }
 
-    pub fn file_size(&self) -> u32 {
-        self.record.data_length.get()
+    /// First block of the data or directory records, after the extended attribute record
+    pub fn data_lba(&self) -> u32 {
+        self.record.data_lba()
+    }
+
+    /// Volume of the set the data is recorded on
+    pub fn volume_sequence_number(&self) -> u16 {
+        self.record.volume_sequence_number()
+    }
+
+    /// Whether the data is recorded in file units separated by gaps
+    pub const fn is_interleaved(&self) -> bool {
+        self.record.is_interleaved()
+    }
+
+    /// Blocks in every file unit, 0 if the data is contiguous
+    pub const fn unit_size(&self) -> u8 {
+        self.record.unit_size()
+    }
+
+    /// Blocks skipped after every file unit
+    pub const fn interleave_gap_size(&self) -> u8 {
+        self.record.interleave_gap_size()
+    }
+
+    /// Size of the file, uncompressed if the data is compressed with zisofs and this build can read it
+    pub fn file_size(&self) -> u64 {
+        #[cfg(feature = "zisofs")]
+        if let Some(compression) = self.compression().filter(zisofs::is_supported) {
+            return compression.real_size;
+        }
+
+        self.stored_size()
+    }
+
+    /// Size of the data as stored in the extent
+    pub fn stored_size(&self) -> u64 {
+        self.record.data_length.get() as u64
+    }
+
+    /// Rock Ridge `ZF` entry of compressed files
+    pub fn compression(&self) -> Option<extensions::rock_ridge::Compression> {
+        extensions::rock_ridge::compression(&self.system_use)
+    }
+
+    /// Version number from the `;N` suffix of the identifier
+    pub fn version(&self) -> Option<u16> {
+        normalize::split_version(&self.identifier).1
+    }
+
+    pub fn normalized_name(&self, options: &normalize::NameOptions) -> Cow<'_, str> {
+        options.normalize(&self.name)
+    }
+
+    /// Rock Ridge `PX` attributes: mode, links, owner and serial number
+    pub fn posix_attributes(&self) -> Option<extensions::rock_ridge::PosixAttributes> {
+        extensions::rock_ridge::posix_attributes(&self.system_use)
+    }
+
+    /// Rock Ridge `TF` timestamps
+    pub fn timestamps(&self) -> extensions::rock_ridge::Timestamps {
+        extensions::rock_ridge::timestamps(&self.system_use)
+    }
+
+    /// Modification time from Rock Ridge, or the recording time of the record
+    pub fn modified(&self) -> i64 {
+        self.timestamps().modify.unwrap_or_else(|| self.record.datetime.unix_time())
+    }
+
+    /// Target of a Rock Ridge symbolic link, `None` if the entry is not a link.
+    pub fn symlink_target(&self) -> Option<String> {
+        extensions::rock_ridge::symlink_target(&self.system_use)
+    }
+
+    /// Finder type, creator and flags from an Apple `AA` entry
+    pub fn finder_info(&self) -> Option<extensions::apple::FinderInfo> {
+        extensions::apple::finder_info(&self.system_use)
+    }
+
+    /// Size of the resource fork, 0 if there is none
+    pub fn resource_size(&self) -> u64 {
+        self.resource_fork.as_ref().map_or(0, ISODirectoryRecord::file_size)
     }
 }
 
 pub mod io;
 pub use io::Read;
-use zerocopy::{FromBytes, Immutable, IntoBytes};
+use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout};
 
 use crate::{descriptors::DescriptorType, iter::{DescriptorIterator, DirectoryIter}};
+#[cfg(feature = "alloc")]
+use crate::{glob::{Glob, Pattern}, walk::Walk};
 
 /// Main structure of the crate.
 /// Used to read and parse data from the `device`
-pub struct ISO9660 {
+///
+/// All reads go through `&self`, so several directory iterators can be open at once and,
+/// if the device is `Send + Sync`, one image can be shared between threads.
+///
+/// The device is stored inline. Pass `&D` or `&mut D` to only borrow it.
+pub struct ISO9660<D: Read> {
     root_directory: ISODirectoryRecord,
     flags: ISOInternalFlags,
-    device: Box<dyn Read>,
+    device: D,
 }
 
-impl ISO9660 {
-    pub fn from_device(mut device: impl Read + 'static) -> Option<ISO9660> {
-        let mut flags = ISOInternalFlags::empty();
+impl<D: Read> ISO9660<D> {
+    pub fn from_device(device: D) -> Option<ISO9660<D>> {
+        let pvd_desc = DescriptorIterator::new(&device).find(|x| x.desc_type == DescriptorType::PrimaryVolume)?;
+        let root_directory = ISODirectoryRecord::read_from_prefix(&pvd_desc.try_as_pvd()?.directory_entry).ok()?.0;
 
-        let pvd_desc = DescriptorIterator::new(&mut device).find(|x| x.desc_type == DescriptorType::PrimaryVolume)?;
-        let mut main_descriptor = pvd_desc.try_as_pvd()?;
+        let mut iso = ISO9660 {
+            root_directory,
+            flags: ISOInternalFlags::empty(),
+            device,
+        };
 
-        let svd: Option<descriptors::Descriptor> = DescriptorIterator::new(&mut device).find(|x| x.desc_type == DescriptorType::SupplementaryVolume);
+        let svd = DescriptorIterator::new(&iso.device).find(|x| x.desc_type == DescriptorType::SupplementaryVolume);
 
         if let Some(ref svd) = svd {
-            main_descriptor = svd.try_as_svd()?;
+            iso.root_directory = ISODirectoryRecord::read_from_prefix(&svd.try_as_svd()?.directory_entry).ok()?.0;
+            iso.flags |= ISOInternalFlags::HasJoliet;
 
-            flags |= ISOInternalFlags::HasJoliet;
+            return Some(iso);
         }
 
-        let root_dir = ISODirectoryRecord::read_from_prefix(&main_descriptor.directory_entry).ok()?.0;
+        // Rock Ridge is announced by an `SP` entry in the first record of the root directory.
+        if iso.read_root().next_raw().is_some_and(|root| root.system_use.starts_with(b"SP")) {
+            iso.flags |= ISOInternalFlags::HasRockRidge;
+        }
 
-        Some(ISO9660 {
-            root_directory: root_dir,
-            flags: flags,
-            device: Box::new(device),
-        })
+        Some(iso)
     }
 
-    pub fn descriptors(&mut self) -> DescriptorIterator<'_> {
-        DescriptorIterator::new(self.device.as_mut())
+    /// Whether names and metadata come from Rock Ridge
+    pub fn has_rock_ridge(&self) -> bool {
+        self.flags.contains(ISOInternalFlags::HasRockRidge)
     }
 
-    fn read_rock_ridge_name(
-        &mut self,
-        byte_offset: usize,
-        main_part_size: usize,
-        extension_size: usize,
-    ) -> Option<String> {
-        let mut address = byte_offset + main_part_size;
-        if address % 2 != 0 {
-            address += 1;
-        }
+    /// Whether names come from the Joliet tree
+    pub fn has_joliet(&self) -> bool {
+        self.flags.contains(ISOInternalFlags::HasJoliet)
+    }
 
-        let mut extension_data: Vec<u8> = vec![0; extension_size];
-        self.device.read(address, &mut extension_data);
+    /// El Torito boot catalog, `None` if the image is not bootable.
+    pub fn boot_catalog(&self) -> Option<eltorito::BootCatalog> {
+        let lba = self.descriptors().find_map(|descriptor| descriptor.try_as_boot_record()?.catalog_lba())?;
+        let mut sector = [0u8; DISK_SECTOR_SIZE];
 
-        let rock_ridge_data = extensions::rock_ridge::parse(&extension_data);
+        self.device.read(block_position(lba as u64)?, &mut sector)?;
 
-        for i in rock_ridge_data {
-            if let extensions::rock_ridge::Entity::Name { name } = i {
-                return Some(name.to_owned());
-            }
-        }
+        eltorito::BootCatalog::new(lba, sector)
+    }
 
-        None
+    /// Reads data of a boot image, `offset` is relative to its first sector.
+    pub fn read_boot_image(&self, entry: &eltorito::BootEntry, offset: u64, data: &mut [u8]) -> Option<()> {
+        let position = block_position(entry.load_rba as u64)?;
+
+        self.device.read(position.checked_add(offset)?, data)
     }
 
-    fn read_joliet_name(
-        &mut self,
-        byte_offset: usize,
-        len: usize,
-    ) -> Option<String> {
-        let mut address = byte_offset;
-        if (address % 2 != 0) && len != 1 {
-            address += 1;
-        }
+    pub fn descriptors(&self) -> DescriptorIterator<'_, D> {
+        DescriptorIterator::new(&self.device)
+    }
+
+    /// Volume partitions set aside from the file structure.
+    pub fn partitions(&self) -> impl Iterator<Item = descriptors::VolumePartitionDescriptor> + '_ {
+        self.descriptors().filter_map(|descriptor| descriptor.try_as_partition().cloned())
+    }
+
+    /// Blocks of a volume partition as a device of their own.
+    pub fn partition_device(&self, partition: &descriptors::VolumePartitionDescriptor) -> Option<io::Region<&D>> {
+        let start = block_position(partition.location() as u64)?;
+        let size = block_position(partition.block_count() as u64)?;
+
+        Some(io::Region::new(&self.device, start, size))
+    }
 
-        let mut ucs2_name: Vec<u8> = vec![0; len];
-        self.device.read(address, &mut ucs2_name);
+    pub fn device(&self) -> &D {
+        &self.device
+    }
+
+    /// Gives mutable access to the device, e.g. to reconfigure a cache.
+    pub fn device_mut(&mut self) -> &mut D {
+        &mut self.device
+    }
 
-        extensions::joliet::parse_name(&ucs2_name)
+    /// Destroys the reader and gives the device back.
+    pub fn into_inner(self) -> D {
+        self.device
     }
 
-    pub fn read_directory(&mut self, start_lba: usize) -> DirectoryIter<'_> {
-        let byte_offset = start_lba * DISK_SECTOR_SIZE;
+    /// Lists the directory at `start_lba`, on the volume of the root directory.
+    pub fn read_directory(&self, start_lba: u32) -> DirectoryIter<'_, D> {
+        DirectoryIter::new(self, start_lba, self.root_directory.volume_sequence_number())
+    }
 
-        DirectoryIter::new(self, byte_offset)
+    /// Lists a directory given by its record, on the volume the record points to.
+    pub fn read_directory_record(&self, directory: &ISODirectoryRecord) -> DirectoryIter<'_, D> {
+        DirectoryIter::new(self, directory.data_lba(), directory.volume_sequence_number())
     }
 
+    /// Reads file data at `offset`. With the `zisofs` feature compressed files are decompressed.
+    ///
+    /// Compressed blocks aren't cached, each call inflates every block it touches: read them in
+    /// large, block aligned chunks (1 MiB is a multiple of every zisofs block size).
+    #[cfg(feature = "alloc")]
     pub fn read_file(
-        &mut self,
+        &self,
         directory_entry: &ISODirectoryEntry,
-        offset: usize,
+        offset: u64,
         data: &mut [u8],
     ) -> Option<()> {
-        if (directory_entry.record.flags & FLAG_DIRECTORY) != 0 {
-            return None;
+        #[cfg(feature = "zisofs")]
+        if let Some(compression) = directory_entry.compression().filter(zisofs::is_supported) {
+            let device = io::Extent {
+                device: &self.device,
+                record: &directory_entry.record,
+            };
+
+            return zisofs::read(&device, 0, directory_entry.stored_size(), &compression, offset, data);
         }
 
-        let position = directory_entry.lsb_position() as usize;
-        let data_length = directory_entry.file_size() as usize;
+        self.read_record(&directory_entry.record, offset, data)
+    }
 
-        if offset + data.len() > data_length {
+    /// Copies the data of a file into `output` in chunks, reporting progress.
+    /// On errors and cancellation `output` holds only a part of the file.
+    #[cfg(feature = "std")]
+    pub fn copy_file(
+        &self,
+        directory_entry: &ISODirectoryEntry,
+        output: &mut impl std::io::Write,
+        monitor: &progress::Monitor,
+    ) -> std::io::Result<()> {
+        const CHUNK_SIZE: usize = 1024 * 1024;
+
+        let size = directory_entry.file_size();
+        let tracker = monitor.start(size);
+        let mut buffer = alloc::vec![0u8; CHUNK_SIZE.min(usize::try_from(size).unwrap_or(usize::MAX))];
+        let mut offset = 0;
+
+        while offset < size {
+            tracker.check()?;
+
+            let length = (size - offset).min(buffer.len() as u64) as usize;
+            let chunk = &mut buffer[..length];
+
+            self.read_file(directory_entry, offset, chunk).ok_or_else(|| {
+                std::io::Error::other(alloc::format!("failed to read data at offset {offset}"))
+            })?;
+            output.write_all(chunk)?;
+
+            offset += length as u64;
+            tracker.advance(length as u64, &directory_entry.name);
+        }
+
+        Ok(())
+    }
+
+    /// Reads resource fork data at `offset`, stored in the associated file of the entry.
+    #[cfg(feature = "alloc")]
+    pub fn read_resource_fork(&self, directory_entry: &ISODirectoryEntry, offset: u64, data: &mut [u8]) -> Option<()> {
+        self.read_record(directory_entry.resource_fork.as_ref()?, offset, data)
+    }
+
+    /// Resource fork of the entry as a stream, `None` if it has none.
+    #[cfg(feature = "std")]
+    pub fn resource_fork(&self, directory_entry: &ISODirectoryEntry) -> Option<stream::RecordReader<'_, D>> {
+        Some(stream::RecordReader::new(self, directory_entry.resource_fork.clone()?))
+    }
+
+    /// Reads file data described by a bare record, e.g. one from [`DirectoryIter::next_raw`].
+    pub fn read_record(
+        &self,
+        record: &ISODirectoryRecord,
+        offset: u64,
+        data: &mut [u8],
+    ) -> Option<()> {
+        if record.is_folder() {
             return None;
         }
 
-        self.device
-            .read((position * DISK_SECTOR_SIZE) + offset, data);
+        if offset.checked_add(data.len() as u64)? > record.file_size() {
+            return None;
+        }
 
-        Some(())
+        io::Extent { device: &self.device, record }.read(offset, data)
     }
 
     #[inline]
-    pub fn read_root(&mut self) -> DirectoryIter<'_> {
-        self.read_directory(self.root_directory.lba.get() as usize)
+    pub fn read_root(&self) -> DirectoryIter<'_, D> {
+        self.read_directory_record(&self.root_directory)
+    }
+
+    /// Recursively walks the whole tree starting at the root directory.
+    #[cfg(feature = "alloc")]
+    pub fn walk(&self) -> Walk<'_, D> {
+        Walk::new(self, self.root_directory.clone(), String::from("/"))
+    }
+
+    /// Recursively walks the tree below `directory`, paths are built on top of `path`.
+    #[cfg(feature = "alloc")]
+    pub fn walk_from(&self, directory: &ISODirectoryEntry, path: &str) -> Walk<'_, D> {
+        Walk::new(self, directory.record.clone(), String::from(path))
+    }
+
+    /// Safely extracts the image into `destination`, see [`extract::Extractor`].
+    #[cfg(feature = "std")]
+    pub fn extract(&self, destination: impl AsRef<std::path::Path>) -> extract::Extractor<'_, D> {
+        extract::Extractor::new(self, destination.as_ref().to_path_buf())
+    }
+
+    /// Writes the image as a tar stream, see [`tar::TarExporter`].
+    #[cfg(feature = "std")]
+    pub fn tar(&self) -> tar::TarExporter<'_, D> {
+        tar::TarExporter::new(self)
+    }
+
+    /// Finds all entries whose path matches a glob pattern like `/Packages/*.rpm` or `**/vmlinuz*`.
+    /// Returns `None` if the pattern is malformed.
+    #[cfg(feature = "alloc")]
+    pub fn glob(&self, pattern: &str) -> Option<Glob<'_, D>> {
+        let pattern = Pattern::new(pattern)?;
+
+        Some(Glob::new(self, pattern))
+    }
+
+    /// Entry of the root directory named `/`, metadata comes from its `.` record.
+    #[cfg(feature = "alloc")]
+    pub fn root_entry(&self) -> ISODirectoryEntry {
+        let system_use = self
+            .read_root()
+            .next_raw()
+            .map(|dot| dot.system_use.to_vec())
+            .unwrap_or_default();
+
+        ISODirectoryEntry {
+            record: self.root_directory.clone(),
+            name: String::from("/"),
+            identifier: String::from("/"),
+            system_use,
+            resource_fork: None,
+            extended_attributes: xar::ExtendedAttributes::read(&self.device, &self.root_directory),
+        }
     }
 
     #[inline]
@@ -0,0 +1,139 @@
+//! Name normalization used for lenient lookups.
+//!
+//! Plain ISO9660 identifiers look like `README.TXT;1`: upper case, with a version
+//! suffix and sometimes a trailing dot (`MAKEFILE.;1`). These options let a lookup
+//! for `/readme.txt` still find them.
+
+#[cfg(feature = "alloc")]
+use alloc::{borrow::Cow, string::String};
+
+/// Unicode normalization form applied before comparing names.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum UnicodeForm {
+    /// Compare code points as they are
+    #[default]
+    None,
+    /// Canonical composition
+    #[cfg(feature = "unicode")]
+    Nfc,
+    /// Canonical decomposition
+    #[cfg(feature = "unicode")]
+    Nfd,
+}
+
+/// How names are normalized before being compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NameOptions {
+    /// Drop the `;N` version suffix
+    pub strip_version: bool,
+    /// Drop a trailing `.` left by names without an extension
+    pub strip_trailing_dot: bool,
+    pub case_insensitive: bool,
+    pub unicode: UnicodeForm,
+}
+
+impl NameOptions {
+    /// Names must be equal byte for byte.
+    pub const EXACT: Self = Self {
+        strip_version: false,
+        strip_trailing_dot: false,
+        case_insensitive: false,
+        unicode: UnicodeForm::None,
+    };
+
+    /// Behaves like the Linux and Windows drivers: no versions, no trailing dots, any case.
+    pub const LENIENT: Self = Self {
+        strip_version: true,
+        strip_trailing_dot: true,
+        case_insensitive: true,
+        unicode: UnicodeForm::None,
+    };
+
+    /// Strips the version suffix and the trailing dot, as requested. Never allocates.
+    fn strip<'a>(&self, name: &'a str) -> &'a str {
+        // "." and ".." are never touched.
+        if name == "." || name == ".." {
+            return name;
+        }
+
+        let mut name = name;
+
+        if self.strip_version {
+            name = split_version(name).0;
+        }
+
+        if self.strip_trailing_dot && name.len() > 1 {
+            name = name.strip_suffix('.').unwrap_or(name);
+        }
+
+        name
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn normalize<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        let mut result = Cow::Borrowed(self.strip(name));
+
+        #[cfg(feature = "unicode")]
+        {
+            use unicode_normalization::UnicodeNormalization;
+
+            match self.unicode {
+                UnicodeForm::None => {}
+                UnicodeForm::Nfc => result = Cow::Owned(result.nfc().collect::<String>()),
+                UnicodeForm::Nfd => result = Cow::Owned(result.nfd().collect::<String>()),
+            }
+        }
+
+        if self.case_insensitive && result.chars().any(|c| c.is_uppercase()) {
+            result = Cow::Owned(result.chars().flat_map(char::to_lowercase).collect::<String>());
+        }
+
+        result
+    }
+
+    /// Compares two names after normalizing both of them, without allocating.
+    pub fn matches(&self, a: &str, b: &str) -> bool {
+        let (a, b) = (self.strip(a), self.strip(b));
+
+        match self.unicode {
+            UnicodeForm::None => self.chars_equal(a.chars(), b.chars()),
+            #[cfg(feature = "unicode")]
+            UnicodeForm::Nfc => {
+                use unicode_normalization::UnicodeNormalization;
+
+                self.chars_equal(a.nfc(), b.nfc())
+            }
+            #[cfg(feature = "unicode")]
+            UnicodeForm::Nfd => {
+                use unicode_normalization::UnicodeNormalization;
+
+                self.chars_equal(a.nfd(), b.nfd())
+            }
+        }
+    }
+
+    fn chars_equal(&self, a: impl Iterator<Item = char>, b: impl Iterator<Item = char>) -> bool {
+        if self.case_insensitive {
+            a.flat_map(char::to_lowercase).eq(b.flat_map(char::to_lowercase))
+        } else {
+            a.eq(b)
+        }
+    }
+}
+
+impl Default for NameOptions {
+    fn default() -> Self {
+        Self::EXACT
+    }
+}
+
+/// Splits `NAME.EXT;N` into `NAME.EXT` and `N`.
+/// Names without a valid version suffix are returned unchanged.
+pub fn split_version(name: &str) -> (&str, Option<u16>) {
+    match name.rsplit_once(';') {
+        Some((base, version)) if !version.is_empty() && version.bytes().all(|b| b.is_ascii_digit()) => {
+            (base, version.parse().ok())
+        }
+        _ => (name, None),
+    }
+}
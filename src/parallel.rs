@@ -0,0 +1,213 @@
+//! Parallel extraction and hashing on a rayon thread pool.
+//!
+//! Work is split by extent: every file is a unit of work, large ones are cut into chunks.
+//! Devices are read positionally through `&self`, so all threads share one device and no
+//! locking is involved as long as the device itself doesn't lock (`std::fs::File` doesn't).
+//! Work runs on the current rayon pool, use `ThreadPool::install` to pick another one.
+
+use std::{fs::File, io, sync::Arc};
+
+use digest::{Digest, Output};
+use rayon::prelude::*;
+
+use crate::{
+    extract::{self, Extractor, Failure, PendingFile, Report, SkipReason},
+    progress::{Cancelled, Monitor, Tracker},
+    walk::WalkEntry,
+    ISODirectoryEntry, Read, ISO9660,
+};
+
+/// Largest piece of a file handled by one task
+const CHUNK_SIZE: u64 = 4 * 1024 * 1024;
+
+/// Hash of a file computed by [`ISO9660::hash_files`].
+pub struct FileHash<H: Digest> {
+    /// Absolute path inside the image
+    pub path: String,
+    pub entry: ISODirectoryEntry,
+    /// `None` if the data couldn't be read
+    pub hash: Option<Output<H>>,
+}
+
+impl<D: Read + Sync> ISO9660<D> {
+    /// Hashes every regular file of the image in parallel, e.g. `iso.hash_files::<sha2::Sha256>()`.
+    /// Results come in the order of [`ISO9660::walk`].
+    pub fn hash_files<H: Digest + Send>(&self) -> Vec<FileHash<H>> {
+        let files = self
+            .walk()
+            .filter(|item| item.entry.is_file() && !item.is_symlink())
+            .collect();
+
+        self.hash_entries(files, &Monitor::NONE).unwrap_or_default()
+    }
+
+    /// Hashes the given entries in parallel, results keep their order.
+    pub fn hash_entries<H: Digest + Send>(&self, items: Vec<WalkEntry>, monitor: &Monitor) -> Result<Vec<FileHash<H>>, Cancelled> {
+        let tracker = monitor.start(items.iter().map(|item| item.entry.file_size()).sum());
+
+        // Threads take contiguous runs of this order, so each of them reads the disc mostly sequentially.
+        let mut order: Vec<usize> = (0..items.len()).collect();
+        order.sort_by_key(|&index| items[index].entry.lsb_position());
+
+        let mut hashes: Vec<(usize, Option<Output<H>>)> = order
+            .into_par_iter()
+            .map_init(Vec::new, |buffer, index| {
+                let hash = self.hash_entry::<H>(&items[index], buffer, &tracker)?;
+
+                Ok((index, hash))
+            })
+            .collect::<Result<_, Cancelled>>()?;
+
+        hashes.sort_unstable_by_key(|(index, _)| *index);
+
+        Ok(items
+            .into_iter()
+            .zip(hashes)
+            .map(|(item, (_, hash))| FileHash {
+                path: item.path,
+                entry: item.entry,
+                hash,
+            })
+            .collect())
+    }
+
+    /// `Ok(None)` if the data couldn't be read.
+    fn hash_entry<H: Digest>(&self, item: &WalkEntry, buffer: &mut Vec<u8>, tracker: &Tracker) -> Result<Option<Output<H>>, Cancelled> {
+        let size = item.entry.file_size();
+        let mut hasher = H::new();
+        let mut offset = 0;
+
+        buffer.resize(CHUNK_SIZE.min(size) as usize, 0);
+
+        while offset < size {
+            tracker.check()?;
+
+            let length = (size - offset).min(buffer.len() as u64) as usize;
+            let chunk = &mut buffer[..length];
+
+            if self.read_file(&item.entry, offset, chunk).is_none() {
+                return Ok(None);
+            }
+
+            hasher.update(&*chunk);
+
+            offset += length as u64;
+            tracker.advance(length as u64, &item.path);
+        }
+
+        Ok(Some(hasher.finalize()))
+    }
+}
+
+impl<D: Read + Sync> Extractor<'_, D> {
+    /// Same as [`Extractor::run`], but file data is copied by the current rayon pool.
+    pub fn run_parallel(self) -> io::Result<Report> {
+        let mut state = self.prepare()?;
+        let tracker = self.monitor.start(state.total_bytes());
+        let files = core::mem::take(&mut state.files);
+
+        // Files are created up front, so chunks can be written into them in any order. Chunks
+        // write through the handle they were created with, the path is never opened again.
+        let outputs: Vec<Result<Arc<File>, Failure>> = files
+            .par_iter()
+            .map(|file| {
+                tracker.check()?;
+
+                let output = extract::create_file(&file.output).map_err(SkipReason::Io)?;
+
+                output.set_len(file.entry.file_size()).map_err(SkipReason::Io)?;
+
+                Ok(Arc::new(output))
+            })
+            .collect();
+
+        let chunks: Vec<(usize, u64, Arc<File>)> = files
+            .iter()
+            .zip(&outputs)
+            .enumerate()
+            .filter_map(|(index, (file, output))| Some((index, file, output.as_ref().ok()?)))
+            .flat_map(|(index, file, output)| {
+                (0..file.entry.file_size()).step_by(CHUNK_SIZE as usize).map(move |offset| (index, offset, output.clone()))
+            })
+            .collect();
+
+        let failures: Vec<(usize, Failure)> = chunks
+            .into_par_iter()
+            .map_init(Vec::new, |buffer, (index, offset, output)| {
+                self.write_chunk(&files[index], &output, offset, buffer, &tracker).err().map(|failure| (index, failure))
+            })
+            .flatten()
+            .collect();
+
+        // Closes the files before their metadata is applied
+        let mut results: Vec<Result<(), Failure>> = outputs.into_iter().map(|output| output.map(drop)).collect();
+
+        for (index, failure) in failures {
+            if results[index].is_ok() {
+                results[index] = Err(failure);
+            }
+        }
+
+        let results: Vec<Result<(), Failure>> = files
+            .par_iter()
+            .zip(results)
+            .map(|(file, result)| {
+                result?;
+
+                self.apply_metadata(&file.entry, &file.output, false).map_err(SkipReason::Io)?;
+
+                Ok(())
+            })
+            .collect();
+
+        for (file, result) in files.iter().zip(results) {
+            state.file_done(file, result);
+        }
+
+        if state.report.cancelled {
+            return Ok(state.report);
+        }
+
+        Ok(self.finish(state))
+    }
+
+    fn write_chunk(&self, file: &PendingFile, output: &File, offset: u64, buffer: &mut Vec<u8>, tracker: &Tracker) -> Result<(), Failure> {
+        tracker.check()?;
+
+        let length = (file.entry.file_size() - offset).min(CHUNK_SIZE) as usize;
+
+        buffer.resize(length, 0);
+
+        self.iso.read_file(&file.entry, offset, buffer).ok_or(SkipReason::Read)?;
+
+        write_at(output, buffer, offset).map_err(SkipReason::Io)?;
+        tracker.advance(length as u64, &file.path);
+
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn write_at(file: &File, data: &[u8], offset: u64) -> io::Result<()> {
+    use std::os::unix::fs::FileExt;
+
+    file.write_all_at(data, offset)
+}
+
+#[cfg(windows)]
+fn write_at(file: &File, data: &[u8], offset: u64) -> io::Result<()> {
+    use std::os::windows::fs::FileExt;
+
+    let mut done = 0;
+
+    while done < data.len() {
+        match file.seek_write(&data[done..], offset + done as u64) {
+            Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+            Ok(n) => done += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(())
+}
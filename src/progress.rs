@@ -0,0 +1,121 @@
+//! Progress reporting and cancellation of long operations: extraction, hashing,
+//! verification and file copies.
+
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+/// Receives progress of an operation.
+///
+/// Parallel operations call it from several threads at once.
+pub trait Progress: Sync {
+    /// `done` out of `total` bytes of file data are processed, `path` is being worked on.
+    fn update(&self, done: u64, total: u64, path: &str);
+}
+
+impl<F: Fn(u64, u64, &str) + Sync> Progress for F {
+    fn update(&self, done: u64, total: u64, path: &str) {
+        self(done, total, path)
+    }
+}
+
+/// Stops an operation from another thread. Clones share the same state.
+#[derive(Debug, Default, Clone)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// The operation was cancelled through its [`CancellationToken`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl From<Cancelled> for io::Error {
+    fn from(_: Cancelled) -> Self {
+        io::Error::new(io::ErrorKind::Interrupted, "cancelled")
+    }
+}
+
+/// Progress observer and cancellation token handed to an operation. Both are optional.
+#[derive(Default, Clone, Copy)]
+pub struct Monitor<'a> {
+    progress: Option<&'a dyn Progress>,
+    token: Option<&'a CancellationToken>,
+}
+
+impl<'a> Monitor<'a> {
+    /// Neither reports nor can be cancelled.
+    pub const NONE: Monitor<'static> = Monitor {
+        progress: None,
+        token: None,
+    };
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn progress(mut self, progress: &'a dyn Progress) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    pub fn cancel_on(mut self, token: &'a CancellationToken) -> Self {
+        self.token = Some(token);
+        self
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_some_and(CancellationToken::is_cancelled)
+    }
+
+    /// Starts tracking an operation over `total` bytes.
+    pub(crate) fn start(&self, total: u64) -> Tracker<'a> {
+        Tracker {
+            monitor: *self,
+            total,
+            done: AtomicU64::new(0),
+        }
+    }
+}
+
+/// Byte counter of a single operation, shared between its threads.
+pub(crate) struct Tracker<'a> {
+    monitor: Monitor<'a>,
+    total: u64,
+    done: AtomicU64,
+}
+
+impl Tracker<'_> {
+    /// Fails once the operation is cancelled.
+    pub(crate) fn check(&self) -> Result<(), Cancelled> {
+        if self.monitor.is_cancelled() {
+            Err(Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Counts processed bytes of `path`.
+    pub(crate) fn advance(&self, bytes: u64, path: &str) {
+        let done = self.done.fetch_add(bytes, Ordering::Relaxed) + bytes;
+
+        if let Some(progress) = self.monitor.progress {
+            progress.update(done, self.total, path);
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! Images stored as several consecutive parts (`image.iso.001`, `image.iso.002`, ...).
+
+use alloc::vec::Vec;
+
+use crate::{io::read_from, Read};
+
+/// Concatenation of devices read as one image.
+pub struct SplitImage<D: Read> {
+    parts: Vec<D>,
+    /// Position of the end of every part in the whole image
+    ends: Vec<u64>,
+}
+
+impl<D: Read> SplitImage<D> {
+    /// Joins parts given in order with their sizes.
+    pub fn new(parts: impl IntoIterator<Item = (D, u64)>) -> Option<Self> {
+        let mut devices = Vec::new();
+        let mut ends = Vec::new();
+        let mut end = 0u64;
+
+        for (device, size) in parts {
+            end = end.checked_add(size)?;
+            devices.push(device);
+            ends.push(end);
+        }
+
+        Some(Self { parts: devices, ends })
+    }
+
+    /// Size of the whole image
+    pub fn size(&self) -> u64 {
+        self.ends.last().copied().unwrap_or(0)
+    }
+
+    pub fn parts(&self) -> &[D] {
+        &self.parts
+    }
+
+    pub fn into_inner(self) -> Vec<D> {
+        self.parts
+    }
+}
+
+#[cfg(feature = "std")]
+impl SplitImage<std::fs::File> {
+    /// Opens the first part (`image.iso.001`, or any other numeric suffix) and every next one
+    /// that exists, keeping the width of the number.
+    pub fn open(first: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        use std::io::{Error, ErrorKind};
+
+        let first = first.as_ref();
+        let invalid = || Error::new(ErrorKind::InvalidInput, "the first part must end with a numeric extension");
+
+        let name = first.file_name().and_then(|name| name.to_str()).ok_or_else(invalid)?;
+        let (stem, number) = name.rsplit_once('.').ok_or_else(invalid)?;
+
+        if number.is_empty() || !number.bytes().all(|byte| byte.is_ascii_digit()) {
+            return Err(invalid());
+        }
+
+        let width = number.len();
+        let mut current: u64 = number.parse().map_err(|_| invalid())?;
+        let mut parts = Vec::new();
+
+        loop {
+            let path = first.with_file_name(format!("{stem}.{current:0width$}"));
+
+            let file = match std::fs::File::open(&path) {
+                Ok(file) => file,
+                Err(e) if e.kind() == ErrorKind::NotFound && !parts.is_empty() => break,
+                Err(e) => return Err(e),
+            };
+
+            let size = file.metadata()?.len();
+
+            parts.push((file, size));
+            current += 1;
+        }
+
+        Self::new(parts).ok_or_else(|| Error::new(ErrorKind::InvalidData, "parts are too large"))
+    }
+}
+
+impl<D: Read> SplitImage<D> {
+    fn read_from(&self, volume: Option<u16>, position: u64, buffer: &mut [u8]) -> Option<()> {
+        if position.checked_add(buffer.len() as u64)? > self.size() {
+            return None;
+        }
+
+        // First part that ends after the position
+        let mut index = self.ends.partition_point(|end| *end <= position);
+        let mut done = 0;
+
+        while done < buffer.len() {
+            let start = if index == 0 { 0 } else { self.ends[index - 1] };
+            let offset = position + done as u64 - start;
+            let count = ((self.ends[index] - start - offset) as usize).min(buffer.len() - done);
+
+            read_from(&self.parts[index], volume, offset, &mut buffer[done..done + count])?;
+
+            done += count;
+            index += 1;
+        }
+
+        Some(())
+    }
+}
+
+impl<D: Read> Read for SplitImage<D> {
+    fn read(&self, position: u64, buffer: &mut [u8]) -> Option<()> {
+        self.read_from(None, position, buffer)
+    }
+
+    fn read_volume(&self, sequence: u16, position: u64, buffer: &mut [u8]) -> Option<()> {
+        self.read_from(Some(sequence), position, buffer)
+    }
+}
@@ -0,0 +1,55 @@
+//! `std::io` streams over the data of a record.
+
+use std::io::{self, Seek, SeekFrom};
+
+use crate::{ISODirectoryRecord, Read, ISO9660};
+
+/// Reads the extent of a record with `std::io::Read` and `Seek`, e.g. a resource fork from
+/// [`ISO9660::resource_fork`]. Data is read as stored, zisofs files are not decompressed.
+pub struct RecordReader<'iso, D: Read> {
+    iso: &'iso ISO9660<D>,
+    record: ISODirectoryRecord,
+    position: u64,
+}
+
+impl<'iso, D: Read> RecordReader<'iso, D> {
+    pub fn new(iso: &'iso ISO9660<D>, record: ISODirectoryRecord) -> Self {
+        Self { iso, record, position: 0 }
+    }
+
+    pub fn len(&self) -> u64 {
+        self.record.file_size()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<D: Read> io::Read for RecordReader<'_, D> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let length = (self.len().saturating_sub(self.position)).min(buffer.len() as u64) as usize;
+
+        self.iso
+            .read_record(&self.record, self.position, &mut buffer[..length])
+            .ok_or_else(|| io::Error::other(format!("failed to read data at offset {}", self.position)))?;
+
+        self.position += length as u64;
+
+        Ok(length)
+    }
+}
+
+impl<D: Read> Seek for RecordReader<'_, D> {
+    fn seek(&mut self, target: SeekFrom) -> io::Result<u64> {
+        let position = match target {
+            SeekFrom::Start(position) => Some(position),
+            SeekFrom::End(delta) => self.len().checked_add_signed(delta),
+            SeekFrom::Current(delta) => self.position.checked_add_signed(delta),
+        };
+
+        self.position = position.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "seek before the start"))?;
+
+        Ok(self.position)
+    }
+}
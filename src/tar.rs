@@ -0,0 +1,679 @@
+//! Export of the tree as a tar stream (ustar with pax extensions), and import of tar
+//! streams into new images.
+//!
+//! Rock Ridge metadata maps to the headers: `PX` gives mode and owner, `TF` the modification
+//! time and `SL` link targets. Names and values that don't fit ustar fields go into pax
+//! headers. Entries sharing an extent become hard links to the first one.
+//!
+//! Import goes the other way through [`ImageWriter::append_tar`]: ustar, pax and GNU
+//! headers are understood and file data is copied straight from the stream.
+
+use std::{
+    collections::HashMap,
+    fmt::Write as _,
+    io::{self, Seek, Write},
+};
+
+use crate::{
+    helpers::get_directory_entry_by_path_with,
+    normalize::NameOptions,
+    progress::{Monitor, Tracker},
+    walk::WalkEntry,
+    writer::{ImageWriter, Metadata, Special},
+    ISODirectoryEntry, Read, ISO9660,
+};
+
+const BLOCK_SIZE: usize = 512;
+
+/// Size of chunks used to copy file data
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+const TYPE_FILE: u8 = b'0';
+const TYPE_HARDLINK: u8 = b'1';
+const TYPE_SYMLINK: u8 = b'2';
+const TYPE_CHAR_DEVICE: u8 = b'3';
+const TYPE_BLOCK_DEVICE: u8 = b'4';
+const TYPE_DIRECTORY: u8 = b'5';
+const TYPE_FIFO: u8 = b'6';
+const TYPE_PAX: u8 = b'x';
+const TYPE_CONTIGUOUS: u8 = b'7';
+const TYPE_PAX_GLOBAL: u8 = b'g';
+const TYPE_GNU_LONG_NAME: u8 = b'L';
+const TYPE_GNU_LONG_LINK: u8 = b'K';
+const TYPE_GNU_SPARSE: u8 = b'S';
+
+/// Largest pax or GNU long name header that is read into memory
+const MAX_EXTENDED_HEADER: u64 = 1024 * 1024;
+
+const S_IFMT: u32 = 0o170000;
+const S_IFIFO: u32 = 0o010000;
+const S_IFCHR: u32 = 0o020000;
+const S_IFBLK: u32 = 0o060000;
+
+/// Writes the image or some of its paths as a tar stream, created by [`ISO9660::tar`].
+pub struct TarExporter<'iso, D: Read> {
+    iso: &'iso ISO9660<D>,
+    paths: Vec<String>,
+    names: NameOptions,
+    monitor: Monitor<'iso>,
+}
+
+impl<'iso, D: Read> TarExporter<'iso, D> {
+    pub(crate) fn new(iso: &'iso ISO9660<D>) -> Self {
+        Self {
+            iso,
+            paths: Vec::new(),
+            names: NameOptions {
+                strip_version: true,
+                strip_trailing_dot: true,
+                ..NameOptions::EXACT
+            },
+            monitor: Monitor::NONE,
+        }
+    }
+
+    /// Exports only this path (looked up leniently, see [`NameOptions::LENIENT`]).
+    /// Can be called several times, the whole image is exported if it's never called.
+    pub fn path(mut self, path: &str) -> Self {
+        self.paths.push(path.into());
+        self
+    }
+
+    /// How names are written. By default version suffixes and trailing dots are dropped.
+    pub fn names(mut self, options: NameOptions) -> Self {
+        self.names = options;
+        self
+    }
+
+    /// Reports progress of file data and stops when cancelled.
+    /// A cancelled export fails with [`io::ErrorKind::Interrupted`], leaving the stream unfinished.
+    pub fn monitor(mut self, monitor: Monitor<'iso>) -> Self {
+        self.monitor = monitor;
+        self
+    }
+
+    /// Writes the whole archive, including the end-of-archive marker.
+    pub fn write_to(self, output: &mut impl Write) -> io::Result<()> {
+        let mut items = Vec::new();
+
+        for path in self.paths_or_root() {
+            let entry = get_directory_entry_by_path_with(self.iso, &path, &NameOptions::LENIENT)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("{path}: no such file or directory")))?;
+
+            let item = WalkEntry {
+                symlink_target: entry.symlink_target(),
+                entry,
+                path: path.clone(),
+                depth: 0,
+            };
+
+            let directory = item.entry.is_folder() && !item.is_symlink();
+
+            // The root itself has no name in the archive.
+            if !path.split('/').all(|v| v.is_empty()) {
+                items.push(item.clone());
+            }
+
+            if directory {
+                items.extend(self.iso.walk_from(&item.entry, &path));
+            }
+        }
+
+        let total = items
+            .iter()
+            .filter(|item| item.entry.is_file() && !item.is_symlink())
+            .map(|item| item.entry.file_size())
+            .sum();
+
+        let tracker = self.monitor.start(total);
+        let mut buffer = Vec::new();
+
+        // First path written for every extent (volume, LBA and size), later ones become hard links.
+        let mut extents: HashMap<(u16, u32, u64), String> = HashMap::new();
+
+        for item in &items {
+            tracker.check()?;
+
+            let name = self.archive_name(&item.path);
+            let entry = &item.entry;
+
+            if let Some(target) = &item.symlink_target {
+                write_header(output, &Header::new(entry, &name, TYPE_SYMLINK).link(target))?;
+                continue;
+            }
+
+            if entry.is_folder() {
+                write_header(output, &Header::new(entry, &format!("{name}/"), TYPE_DIRECTORY))?;
+                continue;
+            }
+
+            let kind = match file_mode(entry) & S_IFMT {
+                S_IFIFO => TYPE_FIFO,
+                S_IFCHR => TYPE_CHAR_DEVICE,
+                S_IFBLK => TYPE_BLOCK_DEVICE,
+                _ => TYPE_FILE,
+            };
+
+            // Empty files often all point at the same block, they are never linked.
+            if kind != TYPE_FILE || entry.file_size() == 0 {
+                write_header(output, &Header::new(entry, &name, kind))?;
+                continue;
+            }
+
+            let extent = (entry.volume_sequence_number(), entry.lsb_position(), entry.file_size());
+
+            if let Some(first) = extents.get(&extent) {
+                write_header(output, &Header::new(entry, &name, TYPE_HARDLINK).link(first))?;
+                continue;
+            }
+
+            extents.insert(extent, name.clone());
+
+            write_header(output, &Header::new(entry, &name, TYPE_FILE).size(entry.file_size()))?;
+            self.write_data(entry, &item.path, output, &mut buffer, &tracker)?;
+        }
+
+        // End of archive: two empty blocks.
+        output.write_all(&[0; BLOCK_SIZE * 2])
+    }
+
+    fn paths_or_root(&self) -> Vec<String> {
+        if self.paths.is_empty() {
+            vec![String::from("/")]
+        } else {
+            self.paths.clone()
+        }
+    }
+
+    /// Relative name inside the archive, `boot/grub/grub.cfg`.
+    fn archive_name(&self, path: &str) -> String {
+        let components: Vec<_> = path
+            .split('/')
+            .filter(|v| !v.is_empty())
+            .map(|component| self.names.normalize(component))
+            .collect();
+
+        components.join("/")
+    }
+
+    fn write_data(
+        &self,
+        entry: &ISODirectoryEntry,
+        path: &str,
+        output: &mut impl Write,
+        buffer: &mut Vec<u8>,
+        tracker: &Tracker,
+    ) -> io::Result<()> {
+        let size = entry.file_size();
+        let mut offset = 0;
+
+        buffer.resize(CHUNK_SIZE.min(usize::try_from(size).unwrap_or(usize::MAX)), 0);
+
+        while offset < size {
+            tracker.check()?;
+
+            let length = (size - offset).min(buffer.len() as u64) as usize;
+            let chunk = &mut buffer[..length];
+
+            self.iso
+                .read_file(entry, offset, chunk)
+                .ok_or_else(|| io::Error::other(format!("{path}: failed to read data at offset {offset}")))?;
+            output.write_all(chunk)?;
+
+            offset += length as u64;
+            tracker.advance(length as u64, path);
+        }
+
+        let padding = (BLOCK_SIZE - (size as usize % BLOCK_SIZE)) % BLOCK_SIZE;
+
+        output.write_all(&[0; BLOCK_SIZE][..padding])
+    }
+}
+
+/// Rock Ridge mode, or a usual one for images without it.
+fn file_mode(entry: &ISODirectoryEntry) -> u32 {
+    match entry.posix_attributes() {
+        Some(attributes) => attributes.mode,
+        None if entry.is_folder() => 0o040755,
+        None => 0o100644,
+    }
+}
+
+struct Header<'a> {
+    name: &'a str,
+    kind: u8,
+    mode: u32,
+    uid: u64,
+    gid: u64,
+    size: u64,
+    mtime: i64,
+    link: &'a str,
+}
+
+impl<'a> Header<'a> {
+    fn new(entry: &ISODirectoryEntry, name: &'a str, kind: u8) -> Self {
+        let attributes = entry.posix_attributes();
+
+        Self {
+            name,
+            kind,
+            mode: file_mode(entry) & 0o7777,
+            uid: attributes.map_or(0, |a| a.uid as u64),
+            gid: attributes.map_or(0, |a| a.gid as u64),
+            size: 0,
+            mtime: entry.modified(),
+            link: "",
+        }
+    }
+
+    fn link(mut self, target: &'a str) -> Self {
+        self.link = target;
+        self
+    }
+
+    fn size(mut self, size: u64) -> Self {
+        self.size = size;
+        self
+    }
+}
+
+/// Largest value of an octal field of `width` bytes (one is taken by the terminator).
+fn octal_limit(width: usize) -> u64 {
+    (1 << (3 * (width - 1))) - 1
+}
+
+/// Writes a ustar header, preceded by a pax header if some value doesn't fit.
+fn write_header(output: &mut impl Write, header: &Header) -> io::Result<()> {
+    let mut pax = String::new();
+
+    let mut record = |key: &str, value: &str| {
+        // The length prefix counts itself.
+        let body = format!(" {key}={value}\n");
+        let mut length = body.len() + 1;
+
+        while format!("{length}").len() + body.len() > length {
+            length += 1;
+        }
+
+        let _ = write!(pax, "{length}{body}");
+    };
+
+    let (prefix, name) = split_name(header.name).unwrap_or_else(|| {
+        record("path", header.name);
+        ("", truncate(header.name, 100))
+    });
+
+    if header.link.len() > 100 || !header.link.is_ascii() {
+        record("linkpath", header.link);
+    }
+
+    if header.size > octal_limit(12) {
+        record("size", &header.size.to_string());
+    }
+
+    if header.uid > octal_limit(8) {
+        record("uid", &header.uid.to_string());
+    }
+
+    if header.gid > octal_limit(8) {
+        record("gid", &header.gid.to_string());
+    }
+
+    if header.mtime < 0 || header.mtime as u64 > octal_limit(12) {
+        record("mtime", &header.mtime.to_string());
+    }
+
+    if !pax.is_empty() {
+        let pax_name = format!("PaxHeaders/{}", truncate(header.name, 89));
+
+        output.write_all(&ustar_block(&Header {
+            name: &pax_name,
+            kind: TYPE_PAX,
+            mode: 0o644,
+            uid: 0,
+            gid: 0,
+            size: pax.len() as u64,
+            mtime: header.mtime,
+            link: "",
+        }, "", truncate(&pax_name, 100)))?;
+
+        output.write_all(pax.as_bytes())?;
+        output.write_all(&[0; BLOCK_SIZE][..(BLOCK_SIZE - pax.len() % BLOCK_SIZE) % BLOCK_SIZE])?;
+    }
+
+    output.write_all(&ustar_block(header, prefix, name))
+}
+
+/// Splits a name into ustar `prefix` and `name` fields, `None` if it doesn't fit.
+fn split_name(name: &str) -> Option<(&str, &str)> {
+    if !name.is_ascii() {
+        return None;
+    }
+
+    if name.len() <= 100 {
+        return Some(("", name));
+    }
+
+    // The prefix ends at a separator that is not stored.
+    name.char_indices()
+        .filter(|&(index, c)| c == '/' && index <= 155 && name.len() - index - 1 <= 100 && index > 0)
+        .map(|(index, _)| (&name[..index], &name[index + 1..]))
+        .find(|(_, rest)| !rest.is_empty())
+}
+
+/// Cuts a string to at most `length` bytes on a character boundary.
+fn truncate(value: &str, length: usize) -> &str {
+    let mut end = value.len().min(length);
+
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    &value[..end]
+}
+
+fn ustar_block(header: &Header, prefix: &str, name: &str) -> [u8; BLOCK_SIZE] {
+    let mut block = [0u8; BLOCK_SIZE];
+
+    let octal = |block: &mut [u8; BLOCK_SIZE], start: usize, width: usize, value: u64| {
+        let value = value.min(octal_limit(width));
+        let text = format!("{value:0width$o}", width = width - 1);
+
+        block[start..start + width - 1].copy_from_slice(text.as_bytes());
+    };
+
+    block[..name.len()].copy_from_slice(name.as_bytes());
+    octal(&mut block, 100, 8, header.mode as u64);
+    octal(&mut block, 108, 8, header.uid);
+    octal(&mut block, 116, 8, header.gid);
+    octal(&mut block, 124, 12, header.size);
+    octal(&mut block, 136, 12, header.mtime.max(0) as u64);
+    block[156] = header.kind;
+
+    let link = truncate(header.link, 100);
+    block[157..157 + link.len()].copy_from_slice(link.as_bytes());
+
+    block[257..263].copy_from_slice(b"ustar\0");
+    block[263..265].copy_from_slice(b"00");
+    block[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+
+    // The checksum is computed with its own field filled with spaces.
+    block[148..156].fill(b' ');
+
+    let checksum: u32 = block.iter().map(|&b| b as u32).sum();
+    let text = format!("{checksum:06o}\0 ");
+
+    block[148..156].copy_from_slice(text.as_bytes());
+
+    block
+}
+
+/// Values of pax and GNU extended headers that replace the ones of the ustar header.
+#[derive(Default, Clone)]
+struct Overrides {
+    path: Option<String>,
+    link: Option<String>,
+    size: Option<u64>,
+    uid: Option<u32>,
+    gid: Option<u32>,
+    mtime: Option<i64>,
+    sparse: bool,
+}
+
+impl Overrides {
+    /// Takes the values of pax records.
+    fn parse_pax(&mut self, data: &[u8]) -> io::Result<()> {
+        let mut rest = data;
+
+        while !rest.is_empty() {
+            // "<length> <key>=<value>\n", the length counts the whole record.
+            let space = rest.iter().position(|&b| b == b' ').ok_or_else(|| invalid("malformed pax header"))?;
+            let length: usize = str::from_utf8(&rest[..space])
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|&length| length > space && length <= rest.len())
+                .ok_or_else(|| invalid("malformed pax header"))?;
+
+            let record = &rest[space + 1..length];
+            let record = record.strip_suffix(b"\n").unwrap_or(record);
+
+            rest = &rest[length..];
+
+            let Some(equals) = record.iter().position(|&b| b == b'=') else {
+                continue;
+            };
+
+            let (key, value) = (&record[..equals], &record[equals + 1..]);
+            let text = String::from_utf8_lossy(value).into_owned();
+
+            let number = |text: &str| -> io::Result<i64> {
+                // Times may have a fraction, it's dropped.
+                let whole = text.split('.').next().unwrap_or_default();
+
+                whole.parse().map_err(|_| invalid(&format!("malformed pax value '{text}'")))
+            };
+
+            match key {
+                b"path" => self.path = Some(text),
+                b"linkpath" => self.link = Some(text),
+                b"size" => self.size = Some(number(&text)? as u64),
+                b"uid" => self.uid = Some(number(&text)? as u32),
+                b"gid" => self.gid = Some(number(&text)? as u32),
+                b"mtime" => self.mtime = Some(number(&text)?),
+                key if key.starts_with(b"GNU.sparse.") => self.sparse = true,
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Values of `self`, missing ones taken from `global`.
+    fn or(self, global: &Overrides) -> Overrides {
+        let global = global.clone();
+
+        Overrides {
+            path: self.path.or(global.path),
+            link: self.link.or(global.link),
+            size: self.size.or(global.size),
+            uid: self.uid.or(global.uid),
+            gid: self.gid.or(global.gid),
+            mtime: self.mtime.or(global.mtime),
+            sparse: self.sparse || global.sparse,
+        }
+    }
+}
+
+impl<W: Write + Seek> ImageWriter<W> {
+    /// Adds every entry of a tar stream (ustar, pax or GNU). File data is copied straight from
+    /// `input` into the image. Sparse files are not supported.
+    pub fn append_tar(&mut self, input: &mut impl io::Read) -> io::Result<()> {
+        let mut block = [0u8; BLOCK_SIZE];
+        let mut global = Overrides::default();
+        let mut local = Overrides::default();
+
+        // The end of the stream is also accepted in place of the end-of-archive marker.
+        while read_block(input, &mut block)? {
+            if block.iter().all(|&b| b == 0) {
+                break;
+            }
+
+            verify_checksum(&block)?;
+
+            let kind = block[156];
+            let size = local.size.or(global.size).unwrap_or(octal_field(&block[124..136])? as u64);
+
+            match kind {
+                TYPE_PAX | TYPE_PAX_GLOBAL | TYPE_GNU_LONG_NAME | TYPE_GNU_LONG_LINK => {
+                    let size = octal_field(&block[124..136])? as u64;
+
+                    if size > MAX_EXTENDED_HEADER {
+                        return Err(invalid("extended header is too large"));
+                    }
+
+                    let mut data = vec![0; size as usize];
+
+                    input.read_exact(&mut data)?;
+                    skip(input, padding(size))?;
+
+                    match kind {
+                        TYPE_PAX => local.parse_pax(&data)?,
+                        TYPE_PAX_GLOBAL => global.parse_pax(&data)?,
+                        _ => {
+                            let value = data.split(|&b| b == 0).next().unwrap_or_default();
+                            let value = String::from_utf8_lossy(value).into_owned();
+
+                            match kind {
+                                TYPE_GNU_LONG_NAME => local.path = Some(value),
+                                _ => local.link = Some(value),
+                            }
+                        }
+                    }
+
+                    continue;
+                }
+                _ => {}
+            }
+
+            let overrides = core::mem::take(&mut local).or(&global);
+            let path = overrides.path.clone().unwrap_or_else(|| header_name(&block));
+            let link = overrides.link.clone().unwrap_or_else(|| field_text(&block[157..257]));
+
+            let metadata = Metadata {
+                mode: octal_field(&block[100..108])? as u32 & 0o7777,
+                uid: overrides.uid.map_or_else(|| octal_field(&block[108..116]).map(|v| v as u32), Ok)?,
+                gid: overrides.gid.map_or_else(|| octal_field(&block[116..124]).map(|v| v as u32), Ok)?,
+                mtime: overrides.mtime.map_or_else(|| octal_field(&block[136..148]), Ok)?,
+            };
+
+            if kind == TYPE_GNU_SPARSE || overrides.sparse {
+                return Err(io::Error::new(io::ErrorKind::Unsupported, format!("{path}: sparse files are not supported")));
+            }
+
+            let major = || octal_field(&block[329..337]).map(|v| v as u32);
+            let minor = || octal_field(&block[337..345]).map(|v| v as u32);
+
+            // Data that isn't consumed below is skipped.
+            let mut remaining = size;
+
+            match kind {
+                // Old archives mark directories only by the trailing slash.
+                TYPE_FILE | 0 | TYPE_CONTIGUOUS if path.ends_with('/') => self.add_directory(&path, &metadata)?,
+                TYPE_FILE | 0 | TYPE_CONTIGUOUS => {
+                    self.add_file(&path, &metadata, size, input)?;
+                    remaining = 0;
+                }
+                TYPE_HARDLINK => self.add_hardlink(&path, &link)?,
+                TYPE_SYMLINK => self.add_symlink(&path, &metadata, &link)?,
+                TYPE_CHAR_DEVICE => self.add_special(&path, &metadata, Special::CharDevice { major: major()?, minor: minor()? })?,
+                TYPE_BLOCK_DEVICE => self.add_special(&path, &metadata, Special::BlockDevice { major: major()?, minor: minor()? })?,
+                TYPE_DIRECTORY => self.add_directory(&path, &metadata)?,
+                TYPE_FIFO => self.add_special(&path, &metadata, Special::Fifo)?,
+                // Volume labels, multi-volume continuations and other extensions have no place in the image.
+                _ => {}
+            }
+
+            skip(input, remaining + padding(size))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("tar: {message}"))
+}
+
+/// Reads the next header block, `false` at the end of the stream.
+fn read_block(input: &mut impl io::Read, block: &mut [u8; BLOCK_SIZE]) -> io::Result<bool> {
+    let mut filled = 0;
+
+    while filled < BLOCK_SIZE {
+        match input.read(&mut block[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "tar: truncated header")),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(true)
+}
+
+fn skip(input: &mut impl io::Read, length: u64) -> io::Result<()> {
+    let skipped = io::copy(&mut io::Read::take(&mut *input, length), &mut io::sink())?;
+
+    if skipped < length {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "tar: truncated archive"));
+    }
+
+    Ok(())
+}
+
+/// Bytes that fill up the last block of `size` bytes of data.
+fn padding(size: u64) -> u64 {
+    (BLOCK_SIZE as u64 - size % BLOCK_SIZE as u64) % BLOCK_SIZE as u64
+}
+
+/// Accepts both the unsigned and the (historical) signed checksum.
+fn verify_checksum(block: &[u8; BLOCK_SIZE]) -> io::Result<()> {
+    let stored = octal_field(&block[148..156])?;
+
+    let spaces = |b: (usize, &u8)| if (148..156).contains(&b.0) { b' ' } else { *b.1 };
+    let unsigned: i64 = block.iter().enumerate().map(|b| spaces(b) as i64).sum();
+    let signed: i64 = block.iter().enumerate().map(|b| spaces(b) as i8 as i64).sum();
+
+    if stored != unsigned && stored != signed {
+        return Err(invalid("header checksum mismatch"));
+    }
+
+    Ok(())
+}
+
+/// Numeric field: octal text, or base-256 (GNU) when the high bit of the first byte is set.
+fn octal_field(field: &[u8]) -> io::Result<i64> {
+    if field[0] & 0x80 != 0 {
+        // Two's complement big endian without the marker bit, negative if the next bit is set.
+        // Fields are at most 12 bytes, values with more than 8 significant bytes are refused.
+        let mut value: i128 = if field[0] & 0x40 != 0 { -1 } else { 0 };
+
+        value = (value << 7) | (field[0] & 0x7f) as i128;
+
+        for &byte in &field[1..] {
+            value = (value << 8) | byte as i128;
+        }
+
+        return i64::try_from(value).map_err(|_| invalid("number doesn't fit 64 bits"));
+    }
+
+    let text = str::from_utf8(field).map_err(|_| invalid("malformed number"))?;
+    let text = text.trim_matches(|c: char| c == '\0' || c == ' ');
+
+    if text.is_empty() {
+        return Ok(0);
+    }
+
+    i64::from_str_radix(text, 8).map_err(|_| invalid(&format!("malformed number '{text}'")))
+}
+
+/// Text of a NUL terminated field.
+fn field_text(field: &[u8]) -> String {
+    let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+/// Name joined with the prefix of POSIX headers. GNU headers keep other data in the prefix field.
+fn header_name(block: &[u8; BLOCK_SIZE]) -> String {
+    let name = field_text(&block[..100]);
+
+    if &block[257..263] != b"ustar\0" {
+        return name;
+    }
+
+    match field_text(&block[345..500]) {
+        prefix if prefix.is_empty() => name,
+        prefix => format!("{prefix}/{name}"),
+    }
+}
@@ -15,3 +15,89 @@ impl<T: Copy> LSB_MSB<T> {
         return self.msb;
     }
 }
+
+impl LSB_MSB<u32> {
+    pub fn new(value: u32) -> Self {
+        Self {
+            lsb: u32::from_ne_bytes(value.to_le_bytes()),
+            msb: u32::from_ne_bytes(value.to_be_bytes()),
+        }
+    }
+}
+
+impl LSB_MSB<u16> {
+    pub fn new(value: u16) -> Self {
+        Self {
+            lsb: u16::from_ne_bytes(value.to_le_bytes()),
+            msb: u16::from_ne_bytes(value.to_be_bytes()),
+        }
+    }
+}
+
+/// UTC civil date and time `(year, month, day, hour, minute, second)` of a Unix timestamp.
+pub(crate) fn civil_time(time: i64) -> (i64, u8, u8, u8, u8, u8) {
+    // Civil from days, see http://howardhinnant.github.io/date_algorithms.html
+    let days = time.div_euclid(86400) + 719468;
+    let seconds = time.rem_euclid(86400);
+
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (
+        year,
+        month as u8,
+        day as u8,
+        (seconds / 3600) as u8,
+        (seconds % 3600 / 60) as u8,
+        (seconds % 60) as u8,
+    )
+}
+
+/// Seconds since the Unix epoch for a UTC civil date and time.
+pub(crate) fn unix_time(year: i64, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> i64 {
+    // Days from civil, see http://howardhinnant.github.io/date_algorithms.html
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+
+    days * 86400 + hour as i64 * 3600 + minute as i64 * 60 + second as i64
+}
+
+/// Decodes a 17-byte `YYYYMMDDHHMMSScc` + offset timestamp used by volume descriptors
+/// and long form Rock Ridge `TF` entries.
+///
+/// Returns seconds since the Unix epoch, `None` if the date is not set or malformed.
+pub fn dec_datetime(data: &[u8]) -> Option<i64> {
+    let digits = data.get(..16)?;
+    let offset = *data.get(16)? as i8;
+
+    if digits.iter().all(|&b| b == b'0' || b == 0) {
+        return None;
+    }
+
+    let field = |range: core::ops::Range<usize>| -> Option<u32> {
+        str::from_utf8(&digits[range]).ok()?.parse().ok()
+    };
+
+    let time = unix_time(
+        field(0..4)? as i64,
+        field(4..6)? as u8,
+        field(6..8)? as u8,
+        field(8..10)? as u8,
+        field(10..12)? as u8,
+        field(12..14)? as u8,
+    );
+
+    // The offset from GMT is stored in 15 minute intervals.
+    Some(time - offset as i64 * 15 * 60)
+}
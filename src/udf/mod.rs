@@ -0,0 +1,891 @@
+//! Universal Disk Format (ECMA-167, OSTA UDF 1.02 to 2.60) reader.
+//!
+//! DVD, Blu-ray and Windows install media are often UDF-bridge discs: they carry an ISO9660
+//! tree for old systems, which may be truncated or miss large files, and the full tree in UDF.
+//! [`UDF`] reads the UDF side with the same directory, entry and read API as [`ISO9660`](crate::ISO9660).
+//!
+//! A volume is found through the volume recognition sequence (`BEA01`, `NSR02`/`NSR03`, `TEA01`)
+//! and the anchor at block 256. Type 1, sparable and metadata partitions are supported, virtual
+//! (VAT) partitions of incrementally written CD-Rs are not.
+
+mod structures;
+
+use alloc::{
+    borrow::ToOwned,
+    format,
+    string::String,
+    vec,
+    vec::Vec,
+};
+
+use structures::*;
+pub use structures::Location;
+
+use crate::{normalize::NameOptions, Read};
+
+/// Start of the volume recognition sequence
+const VRS_START: u64 = 0x8000;
+
+/// Most volume recognition descriptors looked at
+const MAX_VRS_DESCRIPTORS: u64 = 64;
+
+/// Block of the anchor volume descriptor pointer
+const ANCHOR_BLOCK: u32 = 256;
+
+/// Block sizes tried when looking for the anchor
+const BLOCK_SIZES: [u32; 4] = [2048, 512, 4096, 1024];
+
+/// Most volume descriptor pointers and allocation extent descriptors followed
+const MAX_CHAINED_DESCRIPTORS: usize = 64;
+
+/// Largest directory read, 64 MiB
+const MAX_DIRECTORY_SIZE: u64 = 64 << 20;
+
+/// Largest symbolic link read
+const MAX_SYMLINK_SIZE: u64 = 64 << 10;
+
+/// ICB strategies: a single entry, or entries added over time on write-once media
+const STRATEGY_SINGLE: u16 = 4;
+const STRATEGY_WRITE_ONCE: u16 = 4096;
+
+const FILE_TYPE_DIRECTORY: u8 = 4;
+const FILE_TYPE_REGULAR: u8 = 5;
+const FILE_TYPE_BLOCK_DEVICE: u8 = 6;
+const FILE_TYPE_CHAR_DEVICE: u8 = 7;
+const FILE_TYPE_FIFO: u8 = 9;
+const FILE_TYPE_SOCKET: u8 = 10;
+const FILE_TYPE_SYMLINK: u8 = 12;
+
+/// Fixed part of a file identifier descriptor, before the implementation use and identifier
+const FILE_IDENTIFIER_SIZE: usize = 38;
+
+/// File characteristics of a file identifier descriptor
+const CHARACTERISTIC_HIDDEN: u8 = 1 << 0;
+const CHARACTERISTIC_DIRECTORY: u8 = 1 << 1;
+const CHARACTERISTIC_DELETED: u8 = 1 << 2;
+const CHARACTERISTIC_PARENT: u8 = 1 << 3;
+
+/// ICB tag flags
+const ICB_SETUID: u16 = 1 << 6;
+const ICB_SETGID: u16 = 1 << 7;
+const ICB_STICKY: u16 = 1 << 8;
+
+/// Owner or group that is not specified
+const ID_UNSPECIFIED: u32 = u32::MAX;
+
+/// Where the blocks of a partition map are.
+#[derive(Debug)]
+enum Partition {
+    /// Type 1 map: blocks follow each other from `start`
+    Physical { start: u32, length: u32 },
+    /// Rewritable media: packets that went bad were moved to the sectors in `remaps`
+    Sparable {
+        start: u32,
+        length: u32,
+        packet_length: u32,
+        /// Original packet and the sector it was moved to
+        remaps: Vec<(u32, u32)>,
+    },
+    /// Blocks are the data of the metadata file, which is stored in another partition
+    Metadata { extents: Vec<Extent> },
+    Unsupported,
+}
+
+/// Data of a file entry.
+#[derive(Debug, Clone)]
+enum Allocation {
+    /// Small files are stored inside the entry
+    Embedded(Vec<u8>),
+    Extents(Vec<Extent>),
+}
+
+/// File entry or extended file entry.
+#[derive(Debug, Clone)]
+struct Node {
+    file_type: u8,
+    icb_flags: u16,
+    uid: u32,
+    gid: u32,
+    permissions: u32,
+    links: u16,
+    size: u64,
+    accessed: Option<i64>,
+    modified: Option<i64>,
+    created: Option<i64>,
+    unique_id: u64,
+    data: Allocation,
+}
+
+/// A file or directory of a UDF volume.
+#[derive(Debug, Clone)]
+pub struct UDFEntry {
+    pub name: String,
+    location: Location,
+    characteristics: u8,
+    node: Node,
+}
+
+impl UDFEntry {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn is_folder(&self) -> bool {
+        self.node.file_type == FILE_TYPE_DIRECTORY
+    }
+
+    pub fn is_file(&self) -> bool {
+        self.node.file_type == FILE_TYPE_REGULAR
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        self.node.file_type == FILE_TYPE_SYMLINK
+    }
+
+    /// Whether the directory marks the entry as hidden
+    pub fn is_hidden(&self) -> bool {
+        self.characteristics & CHARACTERISTIC_HIDDEN != 0
+    }
+
+    pub fn file_size(&self) -> u64 {
+        self.node.size
+    }
+
+    /// Location of the file entry, unique for every file (hard links share it)
+    pub fn location(&self) -> Location {
+        self.location
+    }
+
+    /// Unique ID assigned when the file was created
+    pub fn unique_id(&self) -> u64 {
+        self.node.unique_id
+    }
+
+    /// POSIX mode with the file type bits
+    pub fn mode(&self) -> u32 {
+        let permissions = self.node.permissions;
+        let file_type = match self.node.file_type {
+            FILE_TYPE_DIRECTORY => 0o040000,
+            FILE_TYPE_SYMLINK => 0o120000,
+            FILE_TYPE_BLOCK_DEVICE => 0o060000,
+            FILE_TYPE_CHAR_DEVICE => 0o020000,
+            FILE_TYPE_FIFO => 0o010000,
+            FILE_TYPE_SOCKET => 0o140000,
+            _ => 0o100000,
+        };
+
+        // Owner, group and others get 5 bits each: execute, write, read, change attributes, delete
+        let mut mode = file_type | (permissions >> 10 & 7) << 6 | (permissions >> 5 & 7) << 3 | (permissions & 7);
+
+        for (flag, bit) in [(ICB_SETUID, 0o4000), (ICB_SETGID, 0o2000), (ICB_STICKY, 0o1000)] {
+            if self.node.icb_flags & flag != 0 {
+                mode |= bit;
+            }
+        }
+
+        mode
+    }
+
+    pub fn uid(&self) -> Option<u32> {
+        (self.node.uid != ID_UNSPECIFIED).then_some(self.node.uid)
+    }
+
+    pub fn gid(&self) -> Option<u32> {
+        (self.node.gid != ID_UNSPECIFIED).then_some(self.node.gid)
+    }
+
+    /// Number of directory entries pointing at the file
+    pub fn links(&self) -> u16 {
+        self.node.links
+    }
+
+    /// Modification time as seconds since the Unix epoch, 0 if not recorded
+    pub fn modified(&self) -> i64 {
+        self.node.modified.unwrap_or(0)
+    }
+
+    pub fn accessed(&self) -> Option<i64> {
+        self.node.accessed
+    }
+
+    /// Creation time, recorded by extended file entries only
+    pub fn created(&self) -> Option<i64> {
+        self.node.created
+    }
+}
+
+/// Main structure of the UDF reader, the counterpart of [`ISO9660`](crate::ISO9660).
+pub struct UDF<D: Read> {
+    device: D,
+    block_size: u32,
+    partitions: Vec<Partition>,
+    root: UDFEntry,
+    volume_id: String,
+    revision: u16,
+}
+
+impl<D: Read> UDF<D> {
+    /// Opens the UDF volume of the device, `None` if there is none or it can't be read.
+    pub fn from_device(device: D) -> Option<Self> {
+        if !has_udf_descriptors(&device) {
+            return None;
+        }
+
+        let (block_size, volume) = BLOCK_SIZES.iter().find_map(|block_size| {
+            let mut anchor = vec![0u8; *block_size as usize];
+
+            device.read(ANCHOR_BLOCK as u64 * *block_size as u64, &mut anchor)?;
+
+            if tag(&anchor) != Some((TAG_ANCHOR, ANCHOR_BLOCK)) {
+                return None;
+            }
+
+            // Main and reserve volume descriptor sequences
+            [16, 24].iter().find_map(|offset| {
+                let length = u32_at(&anchor, *offset)?;
+                let location = u32_at(&anchor, offset + 4)?;
+
+                read_volume_sequence(&device, *block_size, location, length)
+            })
+            .map(|volume| (*block_size, volume))
+        })?;
+
+        let logical = &volume.logical_volume;
+
+        if u32_at(logical, 212)? != block_size {
+            return None;
+        }
+
+        // Domain identifier: flags, "*OSTA UDF Compliant", then the revision in the suffix
+        let revision = u16_at(logical, 216 + 24)?;
+        let volume_id = dstring(logical.get(84..212)?);
+        let file_set = long_ad(logical.get(248..264)?)?.1;
+
+        let mut udf = UDF {
+            device,
+            block_size,
+            partitions: Vec::new(),
+            root: UDFEntry {
+                name: String::from("/"),
+                location: file_set,
+                characteristics: CHARACTERISTIC_DIRECTORY,
+                node: Node {
+                    file_type: FILE_TYPE_DIRECTORY,
+                    icb_flags: 0,
+                    uid: ID_UNSPECIFIED,
+                    gid: ID_UNSPECIFIED,
+                    permissions: 0,
+                    links: 0,
+                    size: 0,
+                    accessed: None,
+                    modified: None,
+                    created: None,
+                    unique_id: 0,
+                    data: Allocation::Extents(Vec::new()),
+                },
+            },
+            volume_id,
+            revision,
+        };
+
+        udf.read_partition_maps(logical, &volume.partitions)?;
+
+        let mut descriptor = vec![0u8; block_size as usize];
+
+        udf.read(file_set, 0, &mut descriptor)?;
+
+        if tag(&descriptor)?.0 != TAG_FILE_SET {
+            return None;
+        }
+
+        let root = long_ad(descriptor.get(400..416)?)?.1;
+
+        udf.root.node = udf.read_node(root)?;
+        udf.root.location = root;
+
+        Some(udf)
+    }
+
+    /// Logical volume identifier
+    pub fn volume_id(&self) -> &str {
+        &self.volume_id
+    }
+
+    /// UDF revision the volume claims to follow, e.g. `0x0250` for 2.50
+    pub fn revision(&self) -> u16 {
+        self.revision
+    }
+
+    pub fn block_size(&self) -> u32 {
+        self.block_size
+    }
+
+    pub fn device(&self) -> &D {
+        &self.device
+    }
+
+    /// Destroys the reader and gives the device back.
+    pub fn into_inner(self) -> D {
+        self.device
+    }
+
+    pub fn root_entry(&self) -> UDFEntry {
+        self.root.clone()
+    }
+
+    pub fn read_root(&self) -> UDFDirectoryIter<'_, D> {
+        self.read_directory(&self.root)
+    }
+
+    /// Lists a directory. The iterator is empty if `directory` is not one or can't be read.
+    pub fn read_directory(&self, directory: &UDFEntry) -> UDFDirectoryIter<'_, D> {
+        let data = Some(directory.node.size)
+            .filter(|size| directory.is_folder() && *size <= MAX_DIRECTORY_SIZE)
+            .and_then(|size| {
+                let mut data = vec![0u8; size as usize];
+
+                self.read_node_data(&directory.node, 0, &mut data).map(|_| data)
+            });
+
+        UDFDirectoryIter {
+            udf: self,
+            data: data.unwrap_or_default(),
+            position: 0,
+        }
+    }
+
+    /// Reads file data at `offset`.
+    pub fn read_file(&self, entry: &UDFEntry, offset: u64, data: &mut [u8]) -> Option<()> {
+        if entry.is_folder() {
+            return None;
+        }
+
+        self.read_node_data(&entry.node, offset, data)
+    }
+
+    /// Target of a symbolic link, `None` if the entry is not a link.
+    pub fn symlink_target(&self, entry: &UDFEntry) -> Option<String> {
+        if !entry.is_symlink() || entry.file_size() > MAX_SYMLINK_SIZE {
+            return None;
+        }
+
+        let mut data = vec![0u8; entry.file_size() as usize];
+
+        self.read_node_data(&entry.node, 0, &mut data)?;
+
+        // Path components: type, identifier length, version, identifier
+        let mut target = String::new();
+        let mut rest = &data[..];
+
+        while let [component_type, length, _, _, tail @ ..] = rest {
+            let identifier = tail.get(..*length as usize)?;
+
+            let component = match component_type {
+                1 | 2 => {
+                    target.clear();
+                    target.push('/');
+                    rest = &tail[*length as usize..];
+                    continue;
+                }
+                3 => "..".to_owned(),
+                4 => ".".to_owned(),
+                5 => cs0(identifier),
+                _ => return None,
+            };
+
+            if !target.is_empty() && !target.ends_with('/') {
+                target.push('/');
+            }
+
+            target.push_str(&component);
+            rest = &tail[*length as usize..];
+        }
+
+        Some(target)
+    }
+
+    /// Recursively walks the whole tree starting at the root directory.
+    pub fn walk(&self) -> UDFWalk<'_, D> {
+        UDFWalk {
+            udf: self,
+            stack: vec![(self.read_root(), String::new(), self.root.location)],
+        }
+    }
+
+    /// Finds an entry by its path, names are compared as is.
+    pub fn entry_by_path(&self, path: &str) -> Option<UDFEntry> {
+        self.entry_by_path_with(path, &NameOptions::EXACT)
+    }
+
+    /// Same as [`entry_by_path`](Self::entry_by_path), but names are compared after normalization.
+    pub fn entry_by_path_with(&self, path: &str, options: &NameOptions) -> Option<UDFEntry> {
+        let mut entry = self.root_entry();
+
+        for stem in path.split('/').filter(|stem| !stem.is_empty()) {
+            entry = self.read_directory(&entry).find(|child| options.matches(&child.name, stem))?;
+        }
+
+        Some(entry)
+    }
+
+    /// Builds the partition maps of the logical volume descriptor.
+    fn read_partition_maps(&mut self, logical: &[u8], descriptors: &[(u16, u32, u32)]) -> Option<()> {
+        let table_length = u32_at(logical, 264)? as usize;
+        let count = u32_at(logical, 268)?;
+        let mut table = logical.get(440..440usize.checked_add(table_length)?)?;
+
+        let physical = |number: u16| descriptors.iter().find(|(found, _, _)| *found == number).map(|(_, start, length)| (*start, *length));
+
+        // Metadata partitions: map index, underlying partition number, file and mirror locations
+        let mut metadata = Vec::new();
+
+        for _ in 0..count {
+            let [map_type, length, ..] = *table else {
+                return None;
+            };
+
+            let map = table.get(..length as usize)?;
+
+            let partition = match map_type {
+                1 => physical(u16_at(map, 4)?).map_or(Partition::Unsupported, |(start, length)| Partition::Physical { start, length }),
+                2 => {
+                    let identifier = map.get(5..28)?;
+                    let number = u16_at(map, 38)?;
+
+                    if identifier.starts_with(b"*UDF Sparable Partition") {
+                        let (start, length) = physical(number)?;
+
+                        Partition::Sparable {
+                            start,
+                            length,
+                            packet_length: u16_at(map, 40)? as u32,
+                            remaps: self.read_sparing_table(map).unwrap_or_default(),
+                        }
+                    } else if identifier.starts_with(b"*UDF Metadata Partition") {
+                        metadata.push((self.partitions.len(), number, u32_at(map, 40)?, u32_at(map, 44)?));
+
+                        Partition::Unsupported
+                    } else {
+                        Partition::Unsupported
+                    }
+                }
+                _ => return None,
+            };
+
+            self.partitions.push(partition);
+            table = &table[length as usize..];
+        }
+
+        for (index, number, file, mirror) in metadata {
+            // The metadata file is stored in the type 1 or sparable map of the same partition
+            let underlying = descriptors.iter().position(|(found, _, _)| *found == number)?;
+            let reference = (0..self.partitions.len()).find(|reference| {
+                *reference != index
+                    && matches!(self.partitions[*reference], Partition::Physical { start, .. } | Partition::Sparable { start, .. } if start == descriptors[underlying].1)
+            })? as u16;
+
+            let extents = [file, mirror].iter().find_map(|block| {
+                match self.read_node(Location { partition: reference, block: *block })?.data {
+                    Allocation::Extents(extents) => Some(extents),
+                    Allocation::Embedded(_) => None,
+                }
+            })?;
+
+            self.partitions[index] = Partition::Metadata { extents };
+        }
+
+        Some(())
+    }
+
+    /// Reads the first readable sparing table of a sparable partition map.
+    ///
+    /// The size recorded in the map is not trusted, the table is read as far as its own
+    /// (16-bit) entry count goes.
+    fn read_sparing_table(&self, map: &[u8]) -> Option<Vec<(u32, u32)>> {
+        let tables = *map.get(42)? as usize;
+        let block_size = self.block_size as usize;
+
+        (0..tables.min(4)).find_map(|index| {
+            let position = u32_at(map, 48 + index * 4)? as u64 * block_size as u64;
+            let mut table = vec![0u8; block_size];
+
+            self.device.read(position, &mut table)?;
+
+            // Tag, "*UDF Sparing Table", number of entries, reserved, sequence number, entries
+            if !table.get(17..35)?.starts_with(b"*UDF Sparing Table") {
+                return None;
+            }
+
+            let entries = u16_at(&table, 48)? as usize;
+            let size = 56 + entries * 8;
+
+            if size > block_size {
+                table.resize(size.next_multiple_of(block_size), 0);
+                self.device.read(position + block_size as u64, &mut table[block_size..])?;
+            }
+
+            Some(
+                table
+                    .get(56..56 + entries * 8)?
+                    .chunks_exact(8)
+                    .map(|entry| (u32_at(entry, 0).unwrap_or(u32::MAX), u32_at(entry, 4).unwrap_or(u32::MAX)))
+                    .filter(|(original, _)| *original < 0xFFFF_FFF0)
+                    .collect(),
+            )
+        })
+    }
+
+    /// Byte position of a block and how many bytes from it are contiguous on the device.
+    fn translate(&self, location: Location, depth: u8) -> Option<(u64, u64)> {
+        let block_size = self.block_size as u64;
+        let block = location.block;
+
+        match self.partitions.get(location.partition as usize)? {
+            Partition::Physical { start, length } if block < *length => {
+                Some(((*start as u64 + block as u64) * block_size, (*length - block) as u64 * block_size))
+            }
+            Partition::Sparable {
+                start,
+                length,
+                packet_length,
+                remaps,
+            } if block < *length && *packet_length != 0 => {
+                let within = block % packet_length;
+                let sector = remaps
+                    .iter()
+                    .find(|(original, _)| *original == block - within)
+                    .map_or(*start as u64 + block as u64, |(_, mapped)| *mapped as u64 + within as u64);
+
+                Some((sector * block_size, (*packet_length - within) as u64 * block_size))
+            }
+            Partition::Metadata { extents } if depth == 0 => {
+                let mut offset = block as u64 * block_size;
+
+                for extent in extents {
+                    if offset < extent.length as u64 {
+                        let inner = Location {
+                            partition: extent.location.partition,
+                            block: extent.location.block.checked_add((offset / block_size) as u32)?,
+                        };
+                        let (position, contiguous) = self.translate(inner, depth + 1)?;
+
+                        return Some((position, contiguous.min(extent.length as u64 - offset)));
+                    }
+
+                    offset -= extent.length as u64;
+                }
+
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// Reads data starting `offset` bytes after the start of a block.
+    fn read(&self, location: Location, mut offset: u64, buffer: &mut [u8]) -> Option<()> {
+        let block_size = self.block_size as u64;
+        let mut done = 0;
+
+        while done < buffer.len() {
+            let block = location.block.checked_add(u32::try_from(offset / block_size).ok()?)?;
+            let within = offset % block_size;
+            let (position, contiguous) = self.translate(Location { block, ..location }, 0)?;
+
+            let count = ((buffer.len() - done) as u64).min(contiguous.checked_sub(within)?) as usize;
+
+            if count == 0 {
+                return None;
+            }
+
+            self.device.read(position + within, &mut buffer[done..done + count])?;
+
+            done += count;
+            offset += count as u64;
+        }
+
+        Some(())
+    }
+
+    /// Reads the file entry or extended file entry at `location`.
+    fn read_node(&self, location: Location) -> Option<Node> {
+        let mut block = vec![0u8; self.block_size as usize];
+
+        self.read(location, 0, &mut block)?;
+
+        // Offsets of the attribute lengths, times and unique ID
+        let (base, accessed, modified, created, unique_id) = match tag(&block)?.0 {
+            TAG_FILE_ENTRY => (168, 72, 84, None, 160),
+            TAG_EXTENDED_FILE_ENTRY => (208, 80, 92, Some(104), 200),
+            _ => return None,
+        };
+
+        // ICB tag: prior entries, strategy, parameters, entries, reserved, file type, parent, flags
+        let strategy = u16_at(&block, 20)?;
+
+        if strategy != STRATEGY_SINGLE && strategy != STRATEGY_WRITE_ONCE {
+            return None;
+        }
+
+        let file_type = block[27];
+        let icb_flags = u16_at(&block, 34)?;
+
+        let extended_attributes = u32_at(&block, base)? as usize;
+        let descriptors = u32_at(&block, base + 4)? as usize;
+        let start = (base + 8).checked_add(extended_attributes)?;
+        let descriptors = block.get(start..start.checked_add(descriptors)?)?;
+
+        let kind = icb_flags & 7;
+        let data = if kind == AD_EMBEDDED {
+            Allocation::Embedded(descriptors.to_vec())
+        } else {
+            Allocation::Extents(self.read_extents(descriptors, kind, location.partition)?)
+        };
+
+        Some(Node {
+            file_type,
+            icb_flags,
+            uid: u32_at(&block, 36)?,
+            gid: u32_at(&block, 40)?,
+            permissions: u32_at(&block, 44)?,
+            links: u16_at(&block, 48)?,
+            size: u64_at(&block, 56)?,
+            accessed: timestamp(block.get(accessed..accessed + 12)?),
+            modified: timestamp(block.get(modified..modified + 12)?),
+            created: created.and_then(|created| timestamp(block.get(created..created + 12)?)),
+            unique_id: u64_at(&block, unique_id)?,
+            data,
+        })
+    }
+
+    /// Decodes allocation descriptors, following allocation extent descriptors.
+    fn read_extents(&self, descriptors: &[u8], kind: u16, partition: u16) -> Option<Vec<Extent>> {
+        let mut extents = Vec::new();
+        let mut next = allocation_descriptors(descriptors, kind, partition, &mut extents)?;
+        let mut block = vec![0u8; self.block_size as usize];
+
+        for _ in 0..MAX_CHAINED_DESCRIPTORS {
+            let Some(location) = next else {
+                return Some(extents);
+            };
+
+            self.read(location, 0, &mut block)?;
+
+            if tag(&block)?.0 != TAG_ALLOCATION_EXTENT {
+                return None;
+            }
+
+            // Tag, previous extent location, length of the descriptors
+            let length = u32_at(&block, 20)? as usize;
+
+            next = allocation_descriptors(block.get(24..24usize.checked_add(length)?)?, kind, partition, &mut extents)?;
+        }
+
+        None
+    }
+
+    fn read_node_data(&self, node: &Node, offset: u64, data: &mut [u8]) -> Option<()> {
+        let end = offset.checked_add(data.len() as u64)?;
+
+        if end > node.size {
+            return None;
+        }
+
+        let extents = match &node.data {
+            Allocation::Embedded(bytes) => {
+                data.copy_from_slice(bytes.get(offset as usize..end as usize)?);
+
+                return Some(());
+            }
+            Allocation::Extents(extents) => extents,
+        };
+
+        let mut start = 0u64;
+
+        for extent in extents {
+            let extent_end = start + extent.length as u64;
+
+            if extent_end > offset && start < end {
+                let from = offset.max(start);
+                let to = end.min(extent_end);
+                let chunk = &mut data[(from - offset) as usize..(to - offset) as usize];
+
+                if extent.recorded {
+                    self.read(extent.location, from - start, chunk)?;
+                } else {
+                    chunk.fill(0);
+                }
+            }
+
+            start = extent_end;
+
+            if start >= end {
+                return Some(());
+            }
+        }
+
+        (start >= end).then_some(())
+    }
+}
+
+/// Whether the volume recognition sequence announces an ECMA-167 (UDF) volume.
+pub fn has_udf_descriptors<D: Read + ?Sized>(device: &D) -> bool {
+    // Descriptors take 2048 bytes, or a whole block on media with larger blocks
+    [2048u64, 4096].iter().any(|step| {
+        let mut header = [0u8; 6];
+
+        for index in 0..MAX_VRS_DESCRIPTORS {
+            if device.read(VRS_START + index * step, &mut header).is_none() {
+                return false;
+            }
+
+            match &header[1..6] {
+                b"NSR02" | b"NSR03" => return true,
+                b"BEA01" | b"TEA01" | b"CD001" | b"BOOT2" | b"CDW02" => {}
+                _ => return false,
+            }
+        }
+
+        false
+    })
+}
+
+/// Descriptors of the volume needed to open it.
+struct Volume {
+    /// Partition number, start and length
+    partitions: Vec<(u16, u32, u32)>,
+    logical_volume: Vec<u8>,
+}
+
+fn read_volume_sequence<D: Read>(device: &D, block_size: u32, mut location: u32, mut length: u32) -> Option<Volume> {
+    let mut partitions = Vec::new();
+    let mut logical_volume = None;
+    let mut block = vec![0u8; block_size as usize];
+    let mut read = 0;
+
+    'sequence: for _ in 0..MAX_CHAINED_DESCRIPTORS {
+        for index in 0..length / block_size {
+            let address = location.checked_add(index)?;
+
+            device.read(address as u64 * block_size as u64, &mut block)?;
+            read += 1;
+
+            match tag(&block) {
+                Some((TAG_PARTITION, _)) => {
+                    // Partition number, then start and length after the contents and access type
+                    let descriptor = (u16_at(&block, 22)?, u32_at(&block, 188)?, u32_at(&block, 192)?);
+
+                    partitions.retain(|(number, _, _)| *number != descriptor.0);
+                    partitions.push(descriptor);
+                }
+                Some((TAG_LOGICAL_VOLUME, _)) => logical_volume = Some(block.clone()),
+                Some((TAG_POINTER, _)) => {
+                    length = u32_at(&block, 20)?;
+                    location = u32_at(&block, 24)?;
+
+                    continue 'sequence;
+                }
+                Some((TAG_PRIMARY_VOLUME, _)) => {}
+                Some((TAG_TERMINATING, _)) | None => break 'sequence,
+                _ => {}
+            }
+
+            if read > MAX_CHAINED_DESCRIPTORS * 16 {
+                return None;
+            }
+        }
+
+        break;
+    }
+
+    Some(Volume {
+        partitions,
+        logical_volume: logical_volume?,
+    })
+}
+
+/// Iterator over a directory, created by [`UDF::read_directory`]. Parent and deleted entries
+/// are skipped, so are entries whose file entry can't be read.
+pub struct UDFDirectoryIter<'udf, D: Read> {
+    udf: &'udf UDF<D>,
+    data: Vec<u8>,
+    position: usize,
+}
+
+impl<D: Read> Iterator for UDFDirectoryIter<'_, D> {
+    type Item = UDFEntry;
+
+    fn next(&mut self) -> Option<UDFEntry> {
+        loop {
+            let descriptor = self.data.get(self.position..)?;
+
+            if descriptor.len() < FILE_IDENTIFIER_SIZE || tag(descriptor)?.0 != TAG_FILE_IDENTIFIER {
+                return None;
+            }
+
+            // Tag, version, characteristics, identifier length, ICB, implementation use length
+            let characteristics = descriptor[18];
+            let identifier_length = descriptor[19] as usize;
+            let icb = long_ad(descriptor.get(20..36)?)?.1;
+            let start = FILE_IDENTIFIER_SIZE + u16_at(descriptor, 36)? as usize;
+            let identifier = descriptor.get(start..start + identifier_length)?;
+
+            self.position += (start + identifier_length).next_multiple_of(4);
+
+            if characteristics & (CHARACTERISTIC_PARENT | CHARACTERISTIC_DELETED) != 0 {
+                continue;
+            }
+
+            let Some(node) = self.udf.read_node(icb) else {
+                continue;
+            };
+
+            return Some(UDFEntry {
+                name: cs0(identifier),
+                location: icb,
+                characteristics,
+                node,
+            });
+        }
+    }
+}
+
+/// An entry yielded by [`UDFWalk`].
+#[derive(Debug, Clone)]
+pub struct UDFWalkEntry {
+    pub entry: UDFEntry,
+    /// Absolute path inside the volume
+    pub path: String,
+    /// 1 for entries of the root directory, 2 for their children and so on
+    pub depth: usize,
+}
+
+/// Depth-first iterator over the tree, created by [`UDF::walk`]. Directories come before their
+/// children, a directory already on the current path (a loop in a crafted image) is not entered.
+pub struct UDFWalk<'udf, D: Read> {
+    udf: &'udf UDF<D>,
+    stack: Vec<(UDFDirectoryIter<'udf, D>, String, Location)>,
+}
+
+impl<D: Read> Iterator for UDFWalk<'_, D> {
+    type Item = UDFWalkEntry;
+
+    fn next(&mut self) -> Option<UDFWalkEntry> {
+        loop {
+            let depth = self.stack.len();
+            let (entries, path, _) = self.stack.last_mut()?;
+
+            let Some(entry) = entries.next() else {
+                self.stack.pop();
+                continue;
+            };
+
+            let path = format!("{path}/{}", entry.name);
+
+            if entry.is_folder() && !self.stack.iter().any(|(_, _, location)| *location == entry.location) {
+                self.stack.push((self.udf.read_directory(&entry), path.clone(), entry.location));
+            }
+
+            return Some(UDFWalkEntry { entry, path, depth });
+        }
+    }
+}
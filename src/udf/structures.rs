@@ -0,0 +1,172 @@
+//! On-disc structures of ECMA-167 and the OSTA UDF specification.
+
+use alloc::{string::String, vec::Vec};
+
+use crate::types;
+
+pub(super) const TAG_PRIMARY_VOLUME: u16 = 1;
+pub(super) const TAG_ANCHOR: u16 = 2;
+pub(super) const TAG_POINTER: u16 = 3;
+pub(super) const TAG_PARTITION: u16 = 5;
+pub(super) const TAG_LOGICAL_VOLUME: u16 = 6;
+pub(super) const TAG_TERMINATING: u16 = 8;
+pub(super) const TAG_FILE_SET: u16 = 256;
+pub(super) const TAG_FILE_IDENTIFIER: u16 = 257;
+pub(super) const TAG_ALLOCATION_EXTENT: u16 = 258;
+pub(super) const TAG_FILE_ENTRY: u16 = 261;
+pub(super) const TAG_EXTENDED_FILE_ENTRY: u16 = 266;
+
+pub(super) const TAG_SIZE: usize = 16;
+
+/// Allocation descriptor types, from the low bits of the ICB tag flags
+const AD_SHORT: u16 = 0;
+const AD_LONG: u16 = 1;
+const AD_EXTENDED: u16 = 2;
+pub(super) const AD_EMBEDDED: u16 = 3;
+
+/// Extent types, from the top bits of the extent length
+const EXTENT_RECORDED: u32 = 0;
+const EXTENT_NEXT: u32 = 3;
+
+pub(super) fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(data.get(offset..offset + 2)?.try_into().ok()?))
+}
+
+pub(super) fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+pub(super) fn u64_at(data: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(data.get(offset..offset + 8)?.try_into().ok()?))
+}
+
+/// Identifier of a descriptor whose tag checksum is valid, and the block it claims to be recorded at.
+pub(super) fn tag(data: &[u8]) -> Option<(u16, u32)> {
+    let header = data.get(..TAG_SIZE)?;
+    let checksum = header
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| *index != 4)
+        .fold(0u8, |sum, (_, byte)| sum.wrapping_add(*byte));
+
+    if checksum != header[4] {
+        return None;
+    }
+
+    Some((u16_at(header, 0)?, u32_at(header, 12)?))
+}
+
+/// Address of a block inside a partition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Location {
+    /// Partition reference number (index of the partition map)
+    pub partition: u16,
+    pub block: u32,
+}
+
+/// `long_ad`: extent length and location
+pub(super) fn long_ad(data: &[u8]) -> Option<(u32, Location)> {
+    Some((
+        u32_at(data, 0)?,
+        Location {
+            block: u32_at(data, 4)?,
+            partition: u16_at(data, 8)?,
+        },
+    ))
+}
+
+/// A run of file data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Extent {
+    pub location: Location,
+    /// Length in bytes
+    pub length: u32,
+    /// Allocated or unallocated extents without recorded data read as zeros
+    pub recorded: bool,
+}
+
+/// Decodes allocation descriptors of type `kind`. Extents of type 3 point at the next
+/// allocation extent descriptor, they are returned in `next` instead.
+pub(super) fn allocation_descriptors(
+    data: &[u8],
+    kind: u16,
+    partition: u16,
+    extents: &mut Vec<Extent>,
+) -> Option<Option<Location>> {
+    let size = match kind {
+        AD_SHORT => 8,
+        AD_LONG => 16,
+        AD_EXTENDED => 20,
+        _ => return None,
+    };
+
+    for descriptor in data.chunks_exact(size) {
+        let raw_length = u32_at(descriptor, 0)?;
+        let (length, extent_type) = (raw_length & 0x3FFF_FFFF, raw_length >> 30);
+
+        if length == 0 {
+            break;
+        }
+
+        let location = match kind {
+            AD_SHORT => Location {
+                block: u32_at(descriptor, 4)?,
+                partition,
+            },
+            AD_LONG => long_ad(descriptor)?.1,
+            _ => Location {
+                block: u32_at(descriptor, 12)?,
+                partition: u16_at(descriptor, 16)?,
+            },
+        };
+
+        if extent_type == EXTENT_NEXT {
+            return Some(Some(location));
+        }
+
+        extents.push(Extent {
+            location,
+            length,
+            recorded: extent_type == EXTENT_RECORDED,
+        });
+    }
+
+    Some(None)
+}
+
+/// Decodes an OSTA compressed Unicode (CS0) string: a compression ID, then 8 or 16-bit characters.
+pub(super) fn cs0(data: &[u8]) -> String {
+    match data.split_first() {
+        Some((8 | 254, bytes)) => bytes.iter().map(|byte| *byte as char).collect(),
+        Some((16 | 255, bytes)) => char::decode_utf16(bytes.chunks_exact(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]])))
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect(),
+        _ => String::new(),
+    }
+}
+
+/// Decodes a fixed-size `dstring`, whose last byte is the length of the used part.
+pub(super) fn dstring(field: &[u8]) -> String {
+    let Some((length, data)) = field.split_last() else {
+        return String::new();
+    };
+
+    cs0(&data[..(*length as usize).min(data.len())])
+}
+
+/// Decodes a 12-byte timestamp into seconds since the Unix epoch, `None` if it is not set.
+pub(super) fn timestamp(data: &[u8]) -> Option<i64> {
+    let type_and_zone = u16_at(data, 0)?;
+    let year = u16_at(data, 2)? as i16;
+    let [month, day, hour, minute, second] = data.get(4..9)?.try_into().ok()?;
+
+    if year == 0 && month == 0 {
+        return None;
+    }
+
+    // Offset from UTC in minutes, a 12-bit signed value, -2047 when not specified
+    let zone = ((type_and_zone << 4) as i16) >> 4;
+    let zone = if type_and_zone >> 12 == 1 && zone != -2047 { zone as i64 } else { 0 };
+
+    Some(types::unix_time(year as i64, month, day, hour, minute, second) - zone * 60)
+}
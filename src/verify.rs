@@ -0,0 +1,65 @@
+//! Media verification: checks that the data of every file can be read.
+
+use crate::{
+    progress::{Cancelled, Monitor},
+    ISODirectoryEntry, Read, ISO9660,
+};
+
+/// Size of chunks used to read file data
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// A file whose data couldn't be read completely.
+#[derive(Debug, Clone)]
+pub struct Unreadable {
+    /// Absolute path inside the image
+    pub path: String,
+    pub entry: ISODirectoryEntry,
+    /// Start of the first chunk that failed
+    pub offset: u64,
+}
+
+impl<D: Read> ISO9660<D> {
+    /// Reads every regular file in the order of their extents and lists the ones that failed,
+    /// e.g. because of damaged sectors or a truncated image.
+    pub fn verify(&self, monitor: &Monitor) -> Result<Vec<Unreadable>, Cancelled> {
+        let mut files: Vec<_> = self
+            .walk()
+            .filter(|item| item.entry.is_file() && !item.is_symlink())
+            .collect();
+
+        files.sort_by_key(|item| item.entry.lsb_position());
+
+        let tracker = monitor.start(files.iter().map(|item| item.entry.file_size()).sum());
+        let mut buffer = vec![0u8; CHUNK_SIZE];
+        let mut result = Vec::new();
+
+        for item in files {
+            let size = item.entry.file_size();
+            let mut offset = 0;
+
+            while offset < size {
+                tracker.check()?;
+
+                let length = (size - offset).min(CHUNK_SIZE as u64) as usize;
+
+                if self.read_file(&item.entry, offset, &mut buffer[..length]).is_none() {
+                    // The rest of the file still counts as processed.
+                    tracker.advance(size - offset, &item.path);
+
+                    result.push(Unreadable {
+                        path: item.path.clone(),
+                        entry: item.entry.clone(),
+                        offset,
+                    });
+
+                    break;
+                }
+
+                offset += length as u64;
+                tracker.advance(length as u64, &item.path);
+            }
+        }
+
+        Ok(result)
+    }
+}
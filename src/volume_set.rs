@@ -0,0 +1,88 @@
+//! Volume sets: one file tree recorded on several discs.
+
+use alloc::vec::Vec;
+
+use crate::{iter::DescriptorIterator, Read};
+
+/// Volumes of a set read as one device.
+///
+/// Plain reads go to the volume with the highest sequence number, whose descriptors and tree
+/// describe the files of the whole set. Extents recorded on other volumes are read through
+/// [`Read::read_volume`] with the sequence number of their directory record.
+pub struct VolumeSet<D: Read> {
+    /// Volumes by sequence number, starting at 1. Missing ones are `None`.
+    volumes: Vec<Option<D>>,
+    /// Index of the volume plain reads go to
+    last: usize,
+}
+
+impl<D: Read> VolumeSet<D> {
+    /// Orders volumes given in any order by the sequence number of their primary volume descriptor.
+    ///
+    /// Some volumes may be left out, reads from them fail. `None` if a device is not ISO9660,
+    /// the volumes are not from the same set or a sequence number is given twice.
+    pub fn new(devices: impl IntoIterator<Item = D>) -> Option<Self> {
+        let mut volumes: Vec<Option<D>> = Vec::new();
+        let mut set: Option<(u16, [u8; 128])> = None;
+
+        for device in devices {
+            let descriptor = DescriptorIterator::new(&device).find_map(|descriptor| descriptor.try_as_pvd().map(|pvd| {
+                (pvd.set_size(), pvd.sequence_number(), pvd.volume_set_id)
+            }));
+            let (size, sequence, id) = descriptor?;
+
+            if sequence == 0 || sequence > size || set.is_some_and(|set| set != (size, id)) {
+                return None;
+            }
+
+            set = Some((size, id));
+            volumes.resize_with(size as usize, || None);
+
+            let slot = &mut volumes[sequence as usize - 1];
+
+            if slot.is_some() {
+                return None;
+            }
+
+            *slot = Some(device);
+        }
+
+        let last = volumes.iter().rposition(Option::is_some)?;
+
+        Some(Self { volumes, last })
+    }
+
+    /// Number of volumes in the set, as recorded on them
+    pub fn set_size(&self) -> u16 {
+        self.volumes.len() as u16
+    }
+
+    /// Whether every volume of the set is there
+    pub fn is_complete(&self) -> bool {
+        self.volumes.iter().all(Option::is_some)
+    }
+
+    /// Volume with the given sequence number
+    pub fn volume(&self, sequence: u16) -> Option<&D> {
+        self.volumes.get((sequence as usize).checked_sub(1)?)?.as_ref()
+    }
+
+    pub fn into_inner(self) -> Vec<Option<D>> {
+        self.volumes
+    }
+}
+
+impl<D: Read> Read for VolumeSet<D> {
+    fn read(&self, position: u64, buffer: &mut [u8]) -> Option<()> {
+        self.volumes[self.last].as_ref()?.read(position, buffer)
+    }
+
+    fn read_volume(&self, sequence: u16, position: u64, buffer: &mut [u8]) -> Option<()> {
+        // Some writers record 0 for single volume images
+        if sequence == 0 {
+            return self.read(position, buffer);
+        }
+
+        self.volume(sequence)?.read(position, buffer)
+    }
+}
@@ -0,0 +1,294 @@
+//! Recursive traversal of the directory tree.
+
+use alloc::{
+    boxed::Box,
+    format,
+    string::String,
+    vec::{self, Vec},
+};
+
+use crate::{iter::ListOptions, ISODirectoryEntry, ISODirectoryRecord, Read, ISO9660};
+
+/// Limit of symbolic links resolved while looking up a single target.
+const MAX_SYMLINK_HOPS: usize = 40;
+
+/// When directories are yielded relative to their contents.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum WalkOrder {
+    /// A directory comes before its children
+    #[default]
+    PreOrder,
+    /// A directory comes after its children
+    PostOrder,
+}
+
+/// An entry yielded by [`Walk`].
+#[derive(Debug, Clone)]
+pub struct WalkEntry {
+    pub entry: ISODirectoryEntry,
+    /// Absolute path inside the image, e.g. `/boot/grub/grub.cfg`
+    pub path: String,
+    /// 1 for entries of the starting directory, 2 for their children and so on
+    pub depth: usize,
+    /// Target of the Rock Ridge symbolic link, if the entry is one.
+    /// When links are followed, `entry.record` describes the link target instead.
+    pub symlink_target: Option<String>,
+}
+
+impl WalkEntry {
+    pub fn name(&self) -> &str {
+        &self.entry.name
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        self.symlink_target.is_some()
+    }
+}
+
+type EntryFilter<'a> = Box<dyn FnMut(&WalkEntry) -> bool + 'a>;
+
+struct Frame {
+    entries: vec::IntoIter<WalkEntry>,
+    /// The directory listed by this frame, yielded after its children in post-order
+    directory: Option<WalkEntry>,
+}
+
+/// Depth-first iterator over a directory tree, created by [`ISO9660::walk`].
+///
+/// "." and ".." are never yielded. A directory whose extent is already on the
+/// current path (a loop made by symbolic links or a crafted image) is yielded but not entered.
+pub struct Walk<'iso, D: Read> {
+    iso: &'iso ISO9660<D>,
+    order: WalkOrder,
+    max_depth: Option<usize>,
+    follow_symlinks: bool,
+    filter: Option<EntryFilter<'iso>>,
+    list_options: ListOptions,
+    start: Option<(ISODirectoryRecord, String)>,
+    stack: Vec<Frame>,
+    /// Volumes and extent LBAs of the directories on the current path
+    ancestors: Vec<(u16, u32)>,
+}
+
+impl<'iso, D: Read> Walk<'iso, D> {
+    pub(crate) fn new(iso: &'iso ISO9660<D>, directory: ISODirectoryRecord, path: String) -> Self {
+        Self {
+            iso,
+            order: WalkOrder::default(),
+            max_depth: None,
+            follow_symlinks: false,
+            filter: None,
+            list_options: ListOptions::ALL,
+            start: Some((directory, path)),
+            stack: Vec::new(),
+            ancestors: Vec::new(),
+        }
+    }
+
+    pub fn order(mut self, order: WalkOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Limits the depth of yielded entries. `1` only lists the starting directory.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Resolves Rock Ridge symbolic links and walks into linked directories.
+    pub fn follow_symlinks(mut self, follow: bool) -> Self {
+        self.follow_symlinks = follow;
+        self
+    }
+
+    /// Skips entries for which `predicate` returns `false`. Skipped directories are not entered.
+    pub fn filter_entry(mut self, predicate: impl FnMut(&WalkEntry) -> bool + 'iso) -> Self {
+        self.filter = Some(Box::new(predicate));
+        self
+    }
+
+    /// Leaves out hidden or associated records of every listed directory.
+    pub fn list_options(mut self, options: ListOptions) -> Self {
+        self.list_options = options;
+        self
+    }
+
+    fn list(&mut self, directory: &ISODirectoryRecord, parent: &str, depth: usize) -> Vec<WalkEntry> {
+        let mut result = Vec::new();
+        let mut entries = self.iso.read_directory_record(directory).options(self.list_options);
+
+        while let Some(raw) = entries.next_raw() {
+            if matches!(raw.identifier, [0] | [1]) {
+                continue;
+            }
+
+            let entry = raw.to_entry();
+            let path = join(parent, &entry.name);
+
+            result.push(WalkEntry {
+                entry,
+                path,
+                depth,
+                symlink_target: raw.symlink_target(),
+            });
+        }
+
+        if self.follow_symlinks {
+            for item in result.iter_mut() {
+                let Some(target) = &item.symlink_target else {
+                    continue;
+                };
+
+                let parent = &item.path[..item.path.len() - item.entry.name.len()];
+                let resolved = lookup(self.iso, &resolve(parent, target), &mut 0);
+
+                if let Some((resolved, _)) = resolved {
+                    item.entry.record = resolved.record;
+                }
+            }
+        }
+
+        result
+    }
+}
+
+impl<D: Read> Iterator for Walk<'_, D> {
+    type Item = WalkEntry;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some((directory, path)) = self.start.take() {
+            let entries = self.list(&directory, &path, 1);
+
+            self.ancestors.push((directory.volume_sequence_number(), directory.data_lba()));
+            self.stack.push(Frame {
+                entries: entries.into_iter(),
+                directory: None,
+            });
+        }
+
+        loop {
+            let frame = self.stack.last_mut()?;
+
+            let Some(item) = frame.entries.next() else {
+                let frame = self.stack.pop()?;
+                self.ancestors.pop();
+
+                match frame.directory {
+                    Some(directory) => return Some(directory),
+                    None => continue,
+                }
+            };
+
+            if let Some(filter) = self.filter.as_mut() {
+                if !filter(&item) {
+                    continue;
+                }
+            }
+
+            let extent = (item.entry.volume_sequence_number(), item.entry.data_lba());
+            let descend = item.entry.is_folder()
+                && self.max_depth.is_none_or(|max| item.depth < max)
+                && !self.ancestors.contains(&extent);
+
+            if !descend {
+                return Some(item);
+            }
+
+            let children = self.list(&item.entry.record, &item.path, item.depth + 1);
+            self.ancestors.push(extent);
+
+            match self.order {
+                WalkOrder::PreOrder => {
+                    self.stack.push(Frame {
+                        entries: children.into_iter(),
+                        directory: None,
+                    });
+
+                    return Some(item);
+                }
+                WalkOrder::PostOrder => {
+                    self.stack.push(Frame {
+                        entries: children.into_iter(),
+                        directory: Some(item),
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Looks up an absolute path, following symbolic links met on the way.
+/// Returns the entry and its path with all links resolved.
+fn lookup<D: Read>(iso: &ISO9660<D>, path: &str, hops: &mut usize) -> Option<(ISODirectoryEntry, String)> {
+    let mut current = iso.root_entry();
+    let mut current_path = String::from("/");
+
+    for component in path.split('/').filter(|v| !v.is_empty()) {
+        if current.is_file() {
+            return None;
+        }
+
+        let mut entries = iso.read_directory_record(&current.record);
+
+        let (mut entry, target) = loop {
+            let raw = entries.next_raw()?;
+
+            if raw.name() == component {
+                break (raw.to_entry(), raw.symlink_target());
+            }
+        };
+
+        let entry_path = join(&current_path, &entry.name);
+
+        current_path = match target {
+            Some(target) => {
+                *hops += 1;
+
+                if *hops > MAX_SYMLINK_HOPS {
+                    return None;
+                }
+
+                let (resolved, resolved_path) = lookup(iso, &resolve(&current_path, &target), hops)?;
+                entry.record = resolved.record;
+                entry.system_use = resolved.system_use;
+
+                resolved_path
+            }
+            None => entry_path,
+        };
+
+        current = entry;
+    }
+
+    Some((current, current_path))
+}
+
+fn join(parent: &str, name: &str) -> String {
+    if parent.ends_with('/') {
+        format!("{parent}{name}")
+    } else {
+        format!("{parent}/{name}")
+    }
+}
+
+/// Turns a link target into an absolute path, `parent` is the directory containing the link.
+fn resolve(parent: &str, target: &str) -> String {
+    let mut components: Vec<&str> = Vec::new();
+
+    if !target.starts_with('/') {
+        components.extend(parent.split('/').filter(|v| !v.is_empty()));
+    }
+
+    for component in target.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => {
+                components.pop();
+            }
+            other => components.push(other),
+        }
+    }
+
+    format!("/{}", components.join("/"))
+}
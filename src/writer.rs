@@ -0,0 +1,981 @@
+//! Mastering of Rock Ridge images from a stream of entries.
+//!
+//! File data is written out as soon as it's added, only the tree is kept in memory.
+//! Directories, continuation areas and path tables follow the data, the volume descriptor
+//! is written last by seeking back to the start of the image.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    io::{self, Seek, SeekFrom, Write},
+    ops::Range,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use zerocopy::{FromZeros, IntoBytes};
+
+use crate::{
+    descriptors::PrimarySupplementaryVolumeDescriptor, types::LSB_MSB, ISODateTime, ISODirectoryRecord,
+    DISK_SECTOR_SIZE, FileFlags,
+};
+
+/// First block of file data, after the system area, the volume descriptor and the terminator
+const DATA_START: u32 = 18;
+
+const MAX_RECORD_LENGTH: usize = 255;
+
+/// Zero blocks closing the image, like other mastering tools do, so readers reading ahead
+/// past the last extent don't fail
+const PADDING_BLOCKS: u32 = 150;
+
+/// Size of a `CE` entry
+const CE_LENGTH: usize = 28;
+
+/// Most bytes following the header of an `NM` or `SL` entry
+const MAX_PIECE: usize = 250;
+
+const S_IFIFO: u32 = 0o010000;
+const S_IFCHR: u32 = 0o020000;
+const S_IFDIR: u32 = 0o040000;
+const S_IFBLK: u32 = 0o060000;
+const S_IFREG: u32 = 0o100000;
+const S_IFLNK: u32 = 0o120000;
+const S_IFSOCK: u32 = 0o140000;
+
+const SL_COMPONENT_CONTINUE: u8 = 1 << 0;
+const SL_COMPONENT_CURRENT: u8 = 1 << 1;
+const SL_COMPONENT_PARENT: u8 = 1 << 2;
+const SL_COMPONENT_ROOT: u8 = 1 << 3;
+
+const RRIP_ID: &[u8] = b"RRIP_1991A";
+const RRIP_DESCRIPTION: &[u8] = b"THE ROCK RIDGE INTERCHANGE PROTOCOL PROVIDES SUPPORT FOR POSIX FILE SYSTEM SEMANTICS";
+const RRIP_SOURCE: &[u8] = b"PLEASE CONTACT DISC PUBLISHER FOR SPECIFICATION SOURCE.  SEE PUBLISHER IDENTIFIER IN PRIMARY VOLUME DESCRIPTOR FOR CONTACT INFORMATION.";
+
+/// Owner, permissions and modification time of a new entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Metadata {
+    /// Permission bits, the file type comes from the kind of entry
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+    /// Seconds since the Unix epoch
+    pub mtime: i64,
+}
+
+impl Default for Metadata {
+    fn default() -> Self {
+        Self {
+            mode: 0o644,
+            uid: 0,
+            gid: 0,
+            mtime: 0,
+        }
+    }
+}
+
+/// Files without data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Special {
+    Fifo,
+    Socket,
+    CharDevice { major: u32, minor: u32 },
+    BlockDevice { major: u32, minor: u32 },
+}
+
+#[derive(Clone)]
+enum Kind {
+    /// Children by name
+    Directory(BTreeMap<String, usize>),
+    File { lba: u32, size: u32 },
+    Symlink(String),
+    Special(Special),
+}
+
+#[derive(Clone)]
+struct Node {
+    metadata: Metadata,
+    kind: Kind,
+    /// `st_ino`, shared by hard links
+    serial: u32,
+}
+
+impl Node {
+    fn is_directory(&self) -> bool {
+        matches!(self.kind, Kind::Directory(_))
+    }
+
+    fn mode(&self) -> u32 {
+        let kind = match self.kind {
+            Kind::Directory(_) => S_IFDIR,
+            Kind::File { .. } => S_IFREG,
+            Kind::Symlink(_) => S_IFLNK,
+            Kind::Special(Special::Fifo) => S_IFIFO,
+            Kind::Special(Special::Socket) => S_IFSOCK,
+            Kind::Special(Special::CharDevice { .. }) => S_IFCHR,
+            Kind::Special(Special::BlockDevice { .. }) => S_IFBLK,
+        };
+
+        kind | (self.metadata.mode & 0o7777)
+    }
+}
+
+/// A record of a directory being laid out.
+struct Record {
+    /// File identifier, `\0` and `\1` for "." and ".."
+    identifier: Vec<u8>,
+    node: usize,
+    /// Rock Ridge entries in the order they are stored
+    entries: Vec<Vec<u8>>,
+}
+
+impl Record {
+    fn padding(&self) -> usize {
+        usize::from(self.identifier.len().is_multiple_of(2))
+    }
+
+    /// Room for the system use area inside the record
+    fn budget(&self) -> usize {
+        MAX_RECORD_LENGTH - size_of::<ISODirectoryRecord>() - self.identifier.len() - self.padding()
+    }
+
+    fn length(&self) -> usize {
+        let (inline, areas) = split(&self.entries, self.budget());
+        let system_use: usize = self.entries[..inline].iter().map(Vec::len).sum();
+        let continuation = if areas.is_empty() { 0 } else { CE_LENGTH };
+
+        size_of::<ISODirectoryRecord>() + self.identifier.len() + self.padding() + system_use + continuation
+    }
+}
+
+/// A directory of the finished image.
+struct Directory {
+    node: usize,
+    /// Number of the parent in the path table, starting at 1
+    parent: u16,
+    identifier: Vec<u8>,
+    records: Vec<Record>,
+    lba: u32,
+    size: u32,
+}
+
+/// Writes a Rock Ridge image: entries are added one by one, then [`ImageWriter::finish`]
+/// writes the directory structure.
+///
+/// Paths are relative to the root, missing parent directories are created. An entry added
+/// again replaces the previous one, except that directories only get their metadata updated.
+/// The data of replaced files stays in the image.
+pub struct ImageWriter<W: Write + Seek> {
+    output: W,
+    volume_id: String,
+    /// The root comes first
+    nodes: Vec<Node>,
+    /// Next free block after the data written so far
+    next_lba: u32,
+}
+
+impl<W: Write + Seek> ImageWriter<W> {
+    /// Starts an image at the beginning of `output`.
+    pub fn new(mut output: W) -> io::Result<Self> {
+        output.seek(SeekFrom::Start(0))?;
+        output.write_all(&[0; DISK_SECTOR_SIZE * DATA_START as usize])?;
+
+        Ok(Self {
+            output,
+            volume_id: String::from("CDROM"),
+            nodes: vec![Node {
+                metadata: Metadata {
+                    mode: 0o755,
+                    mtime: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs() as i64),
+                    ..Metadata::default()
+                },
+                kind: Kind::Directory(BTreeMap::new()),
+                serial: 1,
+            }],
+            next_lba: DATA_START,
+        })
+    }
+
+    /// Volume label, stored uppercase with characters outside `A-Z0-9_` replaced by `_`.
+    pub fn volume_id(mut self, id: &str) -> Self {
+        self.volume_id = id.chars().take(32).map(d_character).collect();
+        self
+    }
+
+    /// Adds a directory, or updates the metadata of an existing one. An empty path is the root.
+    pub fn add_directory(&mut self, path: &str, metadata: &Metadata) -> io::Result<()> {
+        let Some((parent, name)) = self.parent(path, metadata, true)? else {
+            self.nodes[0].metadata = *metadata;
+            return Ok(());
+        };
+
+        if let Some(&existing) = self.children(parent).get(&name) {
+            if self.nodes[existing].is_directory() {
+                self.nodes[existing].metadata = *metadata;
+                return Ok(());
+            }
+        }
+
+        self.link(parent, name, metadata, Kind::Directory(BTreeMap::new()));
+
+        Ok(())
+    }
+
+    /// Adds a regular file, its `size` bytes of content are copied from `data` right away.
+    pub fn add_file(&mut self, path: &str, metadata: &Metadata, size: u64, data: &mut impl io::Read) -> io::Result<()> {
+        let size = u32::try_from(size)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, format!("{path}: files over 4 GiB are not supported")))?;
+
+        let (parent, name) = self.parent(path, metadata, false)?.ok_or_else(|| root_error(path))?;
+        let lba = if size == 0 { 0 } else { self.next_lba };
+
+        let copied = io::copy(&mut io::Read::take(&mut *data, size as u64), &mut self.output)?;
+
+        if copied < size as u64 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, format!("{path}: data ended after {copied} bytes")));
+        }
+
+        let blocks = (size as u64).div_ceil(DISK_SECTOR_SIZE as u64);
+        let padding = (blocks * DISK_SECTOR_SIZE as u64 - size as u64) as usize;
+
+        self.output.write_all(&[0; DISK_SECTOR_SIZE][..padding])?;
+        self.next_lba = u32::try_from(self.next_lba as u64 + blocks).map_err(|_| too_large())?;
+
+        self.link(parent, name, metadata, Kind::File { lba, size });
+
+        Ok(())
+    }
+
+    /// Adds a symbolic link to `target`.
+    pub fn add_symlink(&mut self, path: &str, metadata: &Metadata, target: &str) -> io::Result<()> {
+        let (parent, name) = self.parent(path, metadata, false)?.ok_or_else(|| root_error(path))?;
+
+        self.link(parent, name, metadata, Kind::Symlink(target.into()));
+
+        Ok(())
+    }
+
+    /// Adds a fifo, socket or device.
+    pub fn add_special(&mut self, path: &str, metadata: &Metadata, special: Special) -> io::Result<()> {
+        let (parent, name) = self.parent(path, metadata, false)?.ok_or_else(|| root_error(path))?;
+
+        self.link(parent, name, metadata, Kind::Special(special));
+
+        Ok(())
+    }
+
+    /// Adds a hard link to an entry that was added before, sharing its data and metadata.
+    pub fn add_hardlink(&mut self, path: &str, target: &str) -> io::Result<()> {
+        let node = self
+            .find(target)?
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("{target}: no such file")))?;
+
+        if self.nodes[node].is_directory() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{target}: hard links to directories are not supported")));
+        }
+
+        let node = self.nodes[node].clone();
+        let (parent, name) = self.parent(path, &node.metadata, false)?.ok_or_else(|| root_error(path))?;
+
+        self.nodes.push(node);
+
+        let index = self.nodes.len() - 1;
+
+        self.children_mut(parent).insert(name, index);
+
+        Ok(())
+    }
+
+    /// Writes directories, path tables and the volume descriptor, returns the output.
+    pub fn finish(mut self) -> io::Result<W> {
+        let mut directories = self.layout()?;
+
+        // Extents of directories follow the data, continuation areas follow them.
+        let mut lba = self.next_lba;
+
+        for directory in &mut directories {
+            directory.lba = lba;
+            lba = lba.checked_add(directory.size / DISK_SECTOR_SIZE as u32).ok_or_else(too_large)?;
+        }
+
+        let extents: HashMap<usize, (u32, u32)> = directories
+            .iter()
+            .map(|directory| (directory.node, (directory.lba, directory.size)))
+            .collect();
+
+        let continuation_lba = lba;
+        let mut continuation = Vec::new();
+
+        for directory in &directories {
+            let mut extent = Vec::with_capacity(directory.size as usize);
+
+            for record in &directory.records {
+                let bytes = self.record_bytes(record, &extents, continuation_lba, &mut continuation);
+
+                // Records never cross sector boundaries.
+                if extent.len() % DISK_SECTOR_SIZE + bytes.len() > DISK_SECTOR_SIZE {
+                    extent.resize(extent.len().next_multiple_of(DISK_SECTOR_SIZE), 0);
+                }
+
+                extent.extend_from_slice(&bytes);
+            }
+
+            extent.resize(directory.size as usize, 0);
+            self.output.write_all(&extent)?;
+        }
+
+        continuation.resize(continuation.len().next_multiple_of(DISK_SECTOR_SIZE), 0);
+        self.output.write_all(&continuation)?;
+
+        let little_endian = path_table(&directories, false);
+        let big_endian = path_table(&directories, true);
+        let path_table_blocks = little_endian.len().div_ceil(DISK_SECTOR_SIZE) as u32;
+
+        // Also catches continuation areas placed past the last address
+        let path_table_lba = u32::try_from(continuation.len() / DISK_SECTOR_SIZE)
+            .ok()
+            .and_then(|blocks| continuation_lba.checked_add(blocks))
+            .ok_or_else(too_large)?;
+        let volume_size = path_table_blocks
+            .checked_mul(2)
+            .and_then(|blocks| path_table_lba.checked_add(blocks))
+            .and_then(|lba| lba.checked_add(PADDING_BLOCKS))
+            .ok_or_else(too_large)?;
+
+        for table in [&little_endian, &big_endian] {
+            self.output.write_all(table)?;
+            self.output.write_all(&vec![0; path_table_blocks as usize * DISK_SECTOR_SIZE - table.len()])?;
+        }
+
+        self.output.write_all(&[0; DISK_SECTOR_SIZE * PADDING_BLOCKS as usize])?;
+
+        let mut volume = PrimarySupplementaryVolumeDescriptor::new_zeroed();
+
+        volume.system_name = [b' '; 32];
+        volume.label = [b' '; 32];
+        volume.label[..self.volume_id.len()].copy_from_slice(self.volume_id.as_bytes());
+        volume.volume_space_size = [
+            u32::from_ne_bytes(volume_size.to_le_bytes()),
+            u32::from_ne_bytes(volume_size.to_be_bytes()),
+        ];
+        volume.volume_set_size = both_u16(1);
+        volume.volume_sequence_number = both_u16(1);
+        volume.logical_block_size = both_u16(DISK_SECTOR_SIZE as u16);
+        volume.path_table_size = [
+            u32::from_ne_bytes((little_endian.len() as u32).to_le_bytes()),
+            u32::from_ne_bytes((little_endian.len() as u32).to_be_bytes()),
+        ];
+        volume.loc_of_type_l_path_table = u32::from_ne_bytes(path_table_lba.to_le_bytes());
+        volume.loc_of_type_m_path_table = u32::from_ne_bytes((path_table_lba + path_table_blocks).to_be_bytes());
+        let root = Record {
+            identifier: vec![0],
+            node: 0,
+            entries: Vec::new(),
+        };
+
+        volume.directory_entry = self
+            .record_bytes(&root, &extents, continuation_lba, &mut Vec::new())
+            .try_into()
+            .map_err(|_| io::Error::other("root record doesn't fit the volume descriptor"))?;
+        volume.volume_set_id = [b' '; 128];
+        volume.publisher_id = [b' '; 128];
+        volume.data_preparer_id = [b' '; 128];
+        volume.application_id = [b' '; 128];
+        volume.copyright_file_id = [b' '; 37];
+        volume.abstract_file_id = [b' '; 37];
+        volume.bibliographic_file_id = [b' '; 37];
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs() as i64);
+
+        volume.volume_creation_date = dec_datetime(Some(now));
+        volume.volume_modification_date = dec_datetime(Some(now));
+        volume.volume_expiration_date = dec_datetime(None);
+        volume.volume_effective_date = dec_datetime(None);
+        volume.file_structure_version = 1;
+
+        self.output.seek(SeekFrom::Start(16 * DISK_SECTOR_SIZE as u64))?;
+        self.output.write_all(&[1])?;
+        self.output.write_all(b"CD001\x01")?;
+        self.output.write_all(volume.as_bytes())?;
+
+        let mut terminator = [0u8; DISK_SECTOR_SIZE];
+
+        terminator[..7].copy_from_slice(b"\xffCD001\x01");
+        self.output.write_all(&terminator)?;
+
+        self.output.seek(SeekFrom::Start(volume_size as u64 * DISK_SECTOR_SIZE as u64))?;
+        self.output.flush()?;
+
+        Ok(self.output)
+    }
+
+    /// Orders directories like the path table wants them: level by level, by parent, by identifier.
+    fn layout(&self) -> io::Result<Vec<Directory>> {
+        let links = self.link_counts();
+        let mut directories = vec![Directory {
+            node: 0,
+            parent: 1,
+            identifier: vec![0],
+            records: Vec::new(),
+            lba: 0,
+            size: 0,
+        }];
+
+        let mut index = 0;
+
+        while index < directories.len() {
+            let node = directories[index].node;
+            let parent = directories[directories[index].parent as usize - 1].node;
+
+            let mut taken = HashSet::new();
+            let mut children: Vec<(Vec<u8>, &str, usize)> = self
+                .children(node)
+                .iter()
+                .map(|(name, &child)| (identifier(name, self.nodes[child].is_directory(), &mut taken), name.as_str(), child))
+                .collect();
+
+            children.sort();
+
+            let mut records = vec![self.dot_record(node, 0, &links), self.dot_record(parent, 1, &links)];
+
+            for (identifier, name, child) in children {
+                if self.nodes[child].is_directory() {
+                    // Parents are referred to by 16-bit numbers in the path table.
+                    if index + 1 >= u16::MAX as usize {
+                        return Err(io::Error::new(io::ErrorKind::InvalidInput, "too many directories"));
+                    }
+
+                    directories.push(Directory {
+                        node: child,
+                        parent: index as u16 + 1,
+                        identifier: identifier.clone(),
+                        records: Vec::new(),
+                        lba: 0,
+                        size: 0,
+                    });
+                }
+
+                records.push(Record {
+                    entries: self.rock_ridge(child, Some(name), &links),
+                    identifier,
+                    node: child,
+                });
+            }
+
+            let mut size = 0;
+
+            for record in &records {
+                let length = record.length();
+
+                if size % DISK_SECTOR_SIZE + length > DISK_SECTOR_SIZE {
+                    size = size.next_multiple_of(DISK_SECTOR_SIZE);
+                }
+
+                size += length;
+            }
+
+            directories[index].records = records;
+            directories[index].size = size.next_multiple_of(DISK_SECTOR_SIZE) as u32;
+
+            index += 1;
+        }
+
+        Ok(directories)
+    }
+
+    /// Number of names of every serial number, subdirectories are counted for directories.
+    fn link_counts(&self) -> HashMap<u32, u32> {
+        let mut links = HashMap::new();
+        let mut pending = vec![0];
+
+        while let Some(node) = pending.pop() {
+            let mut count = 2;
+
+            for &child in self.children(node).values() {
+                if self.nodes[child].is_directory() {
+                    count += 1;
+                    pending.push(child);
+                } else {
+                    *links.entry(self.nodes[child].serial).or_insert(0) += 1;
+                }
+            }
+
+            links.insert(self.nodes[node].serial, count);
+        }
+
+        links
+    }
+
+    /// `.` (`identifier` 0) or `..` (1) record pointing to directory `node`.
+    fn dot_record(&self, node: usize, identifier: u8, links: &HashMap<u32, u32>) -> Record {
+        let mut entries = Vec::new();
+
+        // Readers look for `SP` at the start of the root's `.` record.
+        if node == 0 && identifier == 0 {
+            entries.push(vec![b'S', b'P', 7, 1, 0xBE, 0xEF, 0]);
+        }
+
+        entries.extend(self.rock_ridge(node, None, links));
+
+        if node == 0 && identifier == 0 {
+            let mut er = vec![b'E', b'R', 0, 1, RRIP_ID.len() as u8, RRIP_DESCRIPTION.len() as u8, RRIP_SOURCE.len() as u8, 1];
+
+            er.extend_from_slice(RRIP_ID);
+            er.extend_from_slice(RRIP_DESCRIPTION);
+            er.extend_from_slice(RRIP_SOURCE);
+            er[2] = er.len() as u8;
+
+            entries.push(er);
+        }
+
+        Record {
+            identifier: vec![identifier],
+            node,
+            entries,
+        }
+    }
+
+    /// `PX`, `TF` and, for named entries, `NM`, `SL` and `PN` entries of a node.
+    fn rock_ridge(&self, node: usize, name: Option<&str>, links: &HashMap<u32, u32>) -> Vec<Vec<u8>> {
+        let node = &self.nodes[node];
+        let mut entries = Vec::new();
+
+        let mut px = vec![b'P', b'X', 44, 1];
+
+        for value in [node.mode(), links.get(&node.serial).copied().unwrap_or(1), node.metadata.uid, node.metadata.gid, node.serial] {
+            push_both(&mut px, value);
+        }
+
+        entries.push(px);
+
+        // Modification, access and attribute change times.
+        let mut tf = vec![b'T', b'F', 26, 1, 0b1110];
+        let stamp = ISODateTime::from_unix_time(node.metadata.mtime);
+
+        for _ in 0..3 {
+            tf.extend_from_slice(stamp.as_bytes());
+        }
+
+        entries.push(tf);
+
+        let Some(name) = name else {
+            return entries;
+        };
+
+        let mut pieces = name.as_bytes().chunks(MAX_PIECE).peekable();
+
+        while let Some(piece) = pieces.next() {
+            let mut nm = vec![b'N', b'M', (5 + piece.len()) as u8, 1, u8::from(pieces.peek().is_some())];
+
+            nm.extend_from_slice(piece);
+            entries.push(nm);
+        }
+
+        match &node.kind {
+            Kind::Symlink(target) => entries.extend(symlink_entries(target)),
+            Kind::Special(Special::CharDevice { major, minor } | Special::BlockDevice { major, minor }) => {
+                // The high word is the major number, the low word the minor one.
+                let mut pn = vec![b'P', b'N', 20, 1];
+
+                push_both(&mut pn, *major);
+                push_both(&mut pn, *minor);
+                entries.push(pn);
+            }
+            _ => {}
+        }
+
+        entries
+    }
+
+    /// Bytes of a directory record, overflowing Rock Ridge entries are appended to `continuation`.
+    fn record_bytes(
+        &self,
+        record: &Record,
+        extents: &HashMap<usize, (u32, u32)>,
+        continuation_lba: u32,
+        continuation: &mut Vec<u8>,
+    ) -> Vec<u8> {
+        let node = &self.nodes[record.node];
+
+        let (lba, size) = match node.kind {
+            Kind::Directory(_) => extents.get(&record.node).copied().unwrap_or_default(),
+            Kind::File { lba, size } => (lba, size),
+            _ => (0, 0),
+        };
+
+        let (inline, areas) = split(&record.entries, record.budget());
+
+        // Areas never cross sector boundaries, all are placed before they're filled in
+        // because each one points to the next.
+        let lengths: Vec<usize> = areas
+            .iter()
+            .enumerate()
+            .map(|(index, area)| {
+                let length: usize = record.entries[area.clone()].iter().map(Vec::len).sum();
+
+                length + if index + 1 < areas.len() { CE_LENGTH } else { 0 }
+            })
+            .collect();
+
+        let positions: Vec<usize> = lengths
+            .iter()
+            .map(|&length| {
+                if continuation.len() % DISK_SECTOR_SIZE + length > DISK_SECTOR_SIZE {
+                    continuation.resize(continuation.len().next_multiple_of(DISK_SECTOR_SIZE), 0);
+                }
+
+                continuation.resize(continuation.len() + length, 0);
+                continuation.len() - length
+            })
+            .collect();
+
+        let ce = |index: usize| {
+            let position = positions[index];
+            let mut entry = vec![b'C', b'E', CE_LENGTH as u8, 1];
+
+            // An area past the last address makes `finish` fail once the continuation size is known
+            push_both(&mut entry, continuation_lba.saturating_add((position / DISK_SECTOR_SIZE) as u32));
+            push_both(&mut entry, (position % DISK_SECTOR_SIZE) as u32);
+            push_both(&mut entry, lengths[index] as u32);
+
+            entry
+        };
+
+        for (index, area) in areas.iter().enumerate() {
+            let mut bytes = record.entries[area.clone()].concat();
+
+            if index + 1 < areas.len() {
+                bytes.extend(ce(index + 1));
+            }
+
+            continuation[positions[index]..positions[index] + bytes.len()].copy_from_slice(&bytes);
+        }
+
+        let mut system_use = record.entries[..inline].concat();
+
+        if !areas.is_empty() {
+            system_use.extend(ce(0));
+        }
+
+        let length = size_of::<ISODirectoryRecord>() + record.identifier.len() + record.padding() + system_use.len();
+
+        let header = ISODirectoryRecord {
+            length: length as u8,
+            xar_length: 0,
+            lba: LSB_MSB::<u32>::new(lba),
+            data_length: LSB_MSB::<u32>::new(size),
+            datetime: ISODateTime::from_unix_time(node.metadata.mtime),
+            flags: if node.is_directory() { FileFlags::Directory.bits() } else { 0 },
+            unit_size: 0,
+            interleave_gap_size: 0,
+            volume_seq_number: LSB_MSB::<u16>::new(1),
+            file_identifier_length: record.identifier.len() as u8,
+        };
+
+        let mut bytes = header.as_bytes().to_vec();
+
+        bytes.extend_from_slice(&record.identifier);
+        bytes.resize(bytes.len() + record.padding(), 0);
+        bytes.extend(system_use);
+
+        bytes
+    }
+
+    /// Parent directory and name of a new entry, `None` for the root.
+    /// Missing directories are created with the permissions of `metadata` opened up for listing.
+    fn parent(&mut self, path: &str, metadata: &Metadata, directory: bool) -> io::Result<Option<(usize, String)>> {
+        let components = components(path)?;
+
+        let Some((name, ancestors)) = components.split_last() else {
+            return Ok(None);
+        };
+
+        let mut parent = 0;
+
+        for component in ancestors {
+            parent = match self.children(parent).get(*component) {
+                Some(&child) if self.nodes[child].is_directory() => child,
+                Some(_) => return Err(io::Error::new(io::ErrorKind::NotADirectory, format!("{path}: {component} is not a directory"))),
+                None => {
+                    let metadata = Metadata {
+                        mode: 0o755,
+                        ..*metadata
+                    };
+
+                    self.link(parent, component.to_string(), &metadata, Kind::Directory(BTreeMap::new()))
+                }
+            };
+        }
+
+        if !directory {
+            if let Some(&existing) = self.children(parent).get(*name) {
+                if self.nodes[existing].is_directory() {
+                    return Err(io::Error::new(io::ErrorKind::IsADirectory, format!("{path}: a directory with this name exists")));
+                }
+            }
+        }
+
+        Ok(Some((parent, name.to_string())))
+    }
+
+    /// Adds a node under `parent`, replacing an existing one.
+    fn link(&mut self, parent: usize, name: String, metadata: &Metadata, kind: Kind) -> usize {
+        self.nodes.push(Node {
+            metadata: *metadata,
+            kind,
+            serial: self.nodes.len() as u32 + 1,
+        });
+
+        let index = self.nodes.len() - 1;
+
+        self.children_mut(parent).insert(name, index);
+
+        index
+    }
+
+    fn find(&self, path: &str) -> io::Result<Option<usize>> {
+        let mut node = 0;
+
+        for component in components(path)? {
+            match self.children(node).get(component) {
+                Some(&child) => node = child,
+                None => return Ok(None),
+            }
+        }
+
+        Ok(Some(node))
+    }
+
+    fn children(&self, node: usize) -> &BTreeMap<String, usize> {
+        static EMPTY: BTreeMap<String, usize> = BTreeMap::new();
+
+        match &self.nodes[node].kind {
+            Kind::Directory(children) => children,
+            _ => &EMPTY,
+        }
+    }
+
+    fn children_mut(&mut self, node: usize) -> &mut BTreeMap<String, usize> {
+        match &mut self.nodes[node].kind {
+            Kind::Directory(children) => children,
+            _ => unreachable!("parents are always directories"),
+        }
+    }
+}
+
+/// Components of a path, `.` and empty ones are dropped, `..` is refused.
+fn components(path: &str) -> io::Result<Vec<&str>> {
+    let components: Vec<&str> = path.split('/').filter(|v| !v.is_empty() && *v != ".").collect();
+
+    if components.contains(&"..") {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{path}: '..' is not allowed in paths")));
+    }
+
+    Ok(components)
+}
+
+fn too_large() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, "the image is too large")
+}
+
+fn root_error(path: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, format!("'{path}' is the root directory"))
+}
+
+fn d_character(c: char) -> char {
+    match c.to_ascii_uppercase() {
+        c @ ('A'..='Z' | '0'..='9' | '_') => c,
+        _ => '_',
+    }
+}
+
+/// Unique ISO 9660 identifier for a name: d-characters, at most 31 characters,
+/// files get an extension and version 1.
+fn identifier(name: &str, directory: bool, taken: &mut HashSet<String>) -> Vec<u8> {
+    let (base, extension) = match name.rsplit_once('.') {
+        Some((base, extension)) if !directory && !base.is_empty() => (base, extension),
+        _ => (name, ""),
+    };
+
+    let base: String = base.chars().map(d_character).collect();
+    let extension: String = extension.chars().take(8).map(d_character).collect();
+    let limit = if directory { 31 } else { 30 - extension.len() - 1 };
+
+    let candidate = |suffix: &str| {
+        let base = &base[..base.len().min(limit - suffix.len())];
+        let base = if base.is_empty() && suffix.is_empty() { "_" } else { base };
+
+        match directory {
+            true => format!("{base}{suffix}"),
+            false => format!("{base}{suffix}.{extension}"),
+        }
+    };
+
+    let mut identifier = candidate("");
+    let mut number = 1;
+
+    while !taken.insert(identifier.clone()) {
+        identifier = candidate(&format!("~{number}"));
+        number += 1;
+    }
+
+    if !directory {
+        identifier.push_str(";1");
+    }
+
+    identifier.into_bytes()
+}
+
+/// `SL` entries of a link target, each holding at most 250 bytes of component records.
+fn symlink_entries(target: &str) -> Vec<Vec<u8>> {
+    let mut components: Vec<(u8, &[u8])> = Vec::new();
+
+    if target.starts_with('/') {
+        components.push((SL_COMPONENT_ROOT, b""));
+    }
+
+    for component in target.split('/').filter(|v| !v.is_empty()) {
+        match component {
+            "." => components.push((SL_COMPONENT_CURRENT, b"")),
+            ".." => components.push((SL_COMPONENT_PARENT, b"")),
+            _ => components.push((0, component.as_bytes())),
+        }
+    }
+
+    // Entries end inside a split component where possible: some readers don't put
+    // a separator between the last component of an entry and the first one of the next.
+    let mut bodies = vec![Vec::new()];
+    let count = components.len();
+
+    for (index, (flags, mut content)) in components.into_iter().enumerate() {
+        loop {
+            let body: &mut Vec<u8> = bodies.last_mut().unwrap();
+            let room = MAX_PIECE - body.len();
+            let whole = 2 + content.len();
+
+            let (piece, rest) = if whole + 3 <= room || (index + 1 == count && whole <= room) {
+                (content, &[][..])
+            } else if room >= 3 && content.len() >= 2 {
+                content.split_at((room - 2).min(content.len() - 1))
+            } else if whole <= room {
+                (content, &[][..])
+            } else {
+                bodies.push(Vec::new());
+                continue;
+            };
+
+            let continues = if rest.is_empty() { 0 } else { SL_COMPONENT_CONTINUE };
+
+            body.push(flags | continues);
+            body.push(piece.len() as u8);
+            body.extend_from_slice(piece);
+
+            if rest.is_empty() {
+                break;
+            }
+
+            content = rest;
+            bodies.push(Vec::new());
+        }
+    }
+
+    let count = bodies.len();
+
+    bodies
+        .into_iter()
+        .enumerate()
+        .map(|(index, body)| {
+            let mut entry = vec![b'S', b'L', (5 + body.len()) as u8, 1, u8::from(index + 1 < count)];
+
+            entry.extend(body);
+            entry
+        })
+        .collect()
+}
+
+/// Splits system use entries into the ones that stay in the record (their count)
+/// and the continuation areas holding the rest.
+fn split(entries: &[Vec<u8>], budget: usize) -> (usize, Vec<Range<usize>>) {
+    let size = |entries: &[Vec<u8>]| entries.iter().map(Vec::len).sum::<usize>();
+
+    // Entries that fit into `room` bytes
+    let fitting = |entries: &[Vec<u8>], room: usize| {
+        entries
+            .iter()
+            .scan(0, |used, entry| {
+                *used += entry.len();
+                (*used <= room).then_some(())
+            })
+            .count()
+    };
+
+    if size(entries) <= budget {
+        return (entries.len(), Vec::new());
+    }
+
+    let inline = fitting(entries, budget - CE_LENGTH);
+    let mut areas = Vec::new();
+    let mut start = inline;
+
+    while start < entries.len() {
+        let end = match size(&entries[start..]) <= DISK_SECTOR_SIZE {
+            true => entries.len(),
+            false => start + fitting(&entries[start..], DISK_SECTOR_SIZE - CE_LENGTH),
+        };
+
+        areas.push(start..end);
+        start = end;
+    }
+
+    (inline, areas)
+}
+
+/// Path table, directories in their final order.
+fn path_table(directories: &[Directory], big_endian: bool) -> Vec<u8> {
+    let mut table = Vec::new();
+
+    for directory in directories {
+        let (lba, parent) = match big_endian {
+            true => (directory.lba.to_be_bytes(), directory.parent.to_be_bytes()),
+            false => (directory.lba.to_le_bytes(), directory.parent.to_le_bytes()),
+        };
+
+        table.push(directory.identifier.len() as u8);
+        table.push(0);
+        table.extend_from_slice(&lba);
+        table.extend_from_slice(&parent);
+        table.extend_from_slice(&directory.identifier);
+
+        if directory.identifier.len() % 2 == 1 {
+            table.push(0);
+        }
+    }
+
+    table
+}
+
+fn push_both(data: &mut Vec<u8>, value: u32) {
+    data.extend_from_slice(&value.to_le_bytes());
+    data.extend_from_slice(&value.to_be_bytes());
+}
+
+/// Both-endian 16-bit value in a `u32` field of the volume descriptor
+fn both_u16(value: u16) -> u32 {
+    let [low, high] = value.to_le_bytes();
+
+    u32::from_ne_bytes([low, high, high, low])
+}
+
+/// 17-byte `YYYYMMDDHHMMSScc` + offset timestamp, all zero digits if unset.
+fn dec_datetime(time: Option<i64>) -> [u8; 17] {
+    let mut data = [b'0'; 17];
+
+    data[16] = 0;
+
+    if let Some(time) = time {
+        let (year, month, day, hour, minute, second) = crate::types::civil_time(time);
+        let text = format!("{year:04}{month:02}{day:02}{hour:02}{minute:02}{second:02}00");
+
+        data[..16].copy_from_slice(&text.as_bytes()[..16]);
+    }
+
+    data
+}
@@ -0,0 +1,127 @@
+//! Extended attribute records, recorded in the first blocks of an extent before the data.
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use zerocopy::{FromBytes, FromZeros, Immutable, IntoBytes};
+
+use crate::{block_position, types::LSB_MSB, ISODirectoryRecord, Read};
+
+/// Fixed part of an extended attribute record, followed by the application use area
+/// and the escape sequences.
+#[derive(Debug, Clone, FromBytes, Immutable, IntoBytes)]
+#[repr(C, packed(1))]
+pub struct ExtendedAttributeRecord {
+    pub owner: LSB_MSB<u16>,
+    pub group: LSB_MSB<u16>,
+    /// Permission bits, a bit set to 1 denies the access
+    pub permissions: [u8; 2],
+    pub creation_date: [u8; 17],
+    pub modification_date: [u8; 17],
+    pub expiration_date: [u8; 17],
+    pub effective_date: [u8; 17],
+    /// 0 when the structure of the records is not specified, 1 for fixed and 2 or 3 for variable length
+    pub record_format: u8,
+    /// How records are displayed: 0 with line feed and carriage return, 1 FORTRAN, 2 embedded
+    pub record_attributes: u8,
+    pub record_length: LSB_MSB<u16>,
+    pub system_identifier: [u8; 32],
+    pub system_use: [u8; 64],
+    pub version: u8,
+    pub escape_sequences_length: u8,
+    pub reserved: [u8; 64],
+    pub application_use_length: LSB_MSB<u16>,
+}
+
+impl ExtendedAttributeRecord {
+    /// Reads the fixed part of the record of `record`, `None` if it has no extended attributes.
+    pub fn read(device: &(impl Read + ?Sized), record: &ISODirectoryRecord) -> Option<Self> {
+        if record.xar_length() == 0 {
+            return None;
+        }
+
+        let mut result = Self::new_zeroed();
+
+        device.read_volume(record.volume_sequence_number(), block_position(record.lba.get() as u64)?, result.as_mut_bytes())?;
+
+        Some(result)
+    }
+
+    pub fn owner_id(&self) -> u16 {
+        self.owner.get()
+    }
+
+    pub fn group_id(&self) -> u16 {
+        self.group.get()
+    }
+
+    /// POSIX permission bits: owner, group and others, read and execute only.
+    /// The system class has no POSIX counterpart and is left out.
+    pub fn mode(&self) -> u32 {
+        let denied = u16::from_be_bytes(self.permissions);
+
+        // Read and execute bits of the owner, group and other classes, write is never granted
+        [(4, 0o400), (6, 0o100), (8, 0o040), (10, 0o010), (12, 0o004), (14, 0o001)]
+            .iter()
+            .filter(|(bit, _)| denied & (1 << bit) == 0)
+            .fold(0, |mode, (_, permission)| mode | permission)
+    }
+
+    /// Creation date as seconds since the Unix epoch
+    pub fn creation_date(&self) -> Option<i64> {
+        crate::types::dec_datetime(&self.creation_date)
+    }
+
+    pub fn modification_date(&self) -> Option<i64> {
+        crate::types::dec_datetime(&self.modification_date)
+    }
+
+    /// Date after which the file is considered obsolete
+    pub fn expiration_date(&self) -> Option<i64> {
+        crate::types::dec_datetime(&self.expiration_date)
+    }
+
+    /// Date from which the file may be used
+    pub fn effective_date(&self) -> Option<i64> {
+        crate::types::dec_datetime(&self.effective_date)
+    }
+}
+
+/// An extended attribute record with its variable parts.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone)]
+pub struct ExtendedAttributes {
+    pub record: ExtendedAttributeRecord,
+    pub application_use: Vec<u8>,
+    /// Escape sequences of the character set used by the file
+    pub escape_sequences: Vec<u8>,
+}
+
+#[cfg(feature = "alloc")]
+impl ExtendedAttributes {
+    /// Reads the whole record of `record`, `None` if it has no extended attributes.
+    pub fn read(device: &(impl Read + ?Sized), record: &ISODirectoryRecord) -> Option<Self> {
+        let header = ExtendedAttributeRecord::read(device, record)?;
+
+        let application_use_length = header.application_use_length.get() as usize;
+        let escape_sequences_length = header.escape_sequences_length as usize;
+        let mut rest = alloc::vec![0u8; application_use_length + escape_sequences_length];
+
+        let start = block_position(record.lba.get() as u64)? + size_of::<ExtendedAttributeRecord>() as u64;
+        let end = start + rest.len() as u64;
+
+        // Both must fit in the blocks reserved for the record
+        if end > block_position(record.data_lba() as u64)? {
+            return None;
+        }
+
+        device.read_volume(record.volume_sequence_number(), start, &mut rest)?;
+
+        let escape_sequences = rest.split_off(application_use_length);
+
+        Some(Self {
+            record: header,
+            application_use: rest,
+            escape_sequences,
+        })
+    }
+}
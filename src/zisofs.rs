@@ -0,0 +1,183 @@
+//! Transparent decompression of zisofs files, marked by a Rock Ridge `ZF` entry.
+//!
+//! The data starts with a header and a table of block pointers (offsets into the extent),
+//! followed by independently compressed blocks. Equal neighbouring pointers mean a block of
+//! zeros. Any range is read by decompressing only the blocks it touches.
+//!
+//! Nothing is kept between reads: every call loads the header and the pointers it needs and
+//! inflates each block it touches, so reads should cover whole blocks. The chunked copies of
+//! this crate read 1 MiB at a time, a multiple of every block size.
+//!
+//! zisofs2 (`zisofs2` feature) has 64-bit sizes and pointers and names the compressor of the
+//! blocks in its header, zlib and zstd are supported.
+
+use alloc::{vec, vec::Vec};
+
+use crate::{
+    extensions::rock_ridge::{Compression, CompressionAlgorithm},
+    Read,
+};
+
+const ZISOFS_MAGIC: [u8; 8] = [0x37, 0xE4, 0x53, 0x96, 0xC9, 0xDB, 0xD6, 0x07];
+
+#[cfg(feature = "zisofs2")]
+const ZISOFS2_MAGIC: [u8; 8] = [0xEF, 0x22, 0x55, 0xA1, 0xBC, 0x1B, 0x95, 0xA0];
+
+/// Compressors of zisofs2 blocks, zisofs always uses zlib
+const COMPRESSOR_ZLIB: u8 = 1;
+#[cfg(feature = "zisofs2")]
+const COMPRESSOR_ZSTD: u8 = 4;
+
+/// Smallest block size, 32 KiB
+const MIN_BLOCK_SIZE_LOG2: u8 = 15;
+
+/// Largest block size, 1 MiB
+const MAX_BLOCK_SIZE_LOG2: u8 = 20;
+
+/// Layout of the compressed data, from its header.
+struct Header {
+    /// Size of a block pointer, 4 or 8 bytes
+    pointer_size: usize,
+    block_size: u64,
+    real_size: u64,
+    compressor: u8,
+}
+
+/// Whether this build can decompress the data.
+pub(crate) fn is_supported(compression: &Compression) -> bool {
+    match compression.algorithm {
+        CompressionAlgorithm::Zisofs => true,
+        CompressionAlgorithm::Zisofs2 => cfg!(feature = "zisofs2"),
+        CompressionAlgorithm::Other(_) => false,
+    }
+}
+
+/// Reads uncompressed data at `offset` of a compressed extent starting at `position`.
+pub(crate) fn read<D: Read + ?Sized>(
+    device: &D,
+    position: u64,
+    stored_size: u64,
+    compression: &Compression,
+    offset: u64,
+    data: &mut [u8],
+) -> Option<()> {
+    let header = read_header(device, position, compression)?;
+
+    // `file_size` reports the size from the `ZF` entry, the data must agree with it.
+    if header.real_size != compression.real_size {
+        return None;
+    }
+
+    if offset.checked_add(data.len() as u64)? > header.real_size {
+        return None;
+    }
+
+    if data.is_empty() {
+        return Some(());
+    }
+
+    let first = offset / header.block_size;
+    let last = (offset + data.len() as u64 - 1) / header.block_size;
+
+    // Pointers to the start of every block touched and the end of the last one
+    let count = (last - first + 2) as usize;
+    let mut table = vec![0u8; count * header.pointer_size];
+
+    device.read(position.checked_add(compression.header_size as u64 + first * header.pointer_size as u64)?, &mut table)?;
+
+    let pointer = |index: usize| -> u64 {
+        let bytes = &table[index * header.pointer_size..(index + 1) * header.pointer_size];
+        let mut value = [0u8; 8];
+
+        value[..bytes.len()].copy_from_slice(bytes);
+        u64::from_le_bytes(value)
+    };
+
+    let mut compressed = Vec::new();
+    let mut block = vec![0u8; header.block_size as usize];
+    let mut written = 0;
+
+    for index in 0..count - 1 {
+        let block_start = (first + index as u64) * header.block_size;
+        let length = (header.real_size - block_start).min(header.block_size) as usize;
+        let block = &mut block[..length];
+
+        let (start, end) = (pointer(index), pointer(index + 1));
+
+        if start == end {
+            block.fill(0);
+        } else {
+            // Incompressible blocks are stored with a little overhead, anything near twice
+            // the block size is corrupt and not worth allocating for.
+            if start > end || end > stored_size || end - start > 2 * header.block_size {
+                return None;
+            }
+
+            compressed.resize((end - start) as usize, 0);
+            device.read(position + start, &mut compressed)?;
+
+            if decompress(header.compressor, &compressed, block)? != length {
+                return None;
+            }
+        }
+
+        let from = (offset.max(block_start) - block_start) as usize;
+        let to = ((offset + data.len() as u64).min(block_start + length as u64) - block_start) as usize;
+
+        data[written..written + to - from].copy_from_slice(&block[from..to]);
+        written += to - from;
+    }
+
+    Some(())
+}
+
+fn read_header<D: Read + ?Sized>(device: &D, position: u64, compression: &Compression) -> Option<Header> {
+    let mut data = [0u8; 24];
+
+    match compression.algorithm {
+        // magic, size (32), header size / 4, block size log2, reserved
+        CompressionAlgorithm::Zisofs => {
+            let data = &mut data[..16];
+
+            device.read(position, data)?;
+
+            if data[..8] != ZISOFS_MAGIC || !(MIN_BLOCK_SIZE_LOG2..=MAX_BLOCK_SIZE_LOG2).contains(&data[13]) {
+                return None;
+            }
+
+            Some(Header {
+                pointer_size: 4,
+                block_size: 1 << data[13],
+                real_size: u32::from_le_bytes(data[8..12].try_into().ok()?) as u64,
+                compressor: COMPRESSOR_ZLIB,
+            })
+        }
+        // magic, compressor, header size / 4, block size log2, reserved, size (64)
+        #[cfg(feature = "zisofs2")]
+        CompressionAlgorithm::Zisofs2 => {
+            device.read(position, &mut data)?;
+
+            if data[..8] != ZISOFS2_MAGIC || !(MIN_BLOCK_SIZE_LOG2..=MAX_BLOCK_SIZE_LOG2).contains(&data[10]) {
+                return None;
+            }
+
+            Some(Header {
+                pointer_size: 8,
+                block_size: 1 << data[10],
+                real_size: u64::from_le_bytes(data[12..20].try_into().ok()?),
+                compressor: data[8],
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Decompresses one block, returns its size.
+fn decompress(compressor: u8, input: &[u8], output: &mut [u8]) -> Option<usize> {
+    match compressor {
+        COMPRESSOR_ZLIB => miniz_oxide::inflate::decompress_slice_iter_to_slice(output, core::iter::once(input), true, false).ok(),
+        #[cfg(feature = "zisofs2")]
+        COMPRESSOR_ZSTD => ruzstd::decoding::FrameDecoder::new().decode_all(input, output).ok(),
+        _ => None,
+    }
+}
@@ -0,0 +1,119 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use std::io::Read as _;
+
+use iso9660_simple::{extensions::apple::FinderInfo, ISODirectoryEntry, ISO9660};
+
+const ASSOCIATED: u8 = 1 << 2;
+
+/// Apple system use entry with HFS Finder info.
+fn finder(signature: &[u8; 2], kind: u8, file_type: &[u8; 4], creator: &[u8; 4], flags: u16) -> Vec<u8> {
+    let mut entry = vec![signature[0], signature[1], 14, kind];
+
+    entry.extend_from_slice(file_type);
+    entry.extend_from_slice(creator);
+    entry.extend_from_slice(&flags.to_be_bytes());
+    entry
+}
+
+fn entries(iso: &ISO9660<Vec<u8>>) -> Vec<ISODirectoryEntry> {
+    iso.read_root().skip(2).collect()
+}
+
+#[test]
+fn finder_info_is_read_from_aa_and_ba_entries() {
+    let text = finder(b"AA", 2, b"TEXT", b"ttxt", 0);
+    let old = finder(b"BA", 2, b"APPL", b"MACS", 0x4000);
+    let prodos = finder(b"AA", 1, b"BIN ", b"pdos", 0);
+    let iso = common::open(common::directory(&[
+        (b"TEXT.TXT;1", 0, &text, b"text"),
+        (b"OLD.APP;1", 0, &old, b"app"),
+        (b"PRODOS.BIN;1", 0, &prodos, b"bin"),
+        (b"PLAIN.TXT;1", 0, b"", b"plain"),
+    ]));
+
+    let info: Vec<Option<FinderInfo>> = entries(&iso).iter().map(ISODirectoryEntry::finder_info).collect();
+    let expected = |file_type: &[u8; 4], creator: &[u8; 4], flags| {
+        Some(FinderInfo {
+            file_type: *file_type,
+            creator: *creator,
+            flags,
+        })
+    };
+
+    assert_eq!(info, [expected(b"TEXT", b"ttxt", 0), expected(b"APPL", b"MACS", 0x4000), None, None]);
+    assert!(info[1].unwrap().is_invisible() && !info[0].unwrap().is_invisible());
+
+    // Raw entries decode the same thing without allocating
+    let mut iter = iso.read_root();
+    let raw = std::iter::from_fn(|| iter.next_raw().map(|raw| raw.finder_info())).nth(2).unwrap();
+
+    assert_eq!(raw, info[0]);
+}
+
+#[test]
+fn associated_file_is_paired_with_the_file_after_it() {
+    let info = finder(b"AA", 2, b"APPL", b"test", 0);
+    let iso = common::open(common::directory(&[
+        (b"APP;1", ASSOCIATED, &info, b"resource fork"),
+        (b"APP;1", 0, &info, b"data fork"),
+        (b"ORPHAN;1", ASSOCIATED, b"", b"no data fork"),
+        (b"OTHER;1", 0, b"", b"other"),
+    ]));
+
+    let entries = entries(&iso);
+    let names: Vec<&str> = entries.iter().map(|entry| entry.name.as_str()).collect();
+
+    // The orphan stays listed on its own
+    assert_eq!(names, ["APP;1", "ORPHAN;1", "OTHER;1"]);
+    assert!(entries[1].resource_fork.is_none() && entries[2].resource_fork.is_none());
+
+    let app = &entries[0];
+    let mut data = [0u8; 9];
+    let mut fork = [0u8; 13];
+
+    iso.read_file(app, 0, &mut data).unwrap();
+    iso.read_resource_fork(app, 0, &mut fork).unwrap();
+
+    assert_eq!(&data, b"data fork");
+    assert_eq!(&fork, b"resource fork");
+    assert_eq!(app.resource_size(), 13);
+
+    let mut streamed = Vec::new();
+
+    iso.resource_fork(app).unwrap().read_to_end(&mut streamed).unwrap();
+    assert_eq!(streamed, b"resource fork");
+    assert!(iso.resource_fork(&entries[2]).is_none());
+}
+
+#[test]
+fn associated_file_is_paired_across_a_sector_boundary() {
+    // "." and ".." take 68 bytes, 41 fillers of 46 bytes and the 52-byte associated record
+    // fill the first sector to 2006 bytes: the data fork starts the next one.
+    let info = finder(b"AA", 2, b"APPL", b"test", 0);
+    let fillers: Vec<Vec<u8>> = (0..41).map(|number| format!("FILL{number:03}.TXT;1").into_bytes()).collect();
+    let mut files: Vec<common::File> = fillers.iter().map(|name| (&name[..], 0, &[][..], &b"filler"[..])).collect();
+
+    files.push((b"APP;1", ASSOCIATED, &info, b"resource fork"));
+    files.push((b"APP;1", 0, &info, b"data fork"));
+
+    let image = common::directory(&files);
+    let root = common::record_position(&image, b"FILL000.TXT;1") - 68;
+
+    assert_eq!(image[root + 2006], 0);
+    assert_eq!(&image[root + 2048 + 33..root + 2048 + 38], b"APP;1");
+
+    let iso = common::open(image);
+    let entries = entries(&iso);
+    let app = entries.last().unwrap();
+
+    assert_eq!(entries.len(), 42);
+    assert_eq!(app.name, "APP;1");
+
+    let mut fork = [0u8; 13];
+
+    iso.read_resource_fork(app, 0, &mut fork).unwrap();
+    assert_eq!(&fork, b"resource fork");
+}
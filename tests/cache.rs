@@ -0,0 +1,143 @@
+#![cfg(feature = "std")]
+
+use std::cell::RefCell;
+
+use iso9660_simple::{cache::CachedDevice, Read};
+
+const SECTOR: u64 = 2048;
+
+/// Device recording the position and length of every read.
+struct Counting {
+    data: Vec<u8>,
+    reads: RefCell<Vec<(u64, usize)>>,
+}
+
+impl Counting {
+    fn new() -> Self {
+        Counting {
+            data: (0..64 * SECTOR as u32).map(|n| (n % 251) as u8).collect(),
+            reads: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Reads issued since the last call, in sectors.
+    fn take(&self) -> Vec<(u64, usize)> {
+        self.reads.take().into_iter().map(|(position, length)| (position / SECTOR, length / SECTOR as usize)).collect()
+    }
+}
+
+impl Read for Counting {
+    fn read(&self, position: u64, buffer: &mut [u8]) -> Option<()> {
+        self.reads.borrow_mut().push((position, buffer.len()));
+        buffer.copy_from_slice(self.data.get(position as usize..position as usize + buffer.len())?);
+
+        Some(())
+    }
+}
+
+/// Reads `length` bytes at `position` through the cache and checks them against the device.
+fn read<const SECTORS: usize>(cache: &CachedDevice<Counting, SECTORS>, position: u64, length: usize) {
+    let mut buffer = vec![0u8; length];
+
+    cache.read(position, &mut buffer).unwrap();
+    assert!(buffer[..] == cache.get_ref().data[position as usize..position as usize + length]);
+}
+
+#[test]
+fn repeated_reads_hit_the_cache() {
+    let cache = CachedDevice::<_, 4>::new(Counting::new()).with_read_ahead(0);
+
+    read(&cache, 10, 100);
+    read(&cache, 500, 1000);
+    read(&cache, 0, 2048);
+
+    assert_eq!(cache.get_ref().take(), [(0, 1)]);
+    assert_eq!((cache.stats().hits, cache.stats().misses, cache.stats().device_reads), (2, 1, 1));
+}
+
+#[test]
+fn least_recently_used_sector_is_evicted() {
+    let cache = CachedDevice::<_, 2>::new(Counting::new()).with_read_ahead(0);
+
+    // Sectors apart from each other, so nothing is read ahead
+    for sector in [0, 2, 0, 4, 0] {
+        read(&cache, sector * SECTOR, 16);
+    }
+
+    // 2 was used less recently than 0 when 4 came in
+    assert_eq!(cache.get_ref().take(), [(0, 1), (2, 1), (4, 1)]);
+
+    read(&cache, 2 * SECTOR, 16);
+    read(&cache, 0, 16);
+    read(&cache, 4 * SECTOR, 16);
+
+    assert_eq!(cache.get_ref().take(), [(2, 1), (4, 1)]);
+}
+
+#[test]
+fn read_ahead_follows_sequential_misses_only() {
+    let cache = CachedDevice::<_, 8>::new(Counting::new()).with_read_ahead(3);
+
+    read(&cache, 5 * SECTOR, 16);
+    read(&cache, 6 * SECTOR, 16);
+
+    // The miss on 6 follows 5: 7 to 9 come with it and are hits afterwards
+    assert_eq!(cache.get_ref().take(), [(5, 1), (6, 4)]);
+    assert_eq!(cache.stats().read_ahead, 3);
+
+    for sector in 7..10 {
+        read(&cache, sector * SECTOR, 16);
+    }
+
+    assert_eq!(cache.get_ref().take(), []);
+
+    // Jumps around are read one sector at a time
+    read(&cache, 20 * SECTOR, 16);
+    read(&cache, 30 * SECTOR, 16);
+    read(&cache, 25 * SECTOR, 16);
+
+    assert_eq!(cache.get_ref().take(), [(20, 1), (30, 1), (25, 1)]);
+
+    // Read-ahead stops before a sector that is already cached
+    read(&cache, 40 * SECTOR, 16);
+    read(&cache, 38 * SECTOR, 16);
+    read(&cache, 39 * SECTOR, 16);
+
+    assert_eq!(cache.get_ref().take(), [(40, 1), (38, 1), (39, 1)]);
+    assert_eq!(cache.stats().read_ahead, 3);
+}
+
+#[test]
+fn read_spanning_cached_and_uncached_sectors() {
+    let cache = CachedDevice::<_, 8>::new(Counting::new()).with_read_ahead(0);
+
+    read(&cache, SECTOR + 100, 16);
+    cache.get_ref().take();
+    cache.reset_stats();
+
+    // From the middle of sector 0 to the middle of sector 2, sector 1 being cached
+    read(&cache, 1000, 2 * SECTOR as usize);
+
+    assert_eq!(cache.get_ref().take(), [(0, 1), (2, 1)]);
+    assert_eq!((cache.stats().hits, cache.stats().misses), (1, 2));
+
+    read(&cache, 0, 3 * SECTOR as usize);
+
+    assert_eq!(cache.get_ref().take(), []);
+
+    // Reads as large as the cache go straight to the device
+    read(&cache, 0, 8 * SECTOR as usize);
+
+    assert_eq!(cache.get_ref().take(), [(0, 8)]);
+}
+
+#[test]
+fn invalidate_drops_cached_sectors() {
+    let cache = CachedDevice::<_, 4>::new(Counting::new()).with_read_ahead(0);
+
+    read(&cache, 0, 16);
+    cache.invalidate();
+    read(&cache, 0, 16);
+
+    assert_eq!(cache.get_ref().take(), [(0, 1), (0, 1)]);
+}
@@ -0,0 +1,169 @@
+#![allow(dead_code)]
+
+use std::io::{self, Cursor};
+
+use iso9660_simple::{writer::{ImageWriter, Metadata}, ISO9660};
+
+pub const DIRECTORY: Metadata = Metadata {
+    mode: 0o755,
+    uid: 0,
+    gid: 0,
+    mtime: 0,
+};
+
+/// Builds an image in memory with the entries added by `add`.
+pub fn build(add: impl FnOnce(&mut ImageWriter<Cursor<Vec<u8>>>) -> io::Result<()>) -> Vec<u8> {
+    let mut writer = ImageWriter::new(Cursor::new(Vec::new())).unwrap();
+
+    add(&mut writer).unwrap();
+
+    writer.finish().unwrap().into_inner()
+}
+
+pub fn open(image: Vec<u8>) -> ISO9660<Vec<u8>> {
+    ISO9660::from_device(image).unwrap()
+}
+
+pub fn file(writer: &mut ImageWriter<Cursor<Vec<u8>>>, path: &str, data: &[u8]) -> io::Result<()> {
+    writer.add_file(path, &Metadata::default(), data.len() as u64, &mut &data[..])
+}
+
+/// Replaces the first occurrence of `from` in the image by `to`, both of the same length.
+pub fn patch(image: &mut [u8], from: &[u8], to: &[u8]) {
+    assert_eq!(from.len(), to.len());
+
+    let position = image.windows(from.len()).position(|window| window == from).unwrap();
+
+    image[position..position + to.len()].copy_from_slice(to);
+}
+
+/// Position of the first record whose file identifier is `identifier`.
+/// Path table entries carrying the same name are skipped.
+pub fn record_position(image: &[u8], identifier: &[u8]) -> usize {
+    let is_record = |position: usize| position >= 33 && image[position - 1] as usize == identifier.len() && image[position - 33] >= 33;
+    let position = (0..image.len() - identifier.len())
+        .find(|&position| &image[position..position + identifier.len()] == identifier && is_record(position))
+        .unwrap();
+
+    position - 33
+}
+
+/// Writes `value` at `offset` as a both-endian field: little endian, then big endian.
+pub fn set_both_endian(image: &mut [u8], offset: usize, value: &[u8]) {
+    let mut big = value.to_vec();
+    big.reverse();
+
+    image[offset..offset + value.len()].copy_from_slice(value);
+    image[offset + value.len()..offset + 2 * value.len()].copy_from_slice(&big);
+}
+
+/// Sets the data length of the record whose file identifier is `identifier`.
+pub fn set_data_length(image: &mut [u8], identifier: &[u8], length: u32) {
+    let record = record_position(image, identifier);
+
+    set_both_endian(image, record + 10, &length.to_le_bytes());
+}
+
+/// Directory record pointing at `lba`, both-endian fields filled in.
+pub fn record(lba: u32, size: u32, flags: u8, identifier: &[u8]) -> Vec<u8> {
+    let length = 33 + identifier.len() + (identifier.len() + 1) % 2;
+    let mut record = vec![length as u8, 0];
+
+    record.extend_from_slice(&lba.to_le_bytes());
+    record.extend_from_slice(&lba.to_be_bytes());
+    record.extend_from_slice(&size.to_le_bytes());
+    record.extend_from_slice(&size.to_be_bytes());
+    record.extend_from_slice(&[100, 1, 1, 0, 0, 0, 0, flags, 0, 0, 0, 1, 1, 0, identifier.len() as u8]);
+    record.extend_from_slice(identifier);
+    record.resize(length, 0);
+    record
+}
+
+/// Image with a one-directory Joliet tree holding `files` (UCS-2 identifier, data),
+/// shared by the primary volume descriptor so both trees list the same records.
+pub fn joliet(files: &[(&[u8], &[u8])]) -> Vec<u8> {
+    one_directory(&files.iter().map(|&(identifier, data)| (identifier, 0, &[][..], data)).collect::<Vec<_>>(), true)
+}
+
+/// Image with a one-directory tree holding `files` (identifier, data), without Rock Ridge entries.
+pub fn plain(files: &[(&[u8], &[u8])]) -> Vec<u8> {
+    directory(&files.iter().map(|&(identifier, data)| (identifier, 0, &[][..], data)).collect::<Vec<_>>())
+}
+
+/// A file of [`directory`]: identifier, flags, system use area and data.
+pub type File<'a> = (&'a [u8], u8, &'a [u8], &'a [u8]);
+
+/// Same as [`plain`] with flags and system use areas.
+pub fn directory(files: &[File]) -> Vec<u8> {
+    one_directory(files, false)
+}
+
+fn one_directory(files: &[File], joliet: bool) -> Vec<u8> {
+    const SECTOR: usize = 2048;
+    const ROOT: u32 = 19;
+
+    // Records never cross sector boundaries
+    let directory = |size: u32, lbas: &mut dyn Iterator<Item = u32>| {
+        let mut directory = Vec::new();
+        let records = [record(ROOT, size, 2, &[0]), record(ROOT, size, 2, &[1])]
+            .into_iter()
+            .chain(files.iter().map(|(identifier, flags, system_use, data)| {
+                let mut record = record(lbas.next().unwrap(), data.len() as u32, *flags, identifier);
+
+                record.extend_from_slice(system_use);
+                record[0] = record.len() as u8;
+                record
+            }));
+
+        for record in records {
+            if directory.len() % SECTOR + record.len() > SECTOR {
+                directory.resize(directory.len().next_multiple_of(SECTOR), 0);
+            }
+
+            directory.extend(record);
+        }
+
+        directory.resize(directory.len().next_multiple_of(SECTOR), 0);
+        directory
+    };
+
+    let size = directory(0, &mut std::iter::repeat(0)).len();
+    let mut image = vec![0u8; ROOT as usize * SECTOR + size];
+    let mut lbas = Vec::new();
+
+    for (.., data) in files {
+        lbas.push((image.len() / SECTOR) as u32);
+        image.extend_from_slice(data);
+        image.resize(image.len().next_multiple_of(SECTOR), 0);
+    }
+
+    image[ROOT as usize * SECTOR..][..size].copy_from_slice(&directory(size as u32, &mut lbas.into_iter()));
+
+    let blocks = (image.len() / SECTOR) as u32;
+    let descriptors: &[(usize, u8)] = if joliet { &[(16, 1), (17, 2), (18, 255)] } else { &[(16, 1), (17, 255)] };
+
+    for &(sector, kind) in descriptors {
+        let descriptor = &mut image[sector * SECTOR..][..SECTOR];
+
+        descriptor[0] = kind;
+        descriptor[1..7].copy_from_slice(b"CD001\x01");
+
+        if kind != 255 {
+            descriptor[80..84].copy_from_slice(&blocks.to_le_bytes());
+            descriptor[84..88].copy_from_slice(&blocks.to_be_bytes());
+            descriptor[128..132].copy_from_slice(&[0, 8, 8, 0]);
+            descriptor[156..190].copy_from_slice(&record(ROOT, size as u32, 2, &[0]));
+        }
+
+        if kind == 2 {
+            descriptor[88..91].copy_from_slice(b"%/E");
+        }
+    }
+
+    image
+}
+
+/// Joliet identifier of `name`, UCS-2 big-endian.
+pub fn ucs2(name: &str) -> Vec<u8> {
+    name.encode_utf16().flat_map(u16::to_be_bytes).collect()
+}
@@ -0,0 +1,43 @@
+#![cfg(feature = "cso")]
+
+use iso9660_simple::cso::CompressedImage;
+
+#[test]
+fn index_larger_than_the_device_is_refused() {
+    let mut header = Vec::new();
+
+    header.extend_from_slice(b"CISO");
+    header.extend_from_slice(&24u32.to_le_bytes());
+    header.extend_from_slice(&(u64::MAX >> 4).to_le_bytes());
+    header.extend_from_slice(&2048u32.to_le_bytes());
+    header.extend_from_slice(&[1, 0, 0, 0]);
+
+    assert!(CompressedImage::new(header).is_none());
+}
+
+#[test]
+fn stored_blocks_are_read() {
+    let mut image = Vec::new();
+
+    image.extend_from_slice(b"CISO");
+    image.extend_from_slice(&24u32.to_le_bytes());
+    image.extend_from_slice(&4096u64.to_le_bytes());
+    image.extend_from_slice(&2048u32.to_le_bytes());
+    image.extend_from_slice(&[1, 0, 0, 0]);
+
+    for position in [36u32, 36 + 2048, 36 + 4096] {
+        let flag = if position < 36 + 4096 { 0x8000_0000 } else { 0 };
+
+        image.extend_from_slice(&(position | flag).to_le_bytes());
+    }
+
+    image.extend(std::iter::repeat_n(0xAB, 2048));
+    image.extend(std::iter::repeat_n(0xCD, 2048));
+
+    let device = CompressedImage::new(image).unwrap();
+    let mut buffer = [0u8; 4];
+
+    iso9660_simple::Read::read(&device, 2046, &mut buffer).unwrap();
+
+    assert_eq!(buffer, [0xAB, 0xAB, 0xCD, 0xCD]);
+}
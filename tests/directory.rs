@@ -0,0 +1,75 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use std::cell::RefCell;
+
+use iso9660_simple::{Read, ISO9660};
+
+/// Device recording the position of every read.
+struct Counting {
+    data: Vec<u8>,
+    reads: RefCell<Vec<u64>>,
+}
+
+impl Read for Counting {
+    fn read(&self, position: u64, buffer: &mut [u8]) -> Option<()> {
+        self.reads.borrow_mut().push(position);
+        buffer.copy_from_slice(self.data.get(position as usize..position as usize + buffer.len())?);
+
+        Some(())
+    }
+}
+
+fn identifiers(count: usize) -> Vec<Vec<u8>> {
+    (0..count).map(|number| format!("FILE{number:03}.TXT;1").into_bytes()).collect()
+}
+
+#[test]
+fn directory_spanning_several_sectors_is_listed_in_order() {
+    // 46-byte records: 44 fit in a sector, the rest of it is padding
+    let identifiers = identifiers(120);
+    let files: Vec<(&[u8], &[u8])> = identifiers.iter().map(|identifier| (&identifier[..], &b"data"[..])).collect();
+    let iso = common::open(common::plain(&files));
+
+    let names: Vec<String> = iso.read_root().map(|entry| entry.name).collect();
+    let expected: Vec<String> = identifiers.iter().map(|identifier| String::from_utf8(identifier.clone()).unwrap()).collect();
+
+    assert_eq!(names[..2], [".", ".."]);
+    assert_eq!(names[2..], expected);
+
+    // Every listed file can be read, the records on later sectors included
+    let last = iso.read_root().last().unwrap();
+    let mut buffer = [0u8; 4];
+
+    iso.read_file(&last, 0, &mut buffer).unwrap();
+    assert_eq!(&buffer, b"data");
+}
+
+#[test]
+fn directory_is_read_one_sector_at_a_time() {
+    let identifiers = identifiers(120);
+    let files: Vec<(&[u8], &[u8])> = identifiers.iter().map(|identifier| (&identifier[..], &b"data"[..])).collect();
+    let device = Counting {
+        data: common::plain(&files),
+        reads: RefCell::new(Vec::new()),
+    };
+    let iso = ISO9660::from_device(device).unwrap();
+    let root = iso.read_root().next().unwrap().record;
+
+    iso.device().reads.take();
+
+    let mut iter = iso.read_root();
+    let mut count = 0;
+
+    while iter.next_raw().is_some() {
+        count += 1;
+    }
+
+    // 122 records over three sectors, each sector read once
+    let sectors: Vec<u64> = (0..3).map(|sector| (root.data_lba() as u64 + sector) * 2048).collect();
+
+    assert_eq!(count, 122);
+    assert_eq!(root.file_size(), 3 * 2048);
+    assert_eq!(iso.device().reads.take(), sectors);
+}
@@ -0,0 +1,247 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use std::path::Path;
+
+use iso9660_simple::{
+    disc::{nrg, DiscImage, Track, TrackMode},
+    Read, ISO9660,
+};
+
+/// Image with a couple of files to find again through the disc image.
+fn image() -> Vec<u8> {
+    common::build(|writer| {
+        common::file(writer, "readme.txt", b"read me")?;
+        common::file(writer, "data.bin", &[7; 5000])
+    })
+}
+
+/// Stores 2048-byte sectors as raw 2352-byte mode 1 sectors: sync, header, user data, EDC/ECC.
+fn raw(image: &[u8]) -> Vec<u8> {
+    let mut raw = Vec::new();
+
+    for (index, sector) in image.chunks(2048).enumerate() {
+        let address = index + 150;
+
+        raw.extend_from_slice(&[0, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0]);
+        raw.extend([address / 75 / 60, address / 75 % 60, address % 75].map(|part| (((part / 10) << 4) | (part % 10)) as u8));
+        raw.push(1);
+        raw.extend_from_slice(sector);
+        raw.resize(raw.len() + 288, 0);
+    }
+
+    raw
+}
+
+fn chunk(id: &[u8; 4], data: &[u8]) -> Vec<u8> {
+    let mut chunk = id.to_vec();
+
+    chunk.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    chunk.extend_from_slice(data);
+    chunk
+}
+
+/// `DAOX` chunk data for tracks of (mode, sector size, pregap, start, end offsets), numbered from 1.
+fn daox(tracks: &[(u8, u16, u64, u64, u64)]) -> Vec<u8> {
+    let mut data = vec![0u8; 22];
+
+    data[20] = 1;
+    data[21] = tracks.len() as u8;
+
+    for &(mode, sector_size, pregap, start, end) in tracks {
+        let mut block = vec![0u8; 18];
+
+        block[12..14].copy_from_slice(&sector_size.to_be_bytes());
+        block[14] = mode;
+
+        for offset in [pregap, start, end] {
+            block.extend_from_slice(&offset.to_be_bytes());
+        }
+
+        data.extend(block);
+    }
+
+    data
+}
+
+/// Nero image: track data, then the chunk list and a `NER5` footer pointing at it.
+fn nrg(data: &[u8], chunks: &[Vec<u8>]) -> Vec<u8> {
+    let mut image = data.to_vec();
+    let position = image.len() as u64;
+
+    chunks.iter().for_each(|chunk| image.extend_from_slice(chunk));
+    image.extend(chunk(b"END!", &[]));
+    image.extend_from_slice(b"NER5");
+    image.extend_from_slice(&position.to_be_bytes());
+    image
+}
+
+fn names<D: Read>(iso: &ISO9660<D>) -> Vec<String> {
+    let mut names: Vec<String> = iso.read_root().map(|entry| entry.name).collect();
+
+    names.sort();
+    names
+}
+
+fn assert_lists_tree<D: Read>(disc: DiscImage<D>) {
+    let iso = ISO9660::from_device(disc).unwrap();
+
+    assert_eq!(names(&iso), [".", "..", "data.bin", "readme.txt"]);
+
+    let entry = iso.read_root().find(|entry| entry.name == "readme.txt").unwrap();
+    let mut buffer = [0u8; 7];
+
+    iso.read_file(&entry, 0, &mut buffer).unwrap();
+    assert_eq!(&buffer, b"read me");
+}
+
+fn write(directory: &Path, name: &str, contents: &[u8]) -> std::path::PathBuf {
+    let path = directory.join(name);
+
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn nrg_disc_at_once_image_lists_the_tree() {
+    let iso = image();
+    let mut data = vec![0u8; 150 * 2352];
+    let start = data.len() as u64;
+
+    data.extend(raw(&iso));
+
+    // Lead-in, track 1 index 0 and 1, lead-out
+    let mut cue = Vec::new();
+
+    for (track, index, address) in [(0x00u8, 0x00u8, -150i32), (0x01, 0x00, -150), (0x01, 0x01, 0), (0xAA, 0x01, (iso.len() / 2048) as i32)] {
+        cue.extend_from_slice(&[0x41, track, index, 0]);
+        cue.extend_from_slice(&address.to_be_bytes());
+    }
+
+    let chunks = [
+        chunk(b"CUEX", &cue),
+        chunk(b"DAOX", &daox(&[(0x05, 2352, 0, start, data.len() as u64)])),
+        chunk(b"SINF", &1u32.to_be_bytes()),
+    ];
+
+    let directory = tempfile::tempdir().unwrap();
+    let path = write(directory.path(), "disc.nrg", &nrg(&data, &chunks));
+    let disc = DiscImage::open(&path).unwrap();
+
+    assert_eq!(disc.tracks().len(), 1);
+    assert_eq!((disc.tracks()[0].start, disc.tracks()[0].mode), (0, TrackMode::Mode1));
+
+    assert_lists_tree(disc);
+}
+
+#[test]
+fn nrg_address_past_the_last_sector_is_refused() {
+    // Without a cue sheet the second track follows the first and its pregap, past u32::MAX here.
+    let tracks = [(0x00, 1, 0, 0, u32::MAX as u64), (0x00, 1, 0, 10, 20)];
+    let image = nrg(&[], &[chunk(b"DAOX", &daox(&tracks))]);
+
+    assert!(nrg::parse(&image, image.len() as u64).is_none());
+}
+
+#[test]
+fn session_starting_near_the_last_address_is_refused() {
+    let track = Track {
+        number: 1,
+        session: 1,
+        mode: TrackMode::Mode1,
+        start: u32::MAX - 4,
+        length: 4,
+        position: 0,
+        sector_size: 2048,
+    };
+
+    assert!(DiscImage::new(image(), vec![track]).is_none());
+}
+
+#[test]
+fn clonecd_image_lists_the_tree() {
+    let iso = image();
+    let sectors = iso.len() / 2048;
+    let ccd = format!(
+        "[CloneCD]\nVersion=3\n[Disc]\nTocEntries=2\nSessions=1\n\
+         [Entry 0]\nSession=1\nPoint=0x01\nADR=0x01\nControl=0x04\nPLBA=0\n\
+         [Entry 1]\nSession=1\nPoint=0xa2\nADR=0x01\nControl=0x04\nPLBA={sectors}\n\
+         [TRACK 1]\nMODE=1\nINDEX 1=0\n"
+    );
+
+    let directory = tempfile::tempdir().unwrap();
+
+    write(directory.path(), "disc.img", &raw(&iso));
+
+    let path = write(directory.path(), "disc.ccd", ccd.as_bytes());
+    let disc = DiscImage::open(&path).unwrap();
+
+    assert_eq!(disc.tracks()[0].length as usize, sectors);
+
+    assert_lists_tree(disc);
+}
+
+/// Descriptor with one mode 1 track of 2048-byte sectors stored in the file named `data_file`.
+fn mds(data_file: &str, sectors: u32) -> Vec<u8> {
+    let mut mds = vec![0u8; 88];
+
+    mds[..16].copy_from_slice(b"MEDIA DESCRIPTOR");
+    mds[16] = 1;
+    mds[20..22].copy_from_slice(&1u16.to_le_bytes());
+    mds[80..84].copy_from_slice(&88u32.to_le_bytes());
+
+    // Session: start, end, number, track blocks and where they are
+    let mut session = vec![0u8; 24];
+
+    session[4..8].copy_from_slice(&sectors.to_le_bytes());
+    session[8..10].copy_from_slice(&1u16.to_le_bytes());
+    session[10] = 1;
+    session[20..24].copy_from_slice(&112u32.to_le_bytes());
+    mds.extend(session);
+
+    // Track: mode, point, sector size, start, position and footer
+    let mut track = vec![0u8; 80];
+
+    track[0] = 0xAA;
+    track[4] = 1;
+    track[16..18].copy_from_slice(&2048u16.to_le_bytes());
+    track[52..56].copy_from_slice(&192u32.to_le_bytes());
+    mds.extend(track);
+
+    // Footer: name position, narrow characters
+    mds.extend_from_slice(&200u32.to_le_bytes());
+    mds.extend_from_slice(&0u32.to_le_bytes());
+    mds.extend_from_slice(data_file.as_bytes());
+    mds.push(0);
+    mds
+}
+
+#[test]
+fn media_descriptor_image_lists_the_tree() {
+    let iso = image();
+    let directory = tempfile::tempdir().unwrap();
+
+    write(directory.path(), "track.bin", &iso);
+
+    let path = write(directory.path(), "disc.mds", &mds("track.bin", (iso.len() / 2048) as u32));
+
+    assert_lists_tree(DiscImage::open(&path).unwrap());
+}
+
+#[test]
+fn media_descriptor_data_file_must_be_a_bare_name() {
+    let iso = image();
+    let directory = tempfile::tempdir().unwrap();
+    let inner = directory.path().join("inner");
+
+    std::fs::create_dir(&inner).unwrap();
+    write(directory.path(), "outside.bin", &iso);
+
+    for name in ["../outside.bin", "..\\outside.bin", "..", "*./../outside", "/etc/hostname"] {
+        let path = write(&inner, "disc.mds", &mds(name, (iso.len() / 2048) as u32));
+        let error = DiscImage::open(&path).err().unwrap();
+
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData, "{name}");
+    }
+}
@@ -0,0 +1,280 @@
+#![cfg(all(unix, feature = "std"))]
+
+mod common;
+
+use std::{fs, os::unix::fs::PermissionsExt};
+
+use iso9660_simple::{
+    extract::{CollisionPolicy, Report, SkipReason, SymlinkPolicy},
+    writer::Metadata,
+};
+
+#[test]
+fn restrictive_modes_are_applied_after_times() {
+    let metadata = |mode| Metadata {
+        mode,
+        mtime: 1_000_000_000,
+        ..Metadata::default()
+    };
+
+    let image = common::build(|writer| {
+        writer.add_file("write-only", &metadata(0o200), 5, &mut &b"hello"[..])?;
+        writer.add_file("none", &metadata(0o000), 5, &mut &b"hello"[..])?;
+        writer.add_directory("locked", &metadata(0o311))?;
+        writer.add_file("locked/inner", &metadata(0o400), 5, &mut &b"hello"[..])
+    });
+
+    let iso = common::open(image);
+    let destination = tempfile::tempdir().unwrap();
+    let report = iso.extract(destination.path()).owner(false).run().unwrap();
+
+    assert!(report.skipped.is_empty(), "{:?}", report.skipped);
+    assert_eq!(report.files, 3);
+
+    for (path, mode) in [("write-only", 0o200), ("none", 0o000), ("locked/inner", 0o400), ("locked", 0o311)] {
+        let metadata = fs::symlink_metadata(destination.path().join(path)).unwrap();
+
+        assert_eq!(metadata.permissions().mode() & 0o7777, mode, "{path}");
+        assert_eq!(metadata.modified().unwrap(), std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000_000), "{path}");
+    }
+
+    fs::set_permissions(destination.path().join("locked"), fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+#[test]
+fn case_probe_leaves_existing_files_alone() {
+    let image = common::build(|writer| common::file(writer, "a.txt", b"a"));
+    let iso = common::open(image);
+    let destination = tempfile::tempdir().unwrap();
+
+    fs::write(destination.path().join(".iso9660-case-probe"), b"mine").unwrap();
+
+    iso.extract(destination.path()).run().unwrap();
+
+    assert_eq!(fs::read(destination.path().join(".iso9660-case-probe")).unwrap(), b"mine");
+
+    let mut names: Vec<_> = fs::read_dir(destination.path()).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+    names.sort();
+
+    assert_eq!(names, [".iso9660-case-probe", "a.txt"]);
+}
+
+/// A destination next to a directory that must stay untouched.
+struct Sandbox {
+    base: tempfile::TempDir,
+}
+
+impl Sandbox {
+    fn new() -> Self {
+        let base = tempfile::tempdir().unwrap();
+
+        fs::create_dir(base.path().join("destination")).unwrap();
+        fs::create_dir(base.path().join("outside")).unwrap();
+        fs::write(base.path().join("outside/secret"), b"secret").unwrap();
+
+        Self { base }
+    }
+
+    fn destination(&self) -> std::path::PathBuf {
+        self.base.path().join("destination")
+    }
+
+    /// Nothing but the destination was written to.
+    fn assert_contained(&self) {
+        let names = |path: &std::path::Path| {
+            let mut names: Vec<_> = fs::read_dir(path).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(names(self.base.path()), ["destination", "outside"]);
+        assert_eq!(names(&self.base.path().join("outside")), ["secret"]);
+        assert_eq!(fs::read(self.base.path().join("outside/secret")).unwrap(), b"secret");
+    }
+}
+
+fn skipped(report: &Report) -> Vec<(&str, &SkipReason)> {
+    report.skipped.iter().map(|skipped| (skipped.path.as_str(), &skipped.reason)).collect()
+}
+
+#[test]
+fn dot_dot_names_are_refused_or_sanitized() {
+    let mut image = common::build(|writer| {
+        common::file(writer, "zz", b"parent")?;
+        common::file(writer, "zzzzzzzzzzzzzzz", b"escape")?;
+        writer.add_directory("yyyyyyyyy", &common::DIRECTORY)?;
+        common::file(writer, "yyyyyyyyy/yyyyyyy", b"nested")
+    });
+
+    common::patch(&mut image, b"NM\x07\x01\0zz", b"NM\x07\x01\0..");
+    common::patch(&mut image, b"\0zzzzzzzzzzzzzzz", b"\0../../../secret");
+    common::patch(&mut image, b"\0yyyyyyyyy", b"\0../../../");
+    common::patch(&mut image, b"\0yyyyyyy", b"\0../evil");
+
+    let iso = common::open(image);
+    let sandbox = Sandbox::new();
+    let report = iso.extract(sandbox.destination()).run().unwrap();
+
+    sandbox.assert_contained();
+
+    assert!(matches!(skipped(&report)[..], [("/..", SkipReason::UnsafeName)]), "{:?}", report.skipped);
+    assert_eq!(fs::read(sandbox.destination().join(".._.._.._secret")).unwrap(), b"escape");
+    assert_eq!(fs::read(sandbox.destination().join(".._.._.._/.._evil")).unwrap(), b"nested");
+}
+
+fn link_image() -> Vec<u8> {
+    common::build(|writer| {
+        let metadata = Metadata::default();
+
+        writer.add_directory("sub", &common::DIRECTORY)?;
+        common::file(writer, "sub/file", b"inside")?;
+        writer.add_symlink("absolute", &metadata, "/outside/secret")?;
+        writer.add_symlink("up", &metadata, "../outside/secret")?;
+        writer.add_symlink("sub/deep", &metadata, "../../outside")?;
+        writer.add_symlink("tricky", &metadata, "sub/../../outside/secret")?;
+        writer.add_symlink("inside", &metadata, "sub/file")?;
+        writer.add_symlink("sub/sibling", &metadata, "../inside")
+    })
+}
+
+#[test]
+fn escaping_links_are_refused() {
+    let iso = common::open(link_image());
+    let sandbox = Sandbox::new();
+    let report = iso.extract(sandbox.destination()).run().unwrap();
+
+    sandbox.assert_contained();
+
+    let mut refused: Vec<&str> = skipped(&report)
+        .into_iter()
+        .map(|(path, reason)| {
+            assert!(matches!(reason, SkipReason::EscapingSymlink), "{path}: {reason:?}");
+            path
+        })
+        .collect();
+    refused.sort();
+
+    assert_eq!(refused, ["/absolute", "/sub/deep", "/tricky", "/up"]);
+    assert_eq!(report.symlinks, 2);
+    assert_eq!(fs::read(sandbox.destination().join("inside")).unwrap(), b"inside");
+    assert_eq!(fs::read(sandbox.destination().join("sub/sibling")).unwrap(), b"inside");
+
+    for name in ["absolute", "up", "tricky", "sub/deep"] {
+        assert!(fs::symlink_metadata(sandbox.destination().join(name)).is_err(), "{name}");
+    }
+}
+
+#[test]
+fn absolute_links_are_rewritten_into_the_destination() {
+    let iso = common::open(link_image());
+    let sandbox = Sandbox::new();
+    let report = iso.extract(sandbox.destination()).symlinks(SymlinkPolicy::Rewrite).run().unwrap();
+
+    sandbox.assert_contained();
+
+    assert_eq!(fs::read_link(sandbox.destination().join("absolute")).unwrap(), std::path::Path::new("outside/secret"));
+    assert_eq!(report.rewritten_links, [(String::from("/absolute"), String::from("outside/secret"))]);
+    assert!(fs::symlink_metadata(sandbox.destination().join("up")).is_err());
+}
+
+#[test]
+fn links_already_in_the_destination_are_not_followed() {
+    let image = common::build(|writer| {
+        common::file(writer, "file", b"new")?;
+        common::file(writer, "dir/inner", b"new")
+    });
+
+    let iso = common::open(image);
+    let sandbox = Sandbox::new();
+    let destination = sandbox.destination();
+
+    std::os::unix::fs::symlink("../outside/secret", destination.join("file")).unwrap();
+    std::os::unix::fs::symlink("../outside", destination.join("dir")).unwrap();
+
+    let report = iso.extract(&destination).run().unwrap();
+
+    sandbox.assert_contained();
+
+    assert!(report.skipped.is_empty(), "{:?}", report.skipped);
+    assert!(fs::symlink_metadata(destination.join("file")).unwrap().is_file());
+    assert!(fs::symlink_metadata(destination.join("dir")).unwrap().is_dir());
+    assert_eq!(fs::read(destination.join("file")).unwrap(), b"new");
+    assert_eq!(fs::read(destination.join("dir/inner")).unwrap(), b"new");
+}
+
+#[test]
+fn names_differing_in_case_collide_on_case_insensitive_hosts() {
+    let image = common::build(|writer| {
+        common::file(writer, "README", b"upper")?;
+        common::file(writer, "readme", b"lower")?;
+        writer.add_directory("DATA", &common::DIRECTORY)?;
+        common::file(writer, "DATA/file", b"data")?;
+        writer.add_symlink("data", &Metadata::default(), "README")
+    });
+
+    let iso = common::open(image);
+
+    let sandbox = Sandbox::new();
+    let report = iso.extract(sandbox.destination()).case_insensitive(true).run().unwrap();
+
+    sandbox.assert_contained();
+
+    let skipped = skipped(&report);
+
+    assert!(matches!(skipped[..], [("/data", SkipReason::Collision), ("/readme", SkipReason::Collision)]), "{skipped:?}");
+    assert!(fs::symlink_metadata(sandbox.destination().join("DATA")).unwrap().is_dir());
+    assert_eq!(fs::read(sandbox.destination().join("README")).unwrap(), b"upper");
+    assert!(fs::symlink_metadata(sandbox.destination().join("readme")).is_err());
+
+    let sandbox = Sandbox::new();
+    let report = iso
+        .extract(sandbox.destination())
+        .case_insensitive(true)
+        .collisions(CollisionPolicy::Rename)
+        .run()
+        .unwrap();
+
+    sandbox.assert_contained();
+
+    assert!(report.skipped.is_empty(), "{:?}", report.skipped);
+    assert_eq!(fs::read(sandbox.destination().join("readme~1")).unwrap(), b"lower");
+    assert_eq!(fs::read_link(sandbox.destination().join("data~1")).unwrap(), std::path::Path::new("README"));
+}
+
+#[test]
+fn malformed_joliet_names_are_decoded_lossily() {
+    let image = common::joliet(&[
+        (&[0xD8, 0x00, 0x00, b'a'], b"surrogate"),
+        (&[0x00, b'b', 0x00], b"odd"),
+        (&common::ucs2("../escape"), b"escape"),
+        (&common::ucs2("ok.txt"), b"ok"),
+    ]);
+
+    let iso = common::open(image);
+
+    assert!(iso.has_joliet());
+
+    // The allocating and the buffer-based decoders agree, so lookups find what listings show.
+    let mut root = iso.read_root();
+    let mut names = Vec::new();
+
+    while let Some(raw) = root.next_raw() {
+        let mut buffer = [0u8; iso9660_simple::iter::MAX_NAME_LENGTH];
+
+        assert_eq!(raw.name_into(&mut buffer), Some(&*raw.name()));
+        names.push(raw.name().into_owned());
+    }
+
+    assert_eq!(names, [".", "..", "\u{FFFD}a", "b", "../escape", "ok.txt"]);
+
+    let sandbox = Sandbox::new();
+    let report = iso.extract(sandbox.destination()).run().unwrap();
+
+    sandbox.assert_contained();
+
+    assert_eq!(fs::read(sandbox.destination().join("\u{FFFD}a")).unwrap(), b"surrogate");
+    assert_eq!(fs::read(sandbox.destination().join("b")).unwrap(), b"odd");
+    assert_eq!(fs::read(sandbox.destination().join(".._escape")).unwrap(), b"escape");
+    assert_eq!(fs::read(sandbox.destination().join("ok.txt")).unwrap(), b"ok");
+    assert!(report.skipped.is_empty(), "{:?}", report.skipped);
+}
@@ -0,0 +1,79 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use iso9660_simple::{iter::ListOptions, FileFlags, ISODirectoryEntry, ISO9660};
+
+const HIDDEN: u8 = 1 << 0;
+const ASSOCIATED: u8 = 1 << 2;
+
+/// One record of every kind the listing options care about, plus the other flags.
+fn image() -> ISO9660<Vec<u8>> {
+    common::open(common::directory(&[
+        (b"APP;1", ASSOCIATED, b"", b"fork"),
+        (b"APP;1", 0, b"", b"data"),
+        (b"HIDDEN.TXT;1", HIDDEN, b"", b"hidden"),
+        (b"ORPHAN;1", ASSOCIATED, b"", b"orphan"),
+        (b"SECRET;1", ASSOCIATED, b"", b"secret fork"),
+        (b"SECRET;1", HIDDEN, b"", b"secret"),
+        (b"VISIBLE.TXT;1", 0, b"", b"visible"),
+        (b"FORMAT;1", 1 << 3, b"", b"format"),
+        (b"PROTECTED;1", 1 << 4, b"", b"protected"),
+        (b"SPLIT;1", 1 << 7, b"", b"split"),
+    ]))
+}
+
+fn names(iso: &ISO9660<Vec<u8>>, options: ListOptions) -> Vec<String> {
+    iso.read_root().options(options).skip(2).map(|entry| entry.name).collect()
+}
+
+#[test]
+fn record_flags_are_typed() {
+    let iso = image();
+    let entries: Vec<ISODirectoryEntry> = iso.read_root().skip(2).collect();
+    let entry = |name: &str| entries.iter().find(|entry| entry.name == name).unwrap();
+
+    assert_eq!(entry("APP;1").file_flags(), FileFlags::empty());
+    assert!(entry("APP;1").resource_fork.as_ref().unwrap().file_flags().contains(FileFlags::Associated));
+    assert!(entry("HIDDEN.TXT;1").is_hidden());
+    assert!(entry("ORPHAN;1").is_associated() && !entry("ORPHAN;1").is_hidden());
+    assert!(entry("FORMAT;1").has_record_format());
+    assert!(entry("PROTECTED;1").has_protection());
+    assert!(entry("SPLIT;1").is_multi_extent());
+    assert_eq!(entry("SPLIT;1").file_flags(), FileFlags::MultiExtent);
+
+    assert!(iso.read_root().next().unwrap().file_flags() == FileFlags::Directory);
+    assert!(!entries.iter().any(|entry| entry.is_folder()));
+}
+
+#[test]
+fn listing_options_leave_out_hidden_and_associated_records() {
+    let iso = image();
+    let rest = ["VISIBLE.TXT;1", "FORMAT;1", "PROTECTED;1", "SPLIT;1"];
+    let with = |names: &[&str]| names.iter().chain(&rest).map(|name| name.to_string()).collect::<Vec<_>>();
+
+    assert_eq!(names(&iso, ListOptions::ALL), with(&["APP;1", "HIDDEN.TXT;1", "ORPHAN;1", "SECRET;1"]));
+
+    // Paired resource forks are not associated files on their own, they stay
+    assert_eq!(names(&iso, ListOptions::LINUX), with(&["APP;1", "HIDDEN.TXT;1", "SECRET;1"]));
+
+    // A hidden file takes its resource fork with it
+    assert_eq!(names(&iso, ListOptions::WINDOWS), with(&["APP;1"]));
+
+    let hidden_only = ListOptions {
+        hide_hidden: true,
+        hide_associated: false,
+    };
+
+    assert_eq!(names(&iso, hidden_only), with(&["APP;1", "ORPHAN;1"]));
+    assert_eq!(ListOptions::default(), ListOptions::ALL);
+}
+
+#[test]
+fn walk_applies_listing_options() {
+    let iso = image();
+    let paths: Vec<String> = iso.walk().list_options(ListOptions::WINDOWS).map(|item| item.path).collect();
+
+    assert_eq!(paths, ["/APP;1", "/VISIBLE.TXT;1", "/FORMAT;1", "/PROTECTED;1", "/SPLIT;1"]);
+    assert_eq!(iso.walk().count(), 8);
+}
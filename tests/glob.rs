@@ -0,0 +1,110 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use std::cell::RefCell;
+
+use iso9660_simple::{glob::Pattern, helpers::get_directory_entry_by_path, Read, ISO9660};
+
+/// Device recording the sector of every read.
+struct Counting {
+    data: Vec<u8>,
+    reads: RefCell<Vec<u64>>,
+}
+
+impl Read for Counting {
+    fn read(&self, position: u64, buffer: &mut [u8]) -> Option<()> {
+        self.reads.borrow_mut().push(position / 2048);
+        buffer.copy_from_slice(self.data.get(position as usize..position as usize + buffer.len())?);
+
+        Some(())
+    }
+}
+
+fn image() -> ISO9660<Counting> {
+    let data = common::build(|writer| {
+        for path in ["top.txt", "docs/a.txt", "docs/sub/b.txt", "docs/sub/deep/c.txt", "src/main.rs", "src/lib/x.txt", "other/skip/y.txt"] {
+            common::file(writer, path, path.as_bytes())?;
+        }
+
+        Ok(())
+    });
+
+    ISO9660::from_device(Counting {
+        data,
+        reads: RefCell::new(Vec::new()),
+    })
+    .unwrap()
+}
+
+fn glob(iso: &ISO9660<Counting>, pattern: &str) -> Vec<String> {
+    iso.glob(pattern).unwrap().map(|item| item.path).collect()
+}
+
+/// Sector holding the extent of directory `path`.
+fn extent(iso: &ISO9660<Counting>, path: &str) -> u64 {
+    get_directory_entry_by_path(iso, path).unwrap().data_lba() as u64
+}
+
+#[test]
+fn recursive_component_matches_any_number_of_directories() {
+    let iso = image();
+
+    assert_eq!(glob(&iso, "/**/*.txt"), ["/docs/a.txt", "/docs/sub/b.txt", "/docs/sub/deep/c.txt", "/other/skip/y.txt", "/src/lib/x.txt", "/top.txt"]);
+    assert_eq!(glob(&iso, "/docs/**/c.txt"), ["/docs/sub/deep/c.txt"]);
+
+    // `**` also stands for no directory at all
+    assert_eq!(glob(&iso, "/docs/**/a.txt"), ["/docs/a.txt"]);
+    assert_eq!(glob(&iso, "**/main.rs"), ["/src/main.rs"]);
+    assert_eq!(glob(&iso, "/docs/**"), ["/docs/a.txt", "/docs/sub", "/docs/sub/b.txt", "/docs/sub/deep", "/docs/sub/deep/c.txt"]);
+}
+
+#[test]
+fn directories_that_cannot_match_are_not_read() {
+    let iso = image();
+    let skipped = [extent(&iso, "/src/lib"), extent(&iso, "/other/skip"), extent(&iso, "/docs/sub")];
+
+    iso.device().reads.take();
+
+    assert_eq!(glob(&iso, "/*/sub/*.txt"), ["/docs/sub/b.txt"]);
+
+    let reads = iso.device().reads.take();
+
+    // `lib` and `skip` aren't `sub`, nothing below them can match
+    assert!(reads.contains(&skipped[2]));
+    assert!(!reads.contains(&skipped[0]) && !reads.contains(&skipped[1]), "{reads:?}");
+}
+
+#[test]
+fn literal_prefix_is_looked_up_directly() {
+    let iso = image();
+    let unrelated = [extent(&iso, "/src"), extent(&iso, "/other"), extent(&iso, "/docs/sub")];
+
+    iso.device().reads.take();
+
+    assert_eq!(glob(&iso, "/docs/*.txt"), ["/docs/a.txt"]);
+
+    let reads = iso.device().reads.take();
+
+    assert!(unrelated.iter().all(|sector| !reads.contains(sector)), "{reads:?}");
+
+    // A prefix that doesn't exist matches nothing
+    assert!(glob(&iso, "/missing/*.txt").is_empty());
+}
+
+#[test]
+fn patterns_match_whole_paths() {
+    let pattern = Pattern::new("/a/**/[b-c]?.t*").unwrap();
+
+    assert!(pattern.matches_path("/a/bx.txt"));
+    assert!(pattern.matches_path("/a/x/y/cz.t"));
+    assert!(!pattern.matches_path("/a/x/dz.txt"));
+    assert!(!pattern.matches_path("/b/bx.txt"));
+
+    assert!(pattern.could_match_below("/a/x"));
+    assert!(!pattern.could_match_below("/b"));
+
+    assert!(Pattern::new("/a/[bc").is_none());
+    assert!(Pattern::new(r"/\*").unwrap().matches_path("/*"));
+    assert!(!Pattern::new(r"/\*").unwrap().matches_path("/a"));
+}
@@ -0,0 +1,579 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use iso9660_simple::{
+    hfs::{self, HFSEntry, HFS},
+    ISO9660,
+};
+
+/// Allocation block and B-tree node size, both as small as allowed so trees get several levels
+const BLOCK: usize = 512;
+const NODE: usize = 512;
+
+const ROOT_ID: u32 = 2;
+const CATALOG_ID: u32 = 4;
+const README_ID: u32 = 16;
+const FRAGMENTED_ID: u32 = 17;
+const ICON_ID: u32 = 18;
+const FOLDER_ID: u32 = 19;
+
+/// Files in `Café`, enough for the catalog to need index nodes
+const FOLDER_FILES: u32 = 60;
+
+/// 2001-09-09 01:46:40 UTC as a Mac date
+const MODIFIED: u32 = 2_082_844_800 + 1_000_000_000;
+
+const DATA: u8 = 0x00;
+const RESOURCE: u8 = 0xFF;
+
+fn readme() -> &'static [u8] {
+    b"hello from the Mac side"
+}
+
+fn pattern(length: usize, seed: u32) -> Vec<u8> {
+    (0..length as u32).map(|n| (n.wrapping_mul(seed) % 251) as u8).collect()
+}
+
+fn fragmented_data() -> Vec<u8> {
+    pattern(20 * BLOCK - 100, 7)
+}
+
+fn fragmented_resource() -> Vec<u8> {
+    pattern(10 * BLOCK, 13)
+}
+
+/// Name bytes: UTF-16 on HFS+, Mac OS Roman on HFS.
+fn encode(name: &str, plus: bool) -> Vec<u8> {
+    if plus {
+        name.encode_utf16().flat_map(u16::to_be_bytes).collect()
+    } else {
+        name.chars().map(|c| if c == 'é' { 0x8E } else { c as u8 }).collect()
+    }
+}
+
+/// Catalog key with its length: parent and name.
+fn catalog_key(parent: u32, name: &str, plus: bool) -> Vec<u8> {
+    let name = encode(name, plus);
+
+    if plus {
+        let mut key = ((6 + name.len()) as u16).to_be_bytes().to_vec();
+
+        key.extend_from_slice(&parent.to_be_bytes());
+        key.extend_from_slice(&((name.len() / 2) as u16).to_be_bytes());
+        key.extend(name);
+        key
+    } else {
+        let mut key = vec![(6 + name.len()) as u8, 0];
+
+        key.extend_from_slice(&parent.to_be_bytes());
+        key.push(name.len() as u8);
+        key.extend(name);
+        key
+    }
+}
+
+/// Extents overflow key with its length: fork type, file and first allocation block.
+fn extents_key(fork_type: u8, id: u32, start: u32, plus: bool) -> Vec<u8> {
+    if plus {
+        let mut key = vec![0, 10, fork_type, 0];
+
+        key.extend_from_slice(&id.to_be_bytes());
+        key.extend_from_slice(&start.to_be_bytes());
+        key
+    } else {
+        let mut key = vec![7, fork_type];
+
+        key.extend_from_slice(&id.to_be_bytes());
+        key.extend_from_slice(&(start as u16).to_be_bytes());
+        key
+    }
+}
+
+/// Extent record: eight runs on HFS+, three on HFS, unused ones zeroed.
+fn extent_record(extents: &[(u32, u32)], plus: bool) -> Vec<u8> {
+    let mut record = Vec::new();
+
+    for &(start, count) in extents {
+        if plus {
+            record.extend_from_slice(&start.to_be_bytes());
+            record.extend_from_slice(&count.to_be_bytes());
+        } else {
+            record.extend_from_slice(&(start as u16).to_be_bytes());
+            record.extend_from_slice(&(count as u16).to_be_bytes());
+        }
+    }
+
+    record.resize(if plus { 64 } else { 12 }, 0);
+    record
+}
+
+/// A fork and where its allocation blocks are.
+#[derive(Default)]
+struct Fork {
+    size: u64,
+    extents: Vec<(u32, u32)>,
+}
+
+impl Fork {
+    fn capacity(plus: bool) -> usize {
+        if plus { 8 } else { 3 }
+    }
+
+    /// HFS+ fork data: size, clump size, blocks and the first extent record.
+    fn plus_data(&self) -> Vec<u8> {
+        let blocks: u32 = self.extents.iter().map(|(_, count)| count).sum();
+        let mut data = self.size.to_be_bytes().to_vec();
+
+        data.extend_from_slice(&0u32.to_be_bytes());
+        data.extend_from_slice(&blocks.to_be_bytes());
+        data.extend(extent_record(&self.extents[..self.extents.len().min(8)], true));
+        data
+    }
+
+    fn first_record(&self, plus: bool) -> Vec<u8> {
+        extent_record(&self.extents[..self.extents.len().min(Fork::capacity(plus))], plus)
+    }
+
+    /// Extents overflow records for the extents that don't fit in the first record.
+    fn overflow(&self, fork_type: u8, id: u32, plus: bool) -> Vec<(Vec<u8>, Vec<u8>)> {
+        let capacity = Fork::capacity(plus);
+        let mut start: u32 = self.extents.iter().take(capacity).map(|(_, count)| count).sum();
+        let mut records = Vec::new();
+
+        for extents in self.extents.get(capacity..).unwrap_or_default().chunks(capacity) {
+            records.push((extents_key(fork_type, id, start, plus), extent_record(extents, plus)));
+            start += extents.iter().map(|(_, count)| count).sum::<u32>();
+        }
+
+        records
+    }
+}
+
+/// Node: descriptor, records, then their offsets backwards and the free space offset.
+fn node(kind: i8, height: u8, next: u32, records: &[Vec<u8>]) -> Vec<u8> {
+    let mut node = vec![0u8; NODE];
+    let mut offset = 14;
+
+    node[0..4].copy_from_slice(&next.to_be_bytes());
+    node[8] = kind as u8;
+    node[9] = height;
+    node[10..12].copy_from_slice(&(records.len() as u16).to_be_bytes());
+
+    for (index, record) in records.iter().chain([&Vec::new()]).enumerate() {
+        node[NODE - 2 * (index + 1)..NODE - 2 * index].copy_from_slice(&(offset as u16).to_be_bytes());
+        node[offset..offset + record.len()].copy_from_slice(record);
+        offset += record.len();
+    }
+
+    assert!(offset <= NODE - 2 * (records.len() + 1));
+    node
+}
+
+/// Key and data as stored in a node, the data starting on an even offset.
+fn record(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut record = key.to_vec();
+
+    record.resize(key.len().next_multiple_of(2), 0);
+    record.extend_from_slice(data);
+    record.resize(record.len().next_multiple_of(2), 0);
+    record
+}
+
+/// B-tree file of sorted records: header node, leaf nodes, then index levels up to the root.
+fn btree(records: &[(Vec<u8>, Vec<u8>)]) -> Vec<u8> {
+    let mut nodes = vec![Vec::new()];
+    let mut level: Vec<(Vec<u8>, Vec<u8>)> = records.to_vec();
+    let mut kind = -1;
+    let mut depth = 0;
+    let mut root = 0;
+    let mut leaves = (0, 0);
+
+    while !level.is_empty() {
+        depth += 1;
+
+        // Fill nodes in order, remembering the first key of each one for the level above
+        let mut groups: Vec<Vec<Vec<u8>>> = vec![Vec::new()];
+        let mut first_keys = vec![level[0].0.clone()];
+        let mut used = 14 + 2;
+
+        for (key, data) in &level {
+            let record = record(key, data);
+
+            if used + record.len() + 2 > NODE {
+                groups.push(Vec::new());
+                first_keys.push(key.clone());
+                used = 14 + 2;
+            }
+
+            used += record.len() + 2;
+            groups.last_mut().unwrap().push(record);
+        }
+
+        let first = nodes.len() as u32;
+        let count = groups.len() as u32;
+
+        for (index, group) in groups.iter().enumerate() {
+            let next = if index as u32 + 1 < count { first + index as u32 + 1 } else { 0 };
+
+            nodes.push(node(kind, depth, next, group));
+        }
+
+        if kind == -1 {
+            leaves = (first, first + count - 1);
+        }
+
+        if count == 1 {
+            root = first;
+            break;
+        }
+
+        level = first_keys.into_iter().zip(first..).map(|(key, child)| (key, child.to_be_bytes().to_vec())).collect();
+        kind = 0;
+    }
+
+    // Header record: depth, root, leaf records, first and last leaf, node size, key length, nodes
+    let mut header = Vec::new();
+
+    header.extend_from_slice(&(depth as u16).to_be_bytes());
+    header.extend_from_slice(&root.to_be_bytes());
+    header.extend_from_slice(&(records.len() as u32).to_be_bytes());
+    header.extend_from_slice(&leaves.0.to_be_bytes());
+    header.extend_from_slice(&leaves.1.to_be_bytes());
+    header.extend_from_slice(&(NODE as u16).to_be_bytes());
+    header.extend_from_slice(&516u16.to_be_bytes());
+    header.extend_from_slice(&(nodes.len() as u32).to_be_bytes());
+    header.resize(106, 0);
+
+    nodes[0] = node(1, 0, 0, &[header, vec![0; 128], vec![0; NODE - 14 - 106 - 128 - 8]]);
+    nodes.concat()
+}
+
+/// HFS or HFS+ volume being laid out, allocation blocks handed out with a free one between
+/// allocations so that every extent stands alone.
+struct Volume {
+    plus: bool,
+    image: Vec<u8>,
+    /// Position of allocation block 0
+    base: usize,
+    next_block: u32,
+}
+
+impl Volume {
+    fn new(plus: bool) -> Self {
+        Volume {
+            plus,
+            image: vec![0; 2048],
+            base: if plus { 0 } else { 2048 },
+            next_block: if plus { 4 } else { 0 },
+        }
+    }
+
+    fn allocate(&mut self, data: &[u8]) -> (u32, u32) {
+        let count = data.len().div_ceil(BLOCK).max(1) as u32;
+        let start = self.next_block;
+        let position = self.base + start as usize * BLOCK;
+
+        self.image.resize(self.image.len().max(position + count as usize * BLOCK), 0);
+        self.image[position..position + data.len()].copy_from_slice(data);
+        self.next_block += count + 1;
+
+        (start, count)
+    }
+
+    /// Stores `data` with one extent per `piece` bytes.
+    fn fork(&mut self, data: &[u8], piece: usize) -> Fork {
+        Fork {
+            size: data.len() as u64,
+            extents: data.chunks(piece).map(|piece| self.allocate(piece)).collect(),
+        }
+    }
+
+    fn folder(&self, parent: u32, name: &str, id: u32, valence: u32) -> Vec<(Vec<u8>, Vec<u8>)> {
+        let plus = self.plus;
+        let mut folder = vec![0u8; if plus { 88 } else { 70 }];
+        let mut thread = vec![0u8; if plus { 8 } else { 14 }];
+
+        if plus {
+            folder[1] = 1;
+            folder[4..8].copy_from_slice(&valence.to_be_bytes());
+            folder[8..12].copy_from_slice(&id.to_be_bytes());
+            folder[16..20].copy_from_slice(&MODIFIED.to_be_bytes());
+            thread[1] = 3;
+            thread[4..8].copy_from_slice(&parent.to_be_bytes());
+        } else {
+            folder[0] = 1;
+            folder[4..6].copy_from_slice(&(valence as u16).to_be_bytes());
+            folder[6..10].copy_from_slice(&id.to_be_bytes());
+            folder[14..18].copy_from_slice(&MODIFIED.to_be_bytes());
+            thread[0] = 3;
+            thread[10..14].copy_from_slice(&parent.to_be_bytes());
+        }
+
+        // The thread record, keyed by the folder itself, names its parent
+        let mut name_data = catalog_key(parent, name, plus)[6..].to_vec();
+
+        if !plus {
+            name_data.resize(32, 0);
+        }
+
+        thread.extend(name_data);
+
+        vec![(catalog_key(parent, name, plus), folder), (catalog_key(id, "", plus), thread)]
+    }
+
+    fn file(&self, parent: u32, name: &str, id: u32, data: &Fork, resource: &Fork, finder: (&[u8; 4], &[u8; 4], u16)) -> (Vec<u8>, Vec<u8>) {
+        let plus = self.plus;
+        let (file_type, creator, flags) = finder;
+        let mut file = vec![0u8; if plus { 248 } else { 102 }];
+
+        if plus {
+            file[1] = 2;
+            file[8..12].copy_from_slice(&id.to_be_bytes());
+            file[16..20].copy_from_slice(&MODIFIED.to_be_bytes());
+            file[42..44].copy_from_slice(&0o100644u16.to_be_bytes());
+            file[48..52].copy_from_slice(file_type);
+            file[52..56].copy_from_slice(creator);
+            file[56..58].copy_from_slice(&flags.to_be_bytes());
+            file[88..168].copy_from_slice(&data.plus_data());
+            file[168..248].copy_from_slice(&resource.plus_data());
+        } else {
+            file[0] = 2;
+            file[4..8].copy_from_slice(file_type);
+            file[8..12].copy_from_slice(creator);
+            file[12..14].copy_from_slice(&flags.to_be_bytes());
+            file[20..24].copy_from_slice(&id.to_be_bytes());
+            file[26..30].copy_from_slice(&(data.size as u32).to_be_bytes());
+            file[36..40].copy_from_slice(&(resource.size as u32).to_be_bytes());
+            file[48..52].copy_from_slice(&MODIFIED.to_be_bytes());
+            file[74..86].copy_from_slice(&data.first_record(false));
+            file[86..98].copy_from_slice(&resource.first_record(false));
+        }
+
+        (catalog_key(parent, name, plus), file)
+    }
+}
+
+/// Volume named `Hybrid` holding `ReadMe` with a resource fork, `Fragmented` whose forks need the
+/// extents overflow file, an invisible `Icon\r` and the `Café` folder of many small files. The
+/// catalog file is fragmented too, with one extent per node.
+fn volume(plus: bool) -> Vec<u8> {
+    let mut volume = Volume::new(plus);
+    let empty = Fork::default();
+    let mut catalog = Vec::new();
+
+    let readme_data = volume.fork(readme(), BLOCK);
+    let readme_resource = volume.fork(&pattern(1500, 3), BLOCK);
+    let fragmented_data = volume.fork(&fragmented_data(), BLOCK);
+    let fragmented_resource = volume.fork(&fragmented_resource(), BLOCK);
+
+    catalog.extend(volume.folder(1, "Hybrid", ROOT_ID, 4));
+    catalog.push(volume.file(ROOT_ID, "ReadMe", README_ID, &readme_data, &readme_resource, (b"TEXT", b"ttxt", 0)));
+    catalog.push(volume.file(ROOT_ID, "Fragmented", FRAGMENTED_ID, &fragmented_data, &fragmented_resource, (b"BINA", b"????", 0)));
+    catalog.push(volume.file(ROOT_ID, "Icon\r", ICON_ID, &empty, &empty, (b"icon", b"MACS", 0x4000)));
+    catalog.extend(volume.folder(ROOT_ID, "Café", FOLDER_ID, FOLDER_FILES));
+
+    for number in 0..FOLDER_FILES {
+        let data = volume.fork(format!("file {number:02}").as_bytes(), BLOCK);
+
+        catalog.push(volume.file(FOLDER_ID, &format!("File {number:02}"), 100 + number, &data, &empty, (b"TEXT", b"ttxt", 0)));
+    }
+
+    // By parent, then name: the parent follows the key length on both, the name length differs
+    catalog.sort_by(|(a, _), (b, _)| (&a[2..6], &a[if plus { 8 } else { 7 }..]).cmp(&(&b[2..6], &b[if plus { 8 } else { 7 }..])));
+
+    let catalog = btree(&catalog);
+    let catalog = volume.fork(&catalog, NODE);
+
+    // Sorted by file, fork type and first block
+    let mut overflow = catalog.overflow(DATA, CATALOG_ID, plus);
+
+    overflow.extend(fragmented_data.overflow(DATA, FRAGMENTED_ID, plus));
+    overflow.extend(fragmented_resource.overflow(RESOURCE, FRAGMENTED_ID, plus));
+
+    let extents = btree(&overflow);
+    let extents = Fork {
+        size: extents.len() as u64,
+        extents: vec![volume.allocate(&extents)],
+    };
+
+    let mut header = vec![0u8; 512];
+
+    if plus {
+        header[0..2].copy_from_slice(b"H+");
+        header[2..4].copy_from_slice(&4u16.to_be_bytes());
+        header[40..44].copy_from_slice(&(BLOCK as u32).to_be_bytes());
+        header[44..48].copy_from_slice(&volume.next_block.to_be_bytes());
+        header[192..272].copy_from_slice(&extents.plus_data());
+        header[272..352].copy_from_slice(&catalog.plus_data());
+    } else {
+        header[0..2].copy_from_slice(b"BD");
+        header[18..20].copy_from_slice(&(volume.next_block as u16).to_be_bytes());
+        header[20..24].copy_from_slice(&(BLOCK as u32).to_be_bytes());
+        header[28..30].copy_from_slice(&4u16.to_be_bytes());
+        header[36] = 6;
+        header[37..43].copy_from_slice(b"Hybrid");
+        header[130..134].copy_from_slice(&(extents.size as u32).to_be_bytes());
+        header[134..146].copy_from_slice(&extents.first_record(false));
+        header[146..150].copy_from_slice(&(catalog.size as u32).to_be_bytes());
+        header[150..162].copy_from_slice(&catalog.first_record(false));
+    }
+
+    volume.image[1024..1536].copy_from_slice(&header);
+    volume.image.resize(volume.image.len().next_multiple_of(2048), 0);
+    volume.image
+}
+
+fn names(entries: impl Iterator<Item = HFSEntry>) -> Vec<String> {
+    entries.map(|entry| entry.name).collect()
+}
+
+fn read_file(hfs: &HFS<Vec<u8>>, path: &str) -> Vec<u8> {
+    let entry = hfs.entry_by_path(path).unwrap();
+    let mut data = vec![0u8; entry.file_size() as usize];
+
+    hfs.read_file(&entry, 0, &mut data).unwrap();
+    data
+}
+
+fn read_resource_fork(hfs: &HFS<Vec<u8>>, path: &str) -> Vec<u8> {
+    let entry = hfs.entry_by_path(path).unwrap();
+    let mut data = vec![0u8; entry.resource_size() as usize];
+
+    hfs.read_resource_fork(&entry, 0, &mut data).unwrap();
+    data
+}
+
+fn assert_volume_reads_back(hfs: &HFS<Vec<u8>>, plus: bool) {
+    assert_eq!(hfs.is_plus(), plus);
+    assert_eq!(hfs.volume_name(), "Hybrid");
+    assert_eq!(names(hfs.read_root()), ["Café", "Fragmented", "Icon\r", "ReadMe"]);
+
+    let entry = hfs.entry_by_path("/ReadMe").unwrap();
+
+    assert!(entry.is_file());
+    assert_eq!(entry.modified(), 1_000_000_000);
+    assert_eq!((&entry.finder_info().file_type, &entry.finder_info().creator), (b"TEXT", b"ttxt"));
+    assert_eq!(entry.mode(), plus.then_some(0o100644));
+    assert_eq!(read_file(hfs, "/ReadMe"), readme());
+    assert_eq!(read_resource_fork(hfs, "/ReadMe"), pattern(1500, 3));
+
+    assert!(hfs.entry_by_path("/Icon\r").unwrap().is_hidden());
+    assert!(!entry.is_hidden());
+
+    // Both forks of `Fragmented` continue in the extents overflow file
+    assert!(read_file(hfs, "/Fragmented") == fragmented_data());
+    assert!(read_resource_fork(hfs, "/Fragmented") == fragmented_resource());
+
+    let entry = hfs.entry_by_path("/Fragmented").unwrap();
+    let mut data = vec![0u8; 3 * BLOCK];
+
+    hfs.read_file(&entry, 9 * BLOCK as u64 - 10, &mut data).unwrap();
+    assert!(data == fragmented_data()[9 * BLOCK - 10..12 * BLOCK - 10]);
+    assert!(hfs.read_file(&entry, entry.file_size() - 1, &mut [0u8; 2]).is_none());
+
+    // The folder spans several leaf nodes
+    let folder = hfs.entry_by_path("/Café").unwrap();
+    let files: Vec<String> = (0..FOLDER_FILES).map(|number| format!("File {number:02}")).collect();
+
+    assert!(folder.is_folder());
+    assert_eq!(names(hfs.read_directory(&folder)), files);
+    assert_eq!(read_file(hfs, "/Café/File 37"), b"file 37");
+
+    let walked: Vec<(String, usize)> = hfs.walk().map(|entry| (entry.path, entry.depth)).collect();
+
+    assert_eq!(walked.len(), 4 + FOLDER_FILES as usize);
+    assert!(walked.contains(&("/Café/File 59".to_string(), 2)));
+}
+
+#[test]
+fn hfs_plus_volume_reads_back() {
+    let hfs = HFS::from_device(volume(true)).unwrap();
+
+    assert_volume_reads_back(&hfs, true);
+}
+
+#[test]
+fn hfs_volume_reads_back() {
+    let hfs = HFS::from_device(volume(false)).unwrap();
+
+    assert_volume_reads_back(&hfs, false);
+}
+
+#[test]
+fn missing_overflow_record_is_refused() {
+    let mut image = volume(true);
+
+    // Make the second data fork overflow record of `Fragmented` start a block early
+    let mut key = vec![0, 10, DATA, 0];
+
+    key.extend_from_slice(&FRAGMENTED_ID.to_be_bytes());
+    key.extend_from_slice(&16u32.to_be_bytes());
+
+    let mut moved = key.clone();
+
+    moved[8..12].copy_from_slice(&15u32.to_be_bytes());
+    common::patch(&mut image, &key, &moved);
+
+    let hfs = HFS::from_device(image).unwrap();
+    let entry = hfs.entry_by_path("/Fragmented").unwrap();
+
+    assert!(hfs.read_file(&entry, 0, &mut [0u8; 16]).is_none());
+    assert_eq!(read_resource_fork(&hfs, "/Fragmented"), fragmented_resource());
+}
+
+/// ISO9660 image with an Apple Partition Map in its system area, pointing at an HFS+ volume
+/// stored after the ISO9660 data.
+fn hybrid() -> Vec<u8> {
+    let mut image = common::build(|writer| common::file(writer, "readme.txt", b"read me"));
+    let volume = volume(true);
+    let start = image.len();
+
+    // Driver descriptor map with 512-byte blocks, then the map itself and the HFS partition
+    image[0..4].copy_from_slice(&[b'E', b'R', 2, 0]);
+
+    for (index, (start, length, name, partition_type)) in [
+        (1, 2, "Apple", "Apple_partition_map"),
+        (start / 512, volume.len() / 512, "Mac", "Apple_HFS"),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let entry = &mut image[(index + 1) * 512..(index + 2) * 512];
+
+        entry[0..2].copy_from_slice(b"PM");
+        entry[4..8].copy_from_slice(&2u32.to_be_bytes());
+        entry[8..12].copy_from_slice(&(start as u32).to_be_bytes());
+        entry[12..16].copy_from_slice(&(length as u32).to_be_bytes());
+        entry[16..16 + name.len()].copy_from_slice(name.as_bytes());
+        entry[48..48 + partition_type.len()].copy_from_slice(partition_type.as_bytes());
+    }
+
+    image.extend(volume);
+    image
+}
+
+#[test]
+fn apple_partition_map_marks_hybrid_discs() {
+    let image = hybrid();
+    let start = image.len() as u64 - volume(true).len() as u64;
+    let partitions = hfs::apple_partitions(&image);
+
+    assert_eq!(partitions.len(), 2);
+    assert_eq!((partitions[1].name.as_str(), partitions[1].partition_type.as_str()), ("Mac", "Apple_HFS"));
+    assert_eq!(partitions[1].start, start);
+    assert_eq!(hfs::volume_offset(&image), Some(start));
+    assert!(hfs::is_hybrid(&image));
+
+    let iso = ISO9660::from_device(image.clone()).unwrap();
+
+    assert!(iso.read_root().any(|entry| entry.name == "readme.txt"));
+
+    let hfs = HFS::from_device(image).unwrap();
+
+    assert_volume_reads_back(&hfs, true);
+
+    // Neither a plain ISO9660 image nor a bare HFS+ volume is a hybrid disc
+    assert!(!hfs::is_hybrid(&common::build(|writer| common::file(writer, "readme.txt", b"read me"))));
+    assert!(!hfs::is_hybrid(&volume(true)));
+}
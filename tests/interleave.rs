@@ -0,0 +1,105 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use std::io::{Read as _, Seek, SeekFrom};
+
+use iso9660_simple::{stream::RecordReader, ISODirectoryEntry, ISO9660};
+
+const BLOCK: usize = 2048;
+
+/// Logical contents: 5 blocks and a bit, every byte telling where it belongs.
+fn logical() -> Vec<u8> {
+    (0..5 * BLOCK + 100).map(|n| (n % 251) as u8).collect()
+}
+
+/// Image whose `FILE.BIN;1` is recorded in units of `unit` blocks followed by `gap` blocks of
+/// 0xEE, after `xar` blocks of extended attributes. Only the record fields are patched.
+fn image(unit: usize, gap: usize, xar: usize) -> ISO9660<Vec<u8>> {
+    let logical = logical();
+    let mut extent = vec![0u8; xar * BLOCK];
+
+    for chunk in logical.chunks(unit * BLOCK) {
+        extent.extend_from_slice(chunk);
+        extent.resize(extent.len().next_multiple_of(BLOCK) + gap * BLOCK, 0xEE);
+    }
+
+    let mut image = common::plain(&[(b"FILE.BIN;1", &extent)]);
+    let record = common::record_position(&image, b"FILE.BIN;1");
+
+    image[record + 1] = xar as u8;
+    image[record + 26] = unit as u8;
+    image[record + 27] = gap as u8;
+    common::set_data_length(&mut image, b"FILE.BIN;1", logical.len() as u32);
+
+    common::open(image)
+}
+
+fn file(iso: &ISO9660<Vec<u8>>) -> ISODirectoryEntry {
+    iso.read_root().find(|entry| entry.name == "FILE.BIN;1").unwrap()
+}
+
+fn read(iso: &ISO9660<Vec<u8>>, offset: usize, length: usize) -> Option<Vec<u8>> {
+    let mut data = vec![0u8; length];
+
+    iso.read_file(&file(iso), offset as u64, &mut data)?;
+
+    Some(data)
+}
+
+#[test]
+fn interleaving_is_exposed_on_entries() {
+    let iso = image(2, 1, 0);
+    let entry = file(&iso);
+
+    assert!(entry.is_interleaved());
+    assert_eq!((entry.unit_size(), entry.interleave_gap_size()), (2, 1));
+    assert_eq!(entry.file_size(), logical().len() as u64);
+
+    // Without a gap the data is contiguous whatever the unit size
+    assert!(!file(&image(2, 0, 0)).is_interleaved());
+}
+
+#[test]
+fn interleaved_file_reads_back_without_gaps() {
+    let logical = logical();
+
+    for (unit, gap) in [(1, 1), (2, 1), (1, 3), (3, 2)] {
+        let iso = image(unit, gap, 0);
+
+        assert_eq!(read(&iso, 0, logical.len()).unwrap(), logical, "{unit} {gap}");
+
+        // Reads starting inside a unit and crossing one or more gaps
+        for (offset, length) in [(100, 10), (BLOCK - 10, 20), (3000, 5000), (4 * BLOCK + 7, BLOCK + 93)] {
+            assert_eq!(read(&iso, offset, length).unwrap(), logical[offset..offset + length], "{unit} {gap} {offset}");
+        }
+
+        assert!(read(&iso, logical.len() - 10, 11).is_none());
+    }
+}
+
+#[test]
+fn interleaved_file_after_an_extended_attribute_record() {
+    let iso = image(2, 1, 1);
+    let entry = file(&iso);
+
+    assert_eq!(entry.data_lba(), entry.lsb_position() + 1);
+    assert_eq!(read(&iso, 0, logical().len()).unwrap(), logical());
+}
+
+#[test]
+fn interleaved_file_streams_and_extracts() {
+    let iso = image(2, 1, 0);
+    let logical = logical();
+    let mut reader = RecordReader::new(&iso, file(&iso).record);
+    let mut data = Vec::new();
+
+    reader.seek(SeekFrom::Start(BLOCK as u64 + 1)).unwrap();
+    reader.read_to_end(&mut data).unwrap();
+    assert_eq!(data, logical[BLOCK + 1..]);
+
+    let directory = tempfile::tempdir().unwrap();
+
+    iso.extract(directory.path()).owner(false).run().unwrap();
+    assert_eq!(std::fs::read(directory.path().join("FILE.BIN")).unwrap(), logical);
+}
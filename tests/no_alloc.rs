@@ -0,0 +1,101 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use iso9660_simple::{
+    helpers::get_directory_record_by_path,
+    iter::{DirectoryIter, RawDirectoryEntry, MAX_NAME_LENGTH},
+    normalize::NameOptions,
+    Read, ISO9660,
+};
+
+fn image(long_name: &str) -> ISO9660<Vec<u8>> {
+    common::open(common::build(|writer| {
+        common::file(writer, "readme.txt", b"read me")?;
+        common::file(writer, &format!("dir/{long_name}"), b"long")?;
+        writer.add_symlink("link", &common::DIRECTORY, "dir/target")
+    }))
+}
+
+/// Runs `check` on the raw entry named `name`, decoded into a buffer of `MAX_NAME_LENGTH` bytes.
+fn with_entry<D: Read>(mut entries: DirectoryIter<'_, D>, name: &str, check: impl FnOnce(&RawDirectoryEntry<'_>)) {
+    let mut buffer = [0u8; MAX_NAME_LENGTH];
+
+    while let Some(raw) = entries.next_raw() {
+        if raw.name_into(&mut buffer) == Some(name) {
+            return check(&raw);
+        }
+    }
+
+    panic!("{name} not found");
+}
+
+#[test]
+fn names_are_decoded_into_caller_buffers() {
+    let iso = image("x");
+
+    with_entry(iso.read_root(), "readme.txt", |raw| {
+        let mut buffer = [0u8; 10];
+
+        assert_eq!(raw.name_into(&mut buffer), Some("readme.txt"));
+        assert_eq!(raw.identifier_into(&mut buffer), Some("README.TXT;1"));
+        assert_eq!(raw.version(), Some(1));
+
+        // The Rock Ridge name doesn't fit: no name rather than the identifier
+        assert_eq!(raw.name_into(&mut [0u8; 9]), None);
+    });
+}
+
+#[test]
+fn longest_rock_ridge_names_fit_the_name_buffer() {
+    let long_name = "n".repeat(255);
+    let iso = image(&long_name);
+    let dir = get_directory_record_by_path(&iso, "/dir", &NameOptions::EXACT).unwrap();
+
+    with_entry(iso.read_directory_record(&dir), &long_name, |raw| {
+        assert_eq!(raw.name_into(&mut [0u8; 254]), None);
+    });
+
+    let record = get_directory_record_by_path(&iso, &format!("/dir/{long_name}"), &NameOptions::EXACT).unwrap();
+
+    assert_eq!(record.file_size(), 4);
+}
+
+#[test]
+fn symbolic_link_targets_are_decoded_into_caller_buffers() {
+    let iso = image("x");
+
+    with_entry(iso.read_root(), "link", |raw| {
+        assert_eq!(raw.symlink_target_into(&mut [0u8; 10]), Some("dir/target"));
+        assert_eq!(raw.symlink_target_into(&mut [0u8; 9]), None);
+    });
+
+    with_entry(iso.read_root(), "readme.txt", |raw| {
+        assert_eq!(raw.symlink_target_into(&mut [0u8; MAX_NAME_LENGTH]), None);
+    });
+}
+
+#[test]
+fn joliet_names_are_decoded_into_caller_buffers() {
+    let name = common::ucs2("Résumé.txt");
+    let iso = common::open(common::joliet(&[(&name, b"cv")]));
+
+    with_entry(iso.read_root(), "Résumé.txt", |raw| {
+        // Two characters take two bytes in UTF-8
+        assert_eq!(raw.name_into(&mut [0u8; 12]), Some("Résumé.txt"));
+        assert_eq!(raw.name_into(&mut [0u8; 11]), None);
+    });
+}
+
+#[test]
+fn records_are_looked_up_without_allocating() {
+    let iso = image("x");
+
+    let exact = get_directory_record_by_path(&iso, "/dir/x", &NameOptions::EXACT).unwrap();
+    let lenient = get_directory_record_by_path(&iso, "DIR/X", &NameOptions::LENIENT).unwrap();
+
+    assert_eq!(exact.data_lba(), lenient.data_lba());
+    assert_eq!(get_directory_record_by_path(&iso, "/", &NameOptions::EXACT).unwrap().data_lba(), iso.root_entry().data_lba());
+    assert!(get_directory_record_by_path(&iso, "/readme.txt/x", &NameOptions::EXACT).is_none());
+    assert!(get_directory_record_by_path(&iso, "/DIR/X", &NameOptions::EXACT).is_none());
+}
@@ -0,0 +1,78 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use iso9660_simple::{
+    helpers::{get_directory_entry_by_path, get_directory_entry_by_path_with, get_directory_record_by_path},
+    normalize::{split_version, NameOptions},
+    ISO9660,
+};
+
+/// Three versions of `README.TXT`, the highest one in the middle, and a name without an extension.
+fn image() -> ISO9660<Vec<u8>> {
+    common::open(common::plain(&[
+        (b"README.TXT;1", b"first"),
+        (b"README.TXT;3", b"third"),
+        (b"README.TXT;2", b"second"),
+        (b"MAKEFILE.;1", b"all:"),
+    ]))
+}
+
+fn contents(iso: &ISO9660<Vec<u8>>, path: &str, options: &NameOptions) -> Option<Vec<u8>> {
+    let entry = get_directory_entry_by_path_with(iso, path, options)?;
+    let mut data = vec![0u8; entry.file_size() as usize];
+
+    iso.read_file(&entry, 0, &mut data)?;
+
+    Some(data)
+}
+
+#[test]
+fn lenient_lookup_finds_the_highest_version() {
+    let iso = image();
+
+    assert_eq!(contents(&iso, "/readme.txt", &NameOptions::LENIENT).unwrap(), b"third");
+    assert_eq!(contents(&iso, "/README.TXT", &NameOptions::LENIENT).unwrap(), b"third");
+
+    let record = get_directory_record_by_path(&iso, "/Readme.Txt", &NameOptions::LENIENT).unwrap();
+
+    assert_eq!(record.file_size(), 5);
+}
+
+#[test]
+fn lenient_lookup_keeps_a_requested_version() {
+    let iso = image();
+
+    assert_eq!(contents(&iso, "/README.TXT;2", &NameOptions::LENIENT).unwrap(), b"second");
+    assert_eq!(contents(&iso, "/readme.txt;1", &NameOptions::LENIENT).unwrap(), b"first");
+    assert!(contents(&iso, "/readme.txt;4", &NameOptions::LENIENT).is_none());
+}
+
+#[test]
+fn lenient_lookup_drops_the_trailing_dot() {
+    let iso = image();
+
+    assert_eq!(contents(&iso, "/makefile", &NameOptions::LENIENT).unwrap(), b"all:");
+    assert!(contents(&iso, "/makefile", &NameOptions::EXACT).is_none());
+}
+
+#[test]
+fn exact_lookup_compares_identifiers_as_recorded() {
+    let iso = image();
+
+    assert!(get_directory_entry_by_path(&iso, "/readme.txt").is_none());
+    assert!(get_directory_entry_by_path(&iso, "/README.TXT").is_none());
+    assert_eq!(contents(&iso, "/README.TXT;3", &NameOptions::EXACT).unwrap(), b"third");
+}
+
+#[test]
+fn version_suffixes_are_split_off() {
+    assert_eq!(split_version("README.TXT;12"), ("README.TXT", Some(12)));
+    assert_eq!(split_version("README.TXT"), ("README.TXT", None));
+    assert_eq!(split_version("README.TXT;"), ("README.TXT;", None));
+    assert_eq!(split_version("A;B"), ("A;B", None));
+
+    assert_eq!(NameOptions::LENIENT.normalize("MAKEFILE.;1"), "makefile");
+    assert!(NameOptions::LENIENT.matches("Makefile", "MAKEFILE.;1"));
+    assert!(NameOptions::LENIENT.matches(".", "."));
+}
@@ -0,0 +1,113 @@
+#![cfg(all(unix, feature = "parallel"))]
+
+mod common;
+
+use std::{collections::BTreeMap, fs, path::Path};
+
+use iso9660_simple::{progress::Monitor, ISO9660};
+use sha2::{Digest, Sha256};
+
+/// Files smaller and larger than a parallel chunk, one of exactly a chunk, and an empty one.
+fn image() -> Vec<u8> {
+    let pattern = |length: usize, seed: u32| (0..length as u32).map(|n| (n.wrapping_mul(seed) % 251) as u8).collect::<Vec<u8>>();
+
+    common::build(|writer| {
+        common::file(writer, "big.bin", &pattern((9 << 20) + 123, 7))?;
+        common::file(writer, "empty", b"")?;
+        common::file(writer, "dir/sub/chunk.bin", &pattern(4 << 20, 11))?;
+
+        for number in 0..20 {
+            common::file(writer, &format!("dir/small{number}.txt"), format!("small file {number}").as_bytes())?;
+        }
+
+        Ok(())
+    })
+}
+
+/// Every path under `root` with the contents of files, `None` for directories.
+fn tree(root: &Path) -> BTreeMap<String, Option<Vec<u8>>> {
+    let mut entries = BTreeMap::new();
+    let mut pending = vec![root.to_path_buf()];
+
+    while let Some(directory) = pending.pop() {
+        for entry in fs::read_dir(&directory).unwrap() {
+            let path = entry.unwrap().path();
+            let name = path.strip_prefix(root).unwrap().to_string_lossy().into_owned();
+
+            if fs::symlink_metadata(&path).unwrap().is_dir() {
+                pending.push(path);
+                entries.insert(name, None);
+            } else {
+                entries.insert(name, Some(fs::read(&path).unwrap()));
+            }
+        }
+    }
+
+    entries
+}
+
+#[test]
+fn parallel_extraction_matches_sequential_extraction() {
+    let iso = common::open(image());
+    let sequential = tempfile::tempdir().unwrap();
+    let parallel = tempfile::tempdir().unwrap();
+
+    let report = iso.extract(sequential.path()).owner(false).run().unwrap();
+    let parallel_report = iso.extract(parallel.path()).owner(false).run_parallel().unwrap();
+
+    assert!(parallel_report.skipped.is_empty(), "{:?}", parallel_report.skipped);
+    assert_eq!((parallel_report.files, parallel_report.directories), (report.files, report.directories));
+    assert_eq!(parallel_report.files, 23);
+
+    let expected = tree(sequential.path());
+
+    assert!(tree(parallel.path()) == expected);
+    assert_eq!(expected["big.bin"].as_ref().unwrap().len(), (9 << 20) + 123);
+}
+
+#[test]
+fn parallel_extraction_replaces_links_in_the_destination() {
+    let iso = common::open(image());
+    let sandbox = tempfile::tempdir().unwrap();
+    let destination = sandbox.path().join("destination");
+    let outside = sandbox.path().join("outside");
+
+    fs::create_dir(&destination).unwrap();
+    fs::write(&outside, b"keep me").unwrap();
+    std::os::unix::fs::symlink(&outside, destination.join("big.bin")).unwrap();
+
+    iso.extract(&destination).owner(false).run_parallel().unwrap();
+
+    assert_eq!(fs::read(&outside).unwrap(), b"keep me");
+    assert!(fs::symlink_metadata(destination.join("big.bin")).unwrap().is_file());
+    assert_eq!(fs::read(destination.join("big.bin")).unwrap().len(), (9 << 20) + 123);
+}
+
+fn sequential_hash(iso: &ISO9660<Vec<u8>>, path: &str) -> Vec<u8> {
+    let entry = iso.walk().find(|item| item.path == path).unwrap().entry;
+    let mut data = vec![0u8; entry.file_size() as usize];
+
+    iso.read_file(&entry, 0, &mut data).unwrap();
+
+    Sha256::digest(&data).to_vec()
+}
+
+#[test]
+fn parallel_hashes_match_sequential_hashes() {
+    let iso = common::open(image());
+    let hashes = iso.hash_files::<Sha256>();
+    let paths: Vec<String> = iso.walk().filter(|item| item.entry.is_file()).map(|item| item.path).collect();
+
+    // Same files, in walk order
+    assert_eq!(hashes.iter().map(|hash| hash.path.clone()).collect::<Vec<_>>(), paths);
+
+    for hash in &hashes {
+        assert_eq!(hash.hash.unwrap().to_vec(), sequential_hash(&iso, &hash.path), "{}", hash.path);
+    }
+
+    // A reversed selection keeps its order
+    let items: Vec<_> = iso.walk().filter(|item| item.entry.is_file()).collect::<Vec<_>>().into_iter().rev().collect();
+    let reversed = iso.hash_entries::<Sha256>(items, &Monitor::NONE).unwrap();
+
+    assert!(reversed.iter().rev().map(|hash| hash.hash).eq(hashes.iter().map(|hash| hash.hash)));
+}
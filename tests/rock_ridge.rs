@@ -0,0 +1,67 @@
+use iso9660_simple::extensions::rock_ridge;
+
+#[test]
+fn zero_length_entry_ends_the_area() {
+    let mut area = b"ZF\0\x01".to_vec();
+    area.extend_from_slice(&[0u8; 32]);
+
+    assert_eq!(rock_ridge::parse(&area).count(), 0);
+    assert_eq!(rock_ridge::posix_attributes(&area), None);
+    assert_eq!(rock_ridge::compression(&area), None);
+}
+
+#[test]
+fn entry_longer_than_the_area_ends_it() {
+    let mut area = b"NM\x06\x01\0a".to_vec();
+    area.extend_from_slice(b"ZF\x10\x01pz");
+
+    assert_eq!(rock_ridge::parse(&area).count(), 1);
+    assert_eq!(rock_ridge::name_into(&area, &mut [0u8; 16]), Some("a"));
+    assert_eq!(rock_ridge::compression(&area), None);
+}
+
+#[test]
+fn zero_length_continuation_ends_the_area() {
+    let mut area = b"CE\0\x01".to_vec();
+    area.extend_from_slice(&[0u8; 24]);
+
+    assert_eq!(rock_ridge::parse(&area).count(), 0);
+    assert_eq!(rock_ridge::continuation(&area), None);
+}
+
+#[test]
+fn name_split_over_several_entries_is_joined() {
+    let mut area = b"NM\x08\x01\x01abc".to_vec();
+    area.extend_from_slice(b"PX\x24\x01");
+    area.extend_from_slice(&[0u8; 32]);
+    area.extend_from_slice(b"NM\x07\x01\0de");
+
+    assert_eq!(rock_ridge::name_into(&area, &mut [0u8; 16]), Some("abcde"));
+    assert_eq!(rock_ridge::name_into(&area, &mut [0u8; 4]), None);
+}
+
+#[test]
+fn truncated_symlink_entry_is_not_a_link() {
+    let mut buffer = [0u8; 16];
+
+    assert_eq!(rock_ridge::symlink_target_into(b"SL\x09", &mut buffer), None);
+    assert_eq!(rock_ridge::symlink_target_into(b"SL\x04\x01", &mut buffer), None);
+    assert_eq!(rock_ridge::symlink_target_into(b"SL\x09\x01\0\0\x04ab", &mut buffer), None);
+}
+
+#[test]
+fn zisofs2_size_is_64_bits() {
+    let mut area = b"ZF\x10\x01PZ\x06\x11".to_vec();
+    area.extend_from_slice(&(5u64 << 30).to_le_bytes());
+
+    let compression = rock_ridge::compression(&area).unwrap();
+
+    assert_eq!(compression.algorithm, rock_ridge::CompressionAlgorithm::Zisofs2);
+    assert_eq!(compression.real_size, 5 << 30);
+
+    let mut area = b"ZF\x10\x01pz\x04\x0f".to_vec();
+    area.extend_from_slice(&1234u32.to_le_bytes());
+    area.extend_from_slice(&1234u32.to_be_bytes());
+
+    assert_eq!(rock_ridge::compression(&area).unwrap().real_size, 1234);
+}
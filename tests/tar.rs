@@ -0,0 +1,204 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use std::io::Cursor;
+
+use iso9660_simple::writer::ImageWriter;
+
+/// Name, type flag, link target and data of every member of a tar stream, pax paths applied.
+fn read_tar(archive: &[u8]) -> Vec<(String, u8, String, Vec<u8>)> {
+    let text = |field: &[u8]| String::from_utf8_lossy(&field[..field.iter().position(|&b| b == 0).unwrap_or(field.len())]).into_owned();
+    let mut members = Vec::new();
+    let mut pax: Vec<(String, String)> = Vec::new();
+    let mut position = 0;
+
+    while archive[position] != 0 {
+        let header = &archive[position..position + 512];
+        let size = u64::from_str_radix(text(&header[124..136]).trim(), 8).unwrap() as usize;
+        let data = archive[position + 512..position + 512 + size].to_vec();
+
+        position += 512 + size.next_multiple_of(512);
+
+        if header[156] == b'x' {
+            // "<length> <key>=<value>\n" records
+            for record in String::from_utf8(data).unwrap().lines() {
+                let (key, value) = record.split_once(' ').unwrap().1.split_once('=').unwrap();
+
+                pax.push((key.to_string(), value.to_string()));
+            }
+
+            continue;
+        }
+
+        let value = |key: &str, default: String| pax.iter().find(|(found, _)| found == key).map_or(default, |(_, value)| value.clone());
+        let prefix = text(&header[345..500]);
+        let name = if prefix.is_empty() { text(&header[..100]) } else { format!("{prefix}/{}", text(&header[..100])) };
+
+        members.push((value("path", name), header[156], value("linkpath", text(&header[157..257])), data));
+        pax.clear();
+    }
+
+    members
+}
+
+/// Name, type flag and link target of every member of a tar stream.
+fn members(archive: &[u8]) -> Vec<(String, u8, String)> {
+    read_tar(archive).into_iter().map(|(name, kind, link, _)| (name, kind, link)).collect()
+}
+
+/// Appends a ustar member. `size` replaces the octal size field, base-256 ones included.
+fn member(archive: &mut Vec<u8>, name: &str, kind: u8, link: &str, data: &[u8], size: Option<[u8; 12]>) {
+    let mut header = [0u8; 512];
+
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    header[100..108].copy_from_slice(b"0000644\0");
+    header[108..116].copy_from_slice(b"0001750\0");
+    header[116..124].copy_from_slice(b"0001750\0");
+    header[124..136].copy_from_slice(&size.unwrap_or_else(|| format!("{:011o}\0", data.len()).into_bytes().try_into().unwrap()));
+    header[136..148].copy_from_slice(b"14000000000\0");
+    header[156] = kind;
+    header[157..157 + link.len()].copy_from_slice(link.as_bytes());
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    header[148..156].fill(b' ');
+
+    let checksum: u32 = header.iter().map(|&b| b as u32).sum();
+
+    header[148..156].copy_from_slice(format!("{checksum:06o}\0 ").as_bytes());
+
+    archive.extend_from_slice(&header);
+    archive.extend_from_slice(data);
+    archive.resize(archive.len().next_multiple_of(512), 0);
+}
+
+/// Appends a pax extended header applying to the next member.
+fn pax(archive: &mut Vec<u8>, records: &[(&str, &str)]) {
+    let mut data = String::new();
+
+    for (key, value) in records {
+        let body = format!(" {key}={value}\n");
+        let mut length = body.len() + 1;
+
+        // The length counts its own digits
+        while format!("{length}{body}").len() != length {
+            length += 1;
+        }
+
+        data.push_str(&format!("{length}{body}"));
+    }
+
+    member(archive, "PaxHeaders/member", b'x', "", data.as_bytes(), None);
+}
+
+fn end(archive: &mut Vec<u8>) {
+    archive.resize(archive.len() + 1024, 0);
+}
+
+fn append(archive: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut writer = ImageWriter::new(Cursor::new(Vec::new()))?;
+
+    writer.append_tar(&mut &archive[..])?;
+
+    Ok(writer.finish()?.into_inner())
+}
+
+/// Base-256 size field holding `value` in its last 8 bytes, `high` in the 3 bytes before.
+fn base256(high: [u8; 3], value: u64) -> [u8; 12] {
+    let mut field = [0u8; 12];
+
+    field[0] = 0x80;
+    field[1..4].copy_from_slice(&high);
+    field[4..].copy_from_slice(&value.to_be_bytes());
+    field
+}
+
+#[test]
+fn only_entries_sharing_volume_start_and_size_become_hard_links() {
+    let mut image = common::build(|writer| {
+        common::file(writer, "data", b"0123456789")?;
+        writer.add_hardlink("link", "data")?;
+        writer.add_hardlink("shorter", "data")?;
+        common::file(writer, "empty", b"")?;
+        common::file(writer, "empty2", b"")
+    });
+
+    common::set_data_length(&mut image, b"SHORTER.;1", 4);
+
+    let iso = common::open(image);
+    let mut archive = Vec::new();
+
+    iso.tar().write_to(&mut archive).unwrap();
+
+    let members = members(&archive);
+    let member = |name: &str| members.iter().find(|member| member.0 == name).unwrap();
+
+    assert_eq!(member("data").1, b'0');
+    assert_eq!(*member("link"), (String::from("link"), b'1', String::from("data")));
+    assert_eq!(member("shorter").1, b'0');
+    assert_eq!(member("empty").1, b'0');
+    assert_eq!(member("empty2").1, b'0');
+}
+
+#[test]
+fn tar_round_trips_through_an_image() {
+    let long_name = format!("dir/{}.txt", "long-name-".repeat(15));
+    let data: Vec<u8> = (0..3000u32).map(|n| (n % 251) as u8).collect();
+    let mut archive = Vec::new();
+
+    member(&mut archive, "dir/", b'5', "", b"", None);
+    pax(&mut archive, &[("path", &long_name)]);
+    member(&mut archive, "dir/truncated", b'0', "", b"long name contents", None);
+    member(&mut archive, "dir/data.bin", b'0', "", &data, Some(base256([0; 3], data.len() as u64)));
+    member(&mut archive, "dir/hard", b'1', "dir/data.bin", b"", None);
+    member(&mut archive, "dir/sym", b'2', "data.bin", b"", None);
+    end(&mut archive);
+
+    let iso = common::open(append(&archive).unwrap());
+    let mut exported = Vec::new();
+
+    iso.tar().write_to(&mut exported).unwrap();
+
+    let mut members = read_tar(&exported);
+
+    members.sort();
+
+    assert_eq!(
+        members,
+        [
+            (String::from("dir/"), b'5', String::new(), Vec::new()),
+            (String::from("dir/data.bin"), b'0', String::new(), data),
+            (String::from("dir/hard"), b'1', String::from("dir/data.bin"), Vec::new()),
+            (long_name, b'0', String::new(), b"long name contents".to_vec()),
+            (String::from("dir/sym"), b'2', String::from("data.bin"), Vec::new()),
+        ]
+    );
+}
+
+#[test]
+fn sparse_files_are_refused() {
+    let mut gnu = Vec::new();
+
+    member(&mut gnu, "sparse", b'S', "", b"", None);
+    end(&mut gnu);
+
+    let mut pax_sparse = Vec::new();
+
+    pax(&mut pax_sparse, &[("GNU.sparse.size", "1048576")]);
+    member(&mut pax_sparse, "sparse", b'0', "", b"", None);
+    end(&mut pax_sparse);
+
+    for archive in [gnu, pax_sparse] {
+        assert_eq!(append(&archive).unwrap_err().kind(), std::io::ErrorKind::Unsupported);
+    }
+}
+
+#[test]
+fn base256_numbers_over_64_bits_are_refused() {
+    let mut archive = Vec::new();
+
+    member(&mut archive, "huge", b'0', "", b"", Some(base256([0, 0, 1], 0)));
+    end(&mut archive);
+
+    assert_eq!(append(&archive).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+}
@@ -0,0 +1,112 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use std::{
+    collections::BTreeMap,
+    io::{Cursor, Read as _, Seek, SeekFrom},
+};
+
+use iso9660_simple::{
+    cache::CachedDevice,
+    io::{Locked, ReadMut},
+    Read, ISO9660,
+};
+
+/// Stateful device that has to seek before every read.
+struct Seeking(Cursor<Vec<u8>>);
+
+impl ReadMut for Seeking {
+    fn read(&mut self, position: u64, buffer: &mut [u8]) -> Option<()> {
+        self.0.seek(SeekFrom::Start(position)).ok()?;
+        self.0.read_exact(buffer).ok()
+    }
+}
+
+fn image() -> Vec<u8> {
+    common::build(|writer| {
+        for number in 0..16 {
+            let data: Vec<u8> = (0..1000 + number * 700).map(|n| (n * (number + 1) % 251) as u8).collect();
+
+            common::file(writer, &format!("dir{}/file{number}.bin", number % 4), &data)?;
+        }
+
+        Ok(())
+    })
+}
+
+/// Every file of the image with its contents.
+fn contents<D: Read>(iso: &ISO9660<D>) -> BTreeMap<String, Vec<u8>> {
+    iso.walk()
+        .filter(|item| item.entry.is_file())
+        .map(|item| {
+            let mut data = vec![0u8; item.entry.file_size() as usize];
+
+            iso.read_file(&item.entry, 0, &mut data).unwrap();
+
+            (item.path, data)
+        })
+        .collect()
+}
+
+/// Reads the whole image from several threads at once, all of them must see the same thing.
+fn read_from_threads<D: Read + Sync>(iso: &ISO9660<D>) {
+    let expected = contents(&common::open(image()));
+
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..8).map(|_| scope.spawn(|| contents(iso))).collect();
+
+        for worker in workers {
+            assert!(worker.join().unwrap() == expected);
+        }
+    });
+}
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn images_are_send_and_sync_with_their_device() {
+    assert_send_sync::<ISO9660<Vec<u8>>>();
+    assert_send_sync::<ISO9660<&[u8]>>();
+    assert_send_sync::<ISO9660<CachedDevice<Vec<u8>, 8>>>();
+    assert_send_sync::<ISO9660<Locked<Seeking>>>();
+}
+
+#[test]
+fn image_is_shared_between_threads() {
+    read_from_threads(&common::open(image()));
+}
+
+#[test]
+fn cached_image_is_shared_between_threads() {
+    let iso = ISO9660::from_device(CachedDevice::<_, 8>::new(image())).unwrap();
+
+    read_from_threads(&iso);
+
+    let stats = iso.device().stats();
+
+    assert!(stats.hits > 0 && stats.misses > 0);
+}
+
+#[test]
+fn locked_device_is_shared_between_threads() {
+    let iso = ISO9660::from_device(Locked::new(Seeking(Cursor::new(image())))).unwrap();
+
+    read_from_threads(&iso);
+}
+
+#[test]
+fn directories_are_listed_side_by_side() {
+    let iso = common::open(image());
+    let mut first = iso.read_root();
+    let mut second = iso.read_root();
+    let mut names = Vec::new();
+
+    // Both iterators borrow the image at the same time
+    while let (Some(a), Some(b)) = (first.next(), second.next()) {
+        assert_eq!(a.name, b.name);
+        names.push(a.name);
+    }
+
+    assert_eq!(names, [".", "..", "dir0", "dir1", "dir2", "dir3"]);
+}
@@ -0,0 +1,439 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use iso9660_simple::{descriptors::DescriptorType, udf::UDF, ISO9660};
+
+const BLOCK: usize = 2048;
+
+/// First block of the partition
+const PARTITION_START: u32 = 300;
+
+/// Partition blocks holding the metadata file, which is metadata partition block 0 onwards
+const METADATA_START: u32 = 10;
+
+/// Partition blocks of the two extents of `file.txt`
+const DATA_BLOCKS: [u32; 2] = [30, 40];
+
+/// Where the sparing table moves the packet holding the second extent of `file.txt`
+const SPARE_SECTOR: u32 = 600;
+const PACKET_LENGTH: u32 = 16;
+
+/// Partition references: the physical or sparable map, then the metadata map
+const PHYSICAL: u16 = 0;
+const METADATA: u16 = 1;
+
+struct Options {
+    /// ISO9660 descriptors around the volume recognition sequence, and an ISO9660 tree
+    bridge: bool,
+    /// Sparable partition whose sparing table moves the second extent of `file.txt`
+    sparable: bool,
+}
+
+fn contents() -> Vec<u8> {
+    (0..3000u32).map(|n| (n * 7 % 251) as u8).collect()
+}
+
+fn block(image: &mut Vec<u8>, number: u32) -> &mut [u8] {
+    let start = number as usize * BLOCK;
+
+    if image.len() < start + BLOCK {
+        image.resize(start + BLOCK, 0);
+    }
+
+    &mut image[start..start + BLOCK]
+}
+
+fn partition_block(image: &mut Vec<u8>, location: (u16, u32)) -> &mut [u8] {
+    match location {
+        (PHYSICAL, number) => block(image, PARTITION_START + number),
+        (_, number) => block(image, PARTITION_START + METADATA_START + number),
+    }
+}
+
+/// Writes a descriptor tag with a valid checksum.
+fn tag(data: &mut [u8], id: u16, location: u32) {
+    data[0..2].copy_from_slice(&id.to_le_bytes());
+    data[2..4].copy_from_slice(&3u16.to_le_bytes());
+    data[12..16].copy_from_slice(&location.to_le_bytes());
+    data[4] = (0..16).filter(|index| *index != 4).fold(0u8, |sum, index| sum.wrapping_add(data[index]));
+}
+
+fn put(data: &mut [u8], offset: usize, bytes: &[u8]) {
+    data[offset..offset + bytes.len()].copy_from_slice(bytes);
+}
+
+fn long_ad(length: u32, (partition, block): (u16, u32)) -> Vec<u8> {
+    [&length.to_le_bytes()[..], &block.to_le_bytes(), &partition.to_le_bytes(), &[0; 6]].concat()
+}
+
+fn short_ad(length: u32, block: u32) -> Vec<u8> {
+    [length.to_le_bytes(), block.to_le_bytes()].concat()
+}
+
+/// OSTA compressed Unicode with 8-bit characters
+fn cs0(name: &str) -> Vec<u8> {
+    [&[8], name.as_bytes()].concat()
+}
+
+/// File entry, or extended file entry when `extended`, with `descriptors` of type `kind`.
+struct Node<'a> {
+    file_type: u8,
+    permissions: u32,
+    uid: u32,
+    size: u64,
+    modified: Option<(u16, u8, u8)>,
+    kind: u16,
+    descriptors: &'a [u8],
+    extended: bool,
+}
+
+fn node(image: &mut Vec<u8>, location: (u16, u32), node: Node) {
+    let data = partition_block(image, location);
+    let (base, modified) = if node.extended { (208, 92) } else { (168, 84) };
+
+    put(data, 20, &4u16.to_le_bytes());
+    data[27] = node.file_type;
+    put(data, 34, &node.kind.to_le_bytes());
+    put(data, 36, &node.uid.to_le_bytes());
+    put(data, 40, &u32::MAX.to_le_bytes());
+    put(data, 44, &node.permissions.to_le_bytes());
+    put(data, 48, &1u16.to_le_bytes());
+    put(data, 56, &node.size.to_le_bytes());
+
+    if let Some((year, month, day)) = node.modified {
+        // Local time at UTC+1
+        put(data, modified, &(1 << 12 | 60u16).to_le_bytes());
+        put(data, modified + 2, &year.to_le_bytes());
+        put(data, modified + 4, &[month, day, 12, 0, 0]);
+    }
+
+    put(data, base + 4, &(node.descriptors.len() as u32).to_le_bytes());
+    put(data, base + 8, node.descriptors);
+    tag(data, if node.extended { 266 } else { 261 }, location.1);
+}
+
+/// File identifier descriptors of a directory.
+fn directory(entries: &[(u8, &str, (u16, u32))]) -> Vec<u8> {
+    let mut data = Vec::new();
+
+    for (characteristics, name, icb) in entries {
+        let identifier = if name.is_empty() { Vec::new() } else { cs0(name) };
+        let mut descriptor = vec![0u8; 38];
+
+        descriptor[18] = *characteristics;
+        descriptor[19] = identifier.len() as u8;
+        put(&mut descriptor, 20, &long_ad(BLOCK as u32, *icb));
+        descriptor.extend(identifier);
+        descriptor.resize(descriptor.len().next_multiple_of(4), 0);
+        tag(&mut descriptor, 257, 0);
+
+        data.extend(descriptor);
+    }
+
+    data
+}
+
+/// Symbolic link path components: root, then `..` or names.
+fn path_components(target: &str) -> Vec<u8> {
+    let mut data = Vec::new();
+
+    if target.starts_with('/') {
+        data.extend([1, 0, 0, 0]);
+    }
+
+    for component in target.split('/').filter(|component| !component.is_empty()) {
+        match component {
+            ".." => data.extend([3, 0, 0, 0]),
+            name => data.extend([&[5, name.len() as u8 + 1, 0, 0], &cs0(name)[..]].concat()),
+        }
+    }
+
+    data
+}
+
+/// UDF 2.50 volume with a metadata partition:
+///
+/// ```text
+/// /file.txt   3000 bytes in two extents, extended file entry
+/// /dir/
+/// /link       -> /dir/../file.txt, embedded in its file entry
+/// /relative   -> ../file.txt
+/// ```
+fn udf_image(options: Options) -> Vec<u8> {
+    let mut image = Vec::new();
+    let vrs: &[&[u8; 5]] = match options.bridge {
+        true => &[b"CD001", b"BEA01", b"NSR02", b"TEA01", b"CD001", b"CD001"],
+        false => &[b"BEA01", b"NSR03", b"TEA01"],
+    };
+
+    for (index, id) in vrs.iter().enumerate() {
+        let data = block(&mut image, 16 + index as u32);
+
+        put(data, 1, *id);
+        data[6] = 1;
+    }
+
+    // Anchor, then the volume descriptor sequence: partition, logical volume, terminator
+    let data = block(&mut image, 256);
+    put(data, 16, &(4 * BLOCK as u32).to_le_bytes());
+    put(data, 20, &257u32.to_le_bytes());
+    tag(data, 2, 256);
+
+    let data = block(&mut image, 257);
+    put(data, 22, &7u16.to_le_bytes());
+    put(data, 188, &PARTITION_START.to_le_bytes());
+    put(data, 192, &200u32.to_le_bytes());
+    tag(data, 5, 257);
+
+    let mut maps = match options.sparable {
+        true => {
+            let mut map = vec![0u8; 64];
+
+            map[..2].copy_from_slice(&[2, 64]);
+            put(&mut map, 5, b"*UDF Sparable Partition");
+            put(&mut map, 36, &1u16.to_le_bytes());
+            put(&mut map, 38, &7u16.to_le_bytes());
+            put(&mut map, 40, &(PACKET_LENGTH as u16).to_le_bytes());
+            map[42] = 1;
+            // The recorded table size is far larger than the table, only its entry count counts.
+            put(&mut map, 44, &0xFFFF_FFF0u32.to_le_bytes());
+            put(&mut map, 48, &500u32.to_le_bytes());
+            map
+        }
+        false => [&[1, 6, 1, 0][..], &7u16.to_le_bytes()].concat(),
+    };
+
+    let mut metadata_map = vec![0u8; 64];
+    metadata_map[..2].copy_from_slice(&[2, 64]);
+    put(&mut metadata_map, 5, b"*UDF Metadata Partition");
+    put(&mut metadata_map, 36, &1u16.to_le_bytes());
+    put(&mut metadata_map, 38, &7u16.to_le_bytes());
+    put(&mut metadata_map, 40, &0u32.to_le_bytes());
+    put(&mut metadata_map, 44, &1u32.to_le_bytes());
+    maps.extend(metadata_map);
+
+    let data = block(&mut image, 258);
+    put(data, 84, &cs0("UDF VOLUME"));
+    data[211] = 11;
+    put(data, 212, &(BLOCK as u32).to_le_bytes());
+    put(data, 217, b"*OSTA UDF Compliant");
+    put(data, 240, &0x0250u16.to_le_bytes());
+    put(data, 248, &long_ad(BLOCK as u32, (METADATA, 0)));
+    put(data, 264, &(maps.len() as u32).to_le_bytes());
+    put(data, 268, &2u32.to_le_bytes());
+    put(data, 440, &maps);
+    tag(data, 6, 258);
+
+    tag(block(&mut image, 259), 8, 259);
+
+    // Metadata file, its mirror is left unreadable
+    node(&mut image, (PHYSICAL, 0), Node {
+        file_type: 250,
+        permissions: 0,
+        uid: 0,
+        size: 8 * BLOCK as u64,
+        modified: None,
+        kind: 0,
+        descriptors: &short_ad(8 * BLOCK as u32, METADATA_START),
+        extended: false,
+    });
+
+    // File set descriptor and the root directory
+    let data = partition_block(&mut image, (METADATA, 0));
+    put(data, 400, &long_ad(BLOCK as u32, (METADATA, 1)));
+    tag(data, 256, 0);
+
+    let root = directory(&[
+        (8, "", (METADATA, 1)),
+        (0, "file.txt", (METADATA, 3)),
+        (2, "dir", (METADATA, 4)),
+        (0, "link", (METADATA, 6)),
+        (4, "deleted", (METADATA, 3)),
+        (1, "relative", (METADATA, 7)),
+    ]);
+
+    put(partition_block(&mut image, (METADATA, 2)), 0, &root);
+    node(&mut image, (METADATA, 1), Node {
+        file_type: 4,
+        permissions: 0o7777,
+        uid: 0,
+        size: root.len() as u64,
+        modified: None,
+        kind: 0,
+        descriptors: &short_ad(root.len() as u32, 2),
+        extended: false,
+    });
+
+    let contents = contents();
+    let extents = [long_ad(BLOCK as u32, (PHYSICAL, DATA_BLOCKS[0])), long_ad(3000 - BLOCK as u32, (PHYSICAL, DATA_BLOCKS[1]))].concat();
+
+    put(partition_block(&mut image, (PHYSICAL, DATA_BLOCKS[0])), 0, &contents[..BLOCK]);
+    put(partition_block(&mut image, (PHYSICAL, DATA_BLOCKS[1])), 0, &contents[BLOCK..]);
+
+    // Owner read and write, group and others read
+    node(&mut image, (METADATA, 3), Node {
+        file_type: 5,
+        permissions: 6 << 10 | 4 << 5 | 4,
+        uid: 1000,
+        size: 3000,
+        modified: Some((2024, 2, 29)),
+        kind: 1,
+        descriptors: &extents,
+        extended: true,
+    });
+
+    let dir = directory(&[(8, "", (METADATA, 1))]);
+
+    put(partition_block(&mut image, (METADATA, 5)), 0, &dir);
+    node(&mut image, (METADATA, 4), Node {
+        file_type: 4,
+        permissions: 0o7777,
+        uid: 0,
+        size: dir.len() as u64,
+        modified: None,
+        kind: 0,
+        descriptors: &short_ad(dir.len() as u32, 5),
+        extended: false,
+    });
+
+    for (location, target) in [(6, "/dir/../file.txt"), (7, "../file.txt")] {
+        let components = path_components(target);
+
+        node(&mut image, (METADATA, location), Node {
+            file_type: 12,
+            permissions: 0o7777,
+            uid: 0,
+            size: components.len() as u64,
+            modified: None,
+            kind: 3,
+            descriptors: &components,
+            extended: false,
+        });
+    }
+
+    if options.sparable {
+        // The packet holding the second extent moves, what is left in place is garbage.
+        let packet = DATA_BLOCKS[1] - DATA_BLOCKS[1] % PACKET_LENGTH;
+        let within = (DATA_BLOCKS[1] - packet) as usize * BLOCK;
+        let moved = partition_block(&mut image, (PHYSICAL, DATA_BLOCKS[1])).to_vec();
+
+        partition_block(&mut image, (PHYSICAL, DATA_BLOCKS[1])).fill(0xEE);
+
+        let mut spare = vec![0u8; PACKET_LENGTH as usize * BLOCK];
+        spare[within..within + BLOCK].copy_from_slice(&moved);
+
+        for (index, chunk) in spare.chunks(BLOCK).enumerate() {
+            put(block(&mut image, SPARE_SECTOR + index as u32), 0, chunk);
+        }
+
+        let data = block(&mut image, 500);
+        put(data, 17, b"*UDF Sparing Table");
+        put(data, 48, &2u16.to_le_bytes());
+        put(data, 56, &[packet.to_le_bytes(), SPARE_SECTOR.to_le_bytes()].concat());
+        put(data, 64, &[0xFFFF_FFFFu32.to_le_bytes(), 0xFFFF_FFFFu32.to_le_bytes()].concat());
+    }
+
+    if options.bridge {
+        // Primary and Joliet trees share one file record pointing at the UDF data.
+        let file = PARTITION_START + DATA_BLOCKS[0];
+        let data = |identifier: &[u8]| {
+            [common::record(22, BLOCK as u32, 2, &[0]), common::record(22, BLOCK as u32, 2, &[1]), common::record(file, BLOCK as u32, 0, identifier)].concat()
+        };
+
+        put(block(&mut image, 22), 0, &data(b"FILE.TXT;1"));
+        put(block(&mut image, 23), 0, &data(&common::ucs2("file.txt")));
+
+        for (sector, kind, root) in [(16, 1u8, 22u32), (20, 2, 23), (21, 255, 0)] {
+            let data = block(&mut image, sector);
+
+            data[0] = kind;
+
+            if kind != 255 {
+                put(data, 128, &[0, 8, 8, 0]);
+                put(data, 156, &common::record(root, BLOCK as u32, 2, &[0]));
+            }
+
+            if kind == 2 {
+                put(data, 88, b"%/E");
+            }
+        }
+    }
+
+    image
+}
+
+#[test]
+fn metadata_partition_volume_reads_back() {
+    let udf = UDF::from_device(udf_image(Options { bridge: false, sparable: false })).unwrap();
+
+    assert_eq!(udf.volume_id(), "UDF VOLUME");
+    assert_eq!(udf.revision(), 0x0250);
+    assert_eq!(udf.block_size(), 2048);
+
+    let names: Vec<_> = udf.read_root().map(|entry| entry.name).collect();
+
+    assert_eq!(names, ["file.txt", "dir", "link", "relative"]);
+
+    let file = udf.entry_by_path("/file.txt").unwrap();
+    let mut data = vec![0u8; file.file_size() as usize];
+
+    udf.read_file(&file, 0, &mut data).unwrap();
+
+    assert_eq!(data, contents());
+    assert_eq!(file.mode(), 0o100644);
+    assert_eq!((file.uid(), file.gid()), (Some(1000), None));
+    assert_eq!(file.modified(), 1_709_208_000 - 3600);
+
+    let mut data = [0u8; 100];
+
+    udf.read_file(&file, 2000, &mut data).unwrap();
+    assert_eq!(data[..], contents()[2000..2100]);
+    assert!(udf.read_file(&file, 2950, &mut data).is_none());
+
+    assert!(udf.entry_by_path("/dir").unwrap().is_folder());
+    assert!(udf.entry_by_path("/relative").unwrap().is_hidden());
+    assert_eq!(udf.symlink_target(&udf.entry_by_path("/link").unwrap()).as_deref(), Some("/dir/../file.txt"));
+    assert_eq!(udf.symlink_target(&udf.entry_by_path("/relative").unwrap()).as_deref(), Some("../file.txt"));
+    assert_eq!(udf.symlink_target(&file), None);
+
+    let paths: Vec<_> = udf.walk().map(|entry| (entry.path, entry.depth)).collect();
+
+    assert_eq!(paths, [("/file.txt", 1), ("/dir", 1), ("/link", 1), ("/relative", 1)].map(|(path, depth)| (String::from(path), depth)));
+}
+
+#[test]
+fn sparing_table_remaps_packets() {
+    let udf = UDF::from_device(udf_image(Options { bridge: false, sparable: true })).unwrap();
+    let file = udf.entry_by_path("/file.txt").unwrap();
+    let mut data = vec![0u8; file.file_size() as usize];
+
+    udf.read_file(&file, 0, &mut data).unwrap();
+
+    assert_eq!(data, contents());
+}
+
+#[test]
+fn bridge_disc_has_both_trees() {
+    let image = udf_image(Options { bridge: true, sparable: false });
+
+    // Descriptors after the volume recognition sequence are still found.
+    let types: Vec<_> = iso9660_simple::iter::DescriptorIterator::new(&image).map(|descriptor| descriptor.desc_type).collect();
+
+    assert_eq!(types, [DescriptorType::PrimaryVolume, DescriptorType::SupplementaryVolume]);
+
+    let iso = ISO9660::from_device(&image).unwrap();
+
+    assert!(iso.has_joliet());
+
+    let file = iso.read_root().find(|entry| entry.name == "file.txt").unwrap();
+    let mut data = vec![0u8; file.file_size() as usize];
+
+    iso.read_file(&file, 0, &mut data).unwrap();
+
+    assert_eq!(data, contents()[..BLOCK]);
+
+    let udf = UDF::from_device(&image).unwrap();
+
+    assert_eq!(udf.entry_by_path("/file.txt").unwrap().file_size(), 3000);
+}
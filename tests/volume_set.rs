@@ -0,0 +1,77 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use iso9660_simple::{cache::CachedDevice, helpers::get_directory_entry_by_path, io::Region, volume_set::VolumeSet, Read, ISO9660};
+
+/// Position of the volume set size in the primary volume descriptor, the sequence number follows
+const SET_SIZE: usize = 16 * 2048 + 120;
+
+/// Two volumes of a set. The tree of the second one lists `first.txt`, recorded on the first.
+fn volumes() -> [Vec<u8>; 2] {
+    let mut first = common::build(|writer| common::file(writer, "first.txt", b"on volume one"));
+    let mut second = common::build(|writer| {
+        common::file(writer, "first.txt", b"placeholder!!")?;
+        common::file(writer, "second.txt", b"on volume two")
+    });
+
+    // The writer records everything on volume 1, move the root directory of the second to volume 2.
+    let root = 16 * 2048 + 156;
+    let mut record = u32::from_le_bytes(second[root + 2..root + 6].try_into().unwrap()) as usize * 2048;
+
+    common::set_both_endian(&mut second, root + 28, &2u16.to_le_bytes());
+
+    while second[record] != 0 {
+        common::set_both_endian(&mut second, record + 28, &2u16.to_le_bytes());
+        record += second[record] as usize;
+    }
+
+    let lba = common::record_position(&first, b"FIRST.TXT;1") + 2;
+    let lba = first[lba..lba + 4].to_vec();
+    let record = common::record_position(&second, b"FIRST.TXT;1");
+
+    common::set_both_endian(&mut second, record + 2, &lba);
+    common::set_both_endian(&mut second, record + 28, &1u16.to_le_bytes());
+
+    for (image, sequence) in [(&mut first, 1u16), (&mut second, 2)] {
+        common::set_both_endian(image, SET_SIZE, &2u16.to_le_bytes());
+        common::set_both_endian(image, SET_SIZE + 4, &sequence.to_le_bytes());
+    }
+
+    [first, second]
+}
+
+fn assert_files_resolve<D: Read>(iso: &ISO9660<D>) {
+    for (path, volume, data) in [("/first.txt", 1, b"on volume one"), ("/second.txt", 2, b"on volume two")] {
+        let entry = get_directory_entry_by_path(iso, path).unwrap();
+        let mut buffer = [0u8; 13];
+
+        assert_eq!(entry.volume_sequence_number(), volume);
+
+        iso.read_file(&entry, 0, &mut buffer).unwrap();
+        assert_eq!(&buffer, data, "{path}");
+    }
+}
+
+#[test]
+fn extents_are_read_from_their_volume() {
+    let [first, second] = volumes();
+    let set = VolumeSet::new([second, first]).unwrap();
+
+    assert!(set.is_complete());
+
+    assert_files_resolve(&ISO9660::from_device(&set).unwrap());
+}
+
+#[test]
+fn wrappers_pass_the_volume_on() {
+    let [first, second] = volumes();
+    let size = second.len() as u64;
+    let set = VolumeSet::new([first, second]).unwrap();
+
+    let cached: CachedDevice<_, 16> = CachedDevice::new(&set);
+
+    assert_files_resolve(&ISO9660::from_device(&cached).unwrap());
+    assert_files_resolve(&ISO9660::from_device(Region::new(&set, 0, size)).unwrap());
+    assert_files_resolve(&ISO9660::from_device(Region::new(&cached, 0, size)).unwrap());
+}
@@ -0,0 +1,100 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use iso9660_simple::{walk::WalkOrder, ISO9660};
+
+/// `a/b/c/deep.txt` next to `top.txt`.
+fn nested() -> ISO9660<Vec<u8>> {
+    common::open(common::build(|writer| {
+        common::file(writer, "top.txt", b"top")?;
+        common::file(writer, "a/b/c/deep.txt", b"deep")
+    }))
+}
+
+fn paths(walk: impl Iterator<Item = iso9660_simple::walk::WalkEntry>) -> Vec<String> {
+    walk.map(|item| item.path).collect()
+}
+
+#[test]
+fn walk_orders_and_depths() {
+    let iso = nested();
+
+    assert_eq!(paths(iso.walk()), ["/a", "/a/b", "/a/b/c", "/a/b/c/deep.txt", "/top.txt"]);
+    assert_eq!(paths(iso.walk().order(WalkOrder::PostOrder)), ["/a/b/c/deep.txt", "/a/b/c", "/a/b", "/a", "/top.txt"]);
+
+    let depths: Vec<usize> = iso.walk().map(|item| item.depth).collect();
+
+    assert_eq!(depths, [1, 2, 3, 4, 1]);
+}
+
+#[test]
+fn walk_stops_at_the_maximum_depth() {
+    let iso = nested();
+
+    assert_eq!(paths(iso.walk().max_depth(1)), ["/a", "/top.txt"]);
+    assert_eq!(paths(iso.walk().max_depth(2)), ["/a", "/a/b", "/top.txt"]);
+    assert_eq!(paths(iso.walk().max_depth(3).order(WalkOrder::PostOrder)), ["/a/b/c", "/a/b", "/a", "/top.txt"]);
+}
+
+#[test]
+fn linked_directory_loop_is_not_entered() {
+    let iso = common::open(common::build(|writer| {
+        common::file(writer, "a/file.txt", b"file")?;
+        writer.add_symlink("a/loop", &common::DIRECTORY, "..")
+    }));
+
+    let items: Vec<_> = iso.walk().follow_symlinks(true).collect();
+    let loop_link = items.iter().find(|item| item.path == "/a/loop").unwrap();
+
+    // The link resolves to the root, which is on the current path
+    assert_eq!(paths(items.clone().into_iter()), ["/a", "/a/file.txt", "/a/loop"]);
+    assert!(loop_link.entry.is_folder());
+    assert_eq!(loop_link.symlink_target.as_deref(), Some(".."));
+
+    // Without following, the link is just a link
+    assert!(iso.walk().all(|item| item.path != "/a/loop" || !item.entry.is_folder()));
+}
+
+#[test]
+fn crafted_directory_loop_is_not_entered() {
+    let mut image = common::build(|writer| {
+        writer.add_directory("outer", &common::DIRECTORY)?;
+        common::file(writer, "outer/inner/file.txt", b"file")
+    });
+
+    // `inner` points back at the extent of `outer`
+    let outer = common::record_position(&image, b"OUTER");
+    let inner = common::record_position(&image, b"INNER");
+    let lba: [u8; 4] = image[outer + 2..outer + 6].try_into().unwrap();
+
+    common::set_both_endian(&mut image, inner + 2, &lba);
+
+    let iso = common::open(image);
+
+    assert_eq!(paths(iso.walk()), ["/outer", "/outer/inner"]);
+}
+
+#[test]
+fn symbolic_link_chains_are_followed_up_to_forty_hops() {
+    // l00 -> l01 -> ... -> l41 -> dir
+    let iso = common::open(common::build(|writer| {
+        common::file(writer, "dir/file.txt", b"file")?;
+
+        for number in 0..=41 {
+            let target = if number == 41 { String::from("dir") } else { format!("l{:02}", number + 1) };
+
+            writer.add_symlink(&format!("l{number:02}"), &common::DIRECTORY, &target)?;
+        }
+
+        Ok(())
+    }));
+
+    let items: Vec<_> = iso.walk().max_depth(1).follow_symlinks(true).collect();
+    let is_folder = |path: &str| items.iter().find(|item| item.path == path).unwrap().entry.is_folder();
+
+    // Looking up the target of l01 resolves 40 links, l00 takes 41
+    assert!(is_folder("/l41"));
+    assert!(is_folder("/l01"));
+    assert!(!is_folder("/l00"));
+}
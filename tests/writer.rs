@@ -0,0 +1,109 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use iso9660_simple::{
+    helpers::get_directory_entry_by_path,
+    writer::{Metadata, Special},
+    ISODirectoryEntry, ISO9660,
+};
+
+/// High and low words of the `PN` entry.
+fn device_number(entry: &ISODirectoryEntry) -> Option<(u32, u32)> {
+    let data = &entry.system_use;
+    let mut index = 0;
+
+    while index + 4 <= data.len() {
+        let length = data[index + 2] as usize;
+
+        if &data[index..index + 2] == b"PN" {
+            let word = |offset: usize| u32::from_le_bytes(data[index + offset..index + offset + 4].try_into().unwrap());
+
+            return Some((word(4), word(12)));
+        }
+
+        index += length.max(4);
+    }
+
+    None
+}
+
+fn entry(iso: &ISO9660<Vec<u8>>, path: &str) -> ISODirectoryEntry {
+    get_directory_entry_by_path(iso, path).unwrap_or_else(|| panic!("{path} not found"))
+}
+
+#[test]
+fn image_reads_back() {
+    let long_name = "n".repeat(240);
+    let split_name = format!("{}.txt", "s".repeat(249));
+    let long_target = (0..200).map(|n| format!("component{n}")).collect::<Vec<_>>().join("/");
+
+    let metadata = Metadata {
+        mode: 0o640,
+        uid: 1000,
+        gid: 100,
+        mtime: 1_700_000_000,
+    };
+
+    let image = common::build(|writer| {
+        writer.add_file("dir/file.txt", &metadata, 5, &mut &b"hello"[..])?;
+        common::file(writer, &long_name, b"long")?;
+        common::file(writer, &format!("dir/{split_name}"), b"split")?;
+        writer.add_symlink("absolute", &metadata, "/dir/../dir/./file.txt")?;
+        writer.add_symlink("long-link", &metadata, &long_target)?;
+        writer.add_special("tty", &metadata, Special::CharDevice { major: 4096, minor: 300 })?;
+        writer.add_special("sda1", &metadata, Special::BlockDevice { major: 8, minor: 1 })?;
+        writer.add_special("fifo", &metadata, Special::Fifo)
+    });
+
+    let iso = ISO9660::from_device(image).unwrap();
+
+    assert!(iso.has_rock_ridge());
+
+    let file = entry(&iso, "/dir/file.txt");
+    let mut data = vec![0u8; file.file_size() as usize];
+
+    iso.read_file(&file, 0, &mut data).unwrap();
+
+    assert_eq!(data, b"hello");
+
+    let attributes = file.posix_attributes().unwrap();
+
+    assert_eq!((attributes.mode, attributes.uid, attributes.gid), (0o100640, 1000, 100));
+    assert_eq!(file.timestamps().modify, Some(1_700_000_000));
+
+    // Names that don't fit the record move to continuation areas, longer ones span several NM entries.
+    assert_eq!(entry(&iso, &format!("/{long_name}")).name, long_name);
+    assert_eq!(entry(&iso, &format!("/dir/{split_name}")).name, split_name);
+
+    let split = entry(&iso, &format!("/dir/{split_name}"));
+    let mut data = vec![0u8; split.file_size() as usize];
+
+    iso.read_file(&split, 0, &mut data).unwrap();
+
+    assert_eq!(data, b"split");
+
+    assert_eq!(entry(&iso, "/absolute").symlink_target().as_deref(), Some("/dir/../dir/./file.txt"));
+
+    // Longer than a sector: chained continuation areas.
+    let long_link = entry(&iso, "/long-link");
+
+    assert!(long_target.len() > 2048);
+    assert_eq!(long_link.symlink_target(), Some(long_target));
+    assert_eq!(long_link.posix_attributes().unwrap().mode, 0o120640);
+
+    assert_eq!(device_number(&entry(&iso, "/tty")), Some((4096, 300)));
+    assert_eq!(entry(&iso, "/tty").posix_attributes().unwrap().mode, 0o020640);
+    assert_eq!(device_number(&entry(&iso, "/sda1")), Some((8, 1)));
+    assert_eq!(entry(&iso, "/sda1").posix_attributes().unwrap().mode, 0o060640);
+    assert_eq!(device_number(&entry(&iso, "/fifo")), None);
+    assert_eq!(entry(&iso, "/fifo").posix_attributes().unwrap().mode, 0o010640);
+
+    let mut names: Vec<String> = iso.read_root().map(|entry| entry.name).collect();
+    names.sort();
+
+    let mut expected = vec![".", "..", "absolute", "dir", "fifo", "long-link", "sda1", "tty", &long_name];
+    expected.sort();
+
+    assert_eq!(names, expected);
+}
@@ -0,0 +1,115 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use iso9660_simple::{ISODirectoryEntry, ISO9660};
+
+const PAYLOAD: &[u8] = b"the file data comes after the extended attribute record";
+
+/// Extended attribute record with an application use area and escape sequences, in whole blocks.
+fn xar(application_use: &[u8], escape_sequences: &[u8]) -> Vec<u8> {
+    let mut block = vec![0u8; (250 + application_use.len() + escape_sequences.len()).next_multiple_of(2048)];
+
+    common::set_both_endian(&mut block, 0, &1000u16.to_le_bytes());
+    common::set_both_endian(&mut block, 4, &100u16.to_le_bytes());
+
+    // Others may not read
+    block[8..10].copy_from_slice(&(1u16 << 12).to_be_bytes());
+    block[10..26].copy_from_slice(b"2024010203040500");
+    block[27..43].copy_from_slice(b"2024010203040500");
+
+    // One hour ahead of GMT
+    block[43] = 4;
+    block[181] = escape_sequences.len() as u8;
+    common::set_both_endian(&mut block, 246, &(application_use.len() as u16).to_le_bytes());
+
+    block[250..][..application_use.len()].copy_from_slice(application_use);
+    block[250 + application_use.len()..][..escape_sequences.len()].copy_from_slice(escape_sequences);
+    block
+}
+
+/// Image with `FILE.TXT;1` whose extent starts with `xar`, the record saying so.
+fn image(xar: Vec<u8>) -> ISO9660<Vec<u8>> {
+    let blocks = xar.len() / 2048;
+    let mut data = xar;
+
+    data.extend_from_slice(PAYLOAD);
+
+    let mut image = common::plain(&[(b"FILE.TXT;1", &data)]);
+    let record = common::record_position(&image, b"FILE.TXT;1");
+
+    image[record + 1] = blocks as u8;
+    common::set_data_length(&mut image, b"FILE.TXT;1", PAYLOAD.len() as u32);
+
+    common::open(image)
+}
+
+fn file(iso: &ISO9660<Vec<u8>>) -> ISODirectoryEntry {
+    iso.read_root().find(|entry| entry.name == "FILE.TXT;1").unwrap()
+}
+
+#[test]
+fn file_data_starts_after_the_extended_attribute_record() {
+    let iso = image(xar(b"", b""));
+    let entry = file(&iso);
+    let mut data = vec![0u8; PAYLOAD.len()];
+
+    assert_eq!(entry.record.xar_length(), 1);
+    assert_eq!(entry.data_lba(), entry.lsb_position() + 1);
+
+    iso.read_file(&entry, 0, &mut data).unwrap();
+    assert_eq!(data, PAYLOAD);
+
+    // Reads past the data length don't reach into the next extent
+    assert!(iso.read_file(&entry, 1, &mut data).is_none());
+
+    let directory = tempfile::tempdir().unwrap();
+
+    iso.extract(directory.path()).owner(false).run().unwrap();
+    assert_eq!(std::fs::read(directory.path().join("FILE.TXT")).unwrap(), PAYLOAD);
+}
+
+#[test]
+fn extended_attribute_record_is_parsed() {
+    let iso = image(xar(b"application", b"\x1b%/@"));
+    let attributes = file(&iso).extended_attributes.unwrap();
+    let record = &attributes.record;
+
+    assert_eq!((record.owner_id(), record.group_id()), (1000, 100));
+    assert_eq!(record.mode(), 0o551);
+    assert_eq!(record.creation_date(), Some(1704164645));
+    assert_eq!(record.modification_date(), Some(1704164645 - 3600));
+    assert_eq!((record.expiration_date(), record.effective_date()), (None, None));
+    assert_eq!(attributes.application_use, b"application");
+    assert_eq!(attributes.escape_sequences, b"\x1b%/@");
+}
+
+#[test]
+fn application_use_area_spanning_several_blocks() {
+    // The application use area continues in the second block
+    let iso = image(xar(&[7; 3000], b""));
+    let entry = file(&iso);
+    let mut data = vec![0u8; PAYLOAD.len()];
+
+    assert_eq!(entry.extended_attributes.as_ref().unwrap().application_use, [7; 3000]);
+
+    iso.read_file(&entry, 0, &mut data).unwrap();
+    assert_eq!(data, PAYLOAD);
+}
+
+#[test]
+fn application_use_area_larger_than_the_record_is_ignored() {
+    let mut block = xar(b"", b"");
+
+    common::set_both_endian(&mut block, 246, &2000u16.to_le_bytes());
+
+    let iso = image(block);
+    let entry = file(&iso);
+    let mut data = vec![0u8; PAYLOAD.len()];
+
+    assert!(entry.extended_attributes.is_none());
+
+    // The data is still found after the record
+    iso.read_file(&entry, 0, &mut data).unwrap();
+    assert_eq!(data, PAYLOAD);
+}
@@ -0,0 +1,174 @@
+#![cfg(all(feature = "std", feature = "zisofs"))]
+
+mod common;
+
+use iso9660_simple::{helpers::get_directory_entry_by_path, ISODirectoryEntry, ISO9660};
+
+const ZISOFS_MAGIC: [u8; 8] = [0x37, 0xE4, 0x53, 0x96, 0xC9, 0xDB, 0xD6, 0x07];
+
+/// Text, a block of zeros, noise that doesn't compress and a partial last block.
+fn sample(block_size: usize) -> Vec<u8> {
+    let mut state = 0x2545_F491_u32;
+    let mut data = Vec::new();
+
+    data.extend((0..block_size).map(|n| b"zisofs sample text "[n % 19]));
+    data.extend(std::iter::repeat_n(0, block_size));
+    data.extend((0..block_size).map(|_| {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state as u8
+    }));
+    data.extend((0..1000).map(|n| n as u8));
+    data
+}
+
+/// Header, pointer table and blocks the way mkzftree lays them out: blocks of zeros are left empty.
+fn compress(data: &[u8], header: Vec<u8>, block_size: usize, pointer_size: usize, deflate: impl Fn(&[u8]) -> Vec<u8>) -> Vec<u8> {
+    let blocks = data.chunks(block_size).collect::<Vec<_>>();
+    let mut pointers = Vec::new();
+    let mut body = Vec::new();
+    let start = header.len() + (blocks.len() + 1) * pointer_size;
+
+    for block in &blocks {
+        pointers.push(start + body.len());
+
+        if block.iter().any(|&byte| byte != 0) {
+            body.extend(deflate(block));
+        }
+    }
+
+    pointers.push(start + body.len());
+
+    let mut stream = header;
+
+    for pointer in pointers {
+        stream.extend_from_slice(&(pointer as u64).to_le_bytes()[..pointer_size]);
+    }
+
+    stream.extend(body);
+    stream
+}
+
+fn zlib(block: &[u8]) -> Vec<u8> {
+    miniz_oxide::deflate::compress_to_vec_zlib(block, 6)
+}
+
+fn zisofs(data: &[u8], block_size_log2: u8) -> (Vec<u8>, Vec<u8>) {
+    let mut header = ZISOFS_MAGIC.to_vec();
+
+    header.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    header.extend_from_slice(&[4, block_size_log2, 0, 0]);
+
+    let mut zf = b"ZF\x10\x01pz\x04".to_vec();
+
+    zf.push(block_size_log2);
+    zf.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    zf.extend_from_slice(&(data.len() as u32).to_be_bytes());
+
+    (compress(data, header, 1 << block_size_log2, 4, zlib), zf)
+}
+
+/// Image holding `stream` as a file, and its entry with `zf` as the system use area.
+fn compressed_file(stream: &[u8], zf: Vec<u8>) -> (ISO9660<Vec<u8>>, ISODirectoryEntry) {
+    let iso = common::open(common::build(|writer| common::file(writer, "data", stream)));
+    let mut entry = get_directory_entry_by_path(&iso, "/data").unwrap();
+
+    entry.system_use = zf;
+
+    (iso, entry)
+}
+
+/// Reads the whole file, then ranges crossing block boundaries.
+fn assert_reads_back(iso: &ISO9660<Vec<u8>>, entry: &ISODirectoryEntry, data: &[u8]) {
+    assert_eq!(entry.file_size(), data.len() as u64);
+
+    let mut buffer = vec![0u8; data.len()];
+
+    iso.read_file(entry, 0, &mut buffer).unwrap();
+    assert!(buffer == data);
+
+    for (offset, length) in [(0, 1), (32767, 2), (40000, 30000), (65535, 32770), (data.len() - 5, 5)] {
+        let mut buffer = vec![0u8; length];
+
+        iso.read_file(entry, offset as u64, &mut buffer).unwrap();
+        assert_eq!(buffer, data[offset..offset + length], "{offset}+{length}");
+    }
+
+    assert!(iso.read_file(entry, data.len() as u64 - 1, &mut [0u8; 2]).is_none());
+}
+
+#[test]
+fn zisofs_file_reads_back() {
+    let data = sample(1 << 15);
+
+    for block_size_log2 in [15, 16, 17] {
+        let (stream, zf) = zisofs(&data, block_size_log2);
+        let (iso, entry) = compressed_file(&stream, zf);
+
+        assert_reads_back(&iso, &entry, &data);
+    }
+}
+
+#[test]
+fn block_size_below_32k_is_refused() {
+    let data = sample(1 << 14);
+    let (stream, zf) = zisofs(&data, 14);
+    let (iso, entry) = compressed_file(&stream, zf);
+
+    assert!(iso.read_file(&entry, 0, &mut [0u8; 16]).is_none());
+}
+
+#[test]
+fn block_longer_than_twice_the_block_size_is_refused() {
+    let data = sample(1 << 15);
+    let (mut stream, zf) = zisofs(&data, 15);
+
+    // Point the end of the first block far into padding that follows the data.
+    let end = stream.len() as u32 + (1 << 16);
+
+    stream[20..24].copy_from_slice(&end.to_le_bytes());
+    stream.resize(end as usize + 16, 0);
+
+    let (iso, entry) = compressed_file(&stream, zf);
+
+    assert!(iso.read_file(&entry, 0, &mut [0u8; 16]).is_none());
+}
+
+#[test]
+fn size_disagreeing_with_the_header_is_refused() {
+    let data = sample(1 << 15);
+    let (stream, mut zf) = zisofs(&data, 15);
+
+    zf[8..12].copy_from_slice(&(data.len() as u32 - 1).to_le_bytes());
+
+    let (iso, entry) = compressed_file(&stream, zf);
+
+    assert!(iso.read_file(&entry, 0, &mut [0u8; 16]).is_none());
+}
+
+#[cfg(feature = "zisofs2")]
+#[test]
+fn zisofs2_file_reads_back() {
+    const ZISOFS2_MAGIC: [u8; 8] = [0xEF, 0x22, 0x55, 0xA1, 0xBC, 0x1B, 0x95, 0xA0];
+
+    let zstd = |block: &[u8]| ruzstd::encoding::compress_to_vec(block, ruzstd::encoding::CompressionLevel::Fastest);
+    let data = sample(1 << 16);
+
+    for (compressor, deflate) in [(1u8, &zlib as &dyn Fn(&[u8]) -> Vec<u8>), (4, &zstd)] {
+        let mut header = ZISOFS2_MAGIC.to_vec();
+
+        header.extend_from_slice(&[compressor, 6, 16, 0]);
+        header.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        header.extend_from_slice(&[0; 4]);
+
+        let mut zf = b"ZF\x10\x01PZ\x06\x10".to_vec();
+
+        zf.extend_from_slice(&(data.len() as u64).to_le_bytes());
+
+        let stream = compress(&data, header, 1 << 16, 8, deflate);
+        let (iso, entry) = compressed_file(&stream, zf);
+
+        assert_reads_back(&iso, &entry, &data);
+    }
+}